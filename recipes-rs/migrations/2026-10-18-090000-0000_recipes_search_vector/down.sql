@@ -0,0 +1,5 @@
+DROP INDEX ingredients_search_vector_idx;
+ALTER TABLE ingredients DROP COLUMN search_vector;
+
+DROP INDEX recipes_search_vector_idx;
+ALTER TABLE recipes DROP COLUMN search_vector;
@@ -0,0 +1,10 @@
+ALTER TABLE recipes ADD COLUMN search_vector TSVECTOR NOT NULL GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', name), 'A') ||
+    setweight(to_tsvector('english', instructions), 'B')
+) STORED;
+CREATE INDEX recipes_search_vector_idx ON recipes USING GIN (search_vector);
+
+ALTER TABLE ingredients ADD COLUMN search_vector TSVECTOR NOT NULL GENERATED ALWAYS AS (
+    to_tsvector('english', name)
+) STORED;
+CREATE INDEX ingredients_search_vector_idx ON ingredients USING GIN (search_vector);
@@ -0,0 +1 @@
+ALTER TABLE recipe_ingredient ALTER COLUMN quantity TYPE NUMERIC(10, 3);
@@ -0,0 +1,5 @@
+DROP TABLE recipe_shares;
+ALTER TABLE recipes DROP COLUMN visibility;
+ALTER TABLE recipes DROP COLUMN owner_id;
+DROP TYPE share_access;
+DROP TYPE recipe_visibility;
@@ -0,0 +1,16 @@
+CREATE TYPE recipe_visibility AS ENUM ('private', 'shared', 'public');
+CREATE TYPE share_access AS ENUM ('read', 'edit');
+
+-- recipes created before users existed have no owner and stay public
+ALTER TABLE recipes ADD COLUMN owner_id INTEGER REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE recipes ADD COLUMN visibility recipe_visibility NOT NULL DEFAULT 'public';
+ALTER TABLE recipes ALTER COLUMN visibility SET DEFAULT 'private';
+CREATE INDEX recipes_owner_id_idx ON recipes (owner_id);
+
+CREATE TABLE recipe_shares (
+    recipe_id INTEGER NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    access share_access NOT NULL,
+    PRIMARY KEY (recipe_id, user_id)
+);
+CREATE INDEX recipe_shares_user_id_idx ON recipe_shares (user_id);
@@ -0,0 +1,10 @@
+CREATE TYPE user_role AS ENUM ('admin', 'editor', 'viewer');
+
+CREATE TABLE user_roles (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role user_role NOT NULL,
+    PRIMARY KEY (user_id, role)
+);
+
+-- existing users become viewers, admins only come from ADMIN_USERNAME and ADMIN_PASSWORD
+INSERT INTO user_roles (user_id, role) SELECT id, 'viewer' FROM users;
@@ -0,0 +1,12 @@
+CREATE TYPE meal_slot AS ENUM ('breakfast', 'lunch', 'dinner', 'snack');
+
+CREATE TABLE meal_plan_entries (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    slot meal_slot NOT NULL,
+    recipe_id INTEGER NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    portions INTEGER NOT NULL CHECK (portions > 0)
+);
+CREATE INDEX meal_plan_entries_user_id_date_idx ON meal_plan_entries (user_id, date);
+CREATE INDEX meal_plan_entries_recipe_id_idx ON meal_plan_entries (recipe_id);
@@ -0,0 +1,18 @@
+CREATE TABLE shopping_lists (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX shopping_lists_user_id_idx ON shopping_lists (user_id);
+
+-- items keep the ingredient name so lists survive renaming and merging of ingredients
+CREATE TABLE shopping_list_items (
+    id SERIAL PRIMARY KEY,
+    shopping_list_id INTEGER NOT NULL REFERENCES shopping_lists(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    quantity NUMERIC(10, 3) NOT NULL,
+    unit VARCHAR NOT NULL,
+    checked BOOLEAN NOT NULL DEFAULT FALSE
+);
+CREATE INDEX shopping_list_items_shopping_list_id_idx ON shopping_list_items (shopping_list_id);
@@ -0,0 +1,10 @@
+CREATE TABLE pantry_items (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ingredient_id INTEGER NOT NULL REFERENCES ingredients(id),
+    quantity NUMERIC(10, 3) NOT NULL,
+    unit VARCHAR NOT NULL,
+    expires_on DATE
+);
+CREATE INDEX pantry_items_user_id_idx ON pantry_items (user_id);
+CREATE INDEX pantry_items_ingredient_id_idx ON pantry_items (ingredient_id);
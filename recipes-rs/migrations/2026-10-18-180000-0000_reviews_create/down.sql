@@ -0,0 +1,4 @@
+DROP TABLE reviews;
+DROP FUNCTION recipes_update_rating;
+ALTER TABLE recipes DROP COLUMN rating_count;
+ALTER TABLE recipes DROP COLUMN rating_avg;
@@ -0,0 +1,29 @@
+CREATE TABLE reviews (
+    recipe_id INTEGER NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    text TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (recipe_id, user_id)
+);
+CREATE INDEX reviews_user_id_idx ON reviews (user_id);
+
+-- aggregates are kept on recipes so listings can filter and sort by them
+ALTER TABLE recipes ADD COLUMN rating_avg NUMERIC(3, 2);
+ALTER TABLE recipes ADD COLUMN rating_count INTEGER NOT NULL DEFAULT 0;
+CREATE INDEX recipes_rating_avg_idx ON recipes (rating_avg);
+
+CREATE FUNCTION recipes_update_rating() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE recipes
+    SET (rating_avg, rating_count) = (
+        SELECT AVG(rating), COUNT(*) FROM reviews WHERE reviews.recipe_id = recipes.id
+    )
+    WHERE id IN (OLD.recipe_id, NEW.recipe_id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reviews_update_rating AFTER INSERT OR UPDATE OR DELETE ON reviews
+FOR EACH ROW EXECUTE FUNCTION recipes_update_rating();
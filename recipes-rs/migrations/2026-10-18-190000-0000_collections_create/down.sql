@@ -0,0 +1,3 @@
+DROP TABLE collection_recipes;
+DROP TABLE collections;
+DROP TABLE favourites;
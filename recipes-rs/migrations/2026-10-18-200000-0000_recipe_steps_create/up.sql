@@ -0,0 +1,20 @@
+CREATE TABLE recipe_steps (
+    recipe_id INTEGER NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    ordinal INTEGER NOT NULL CHECK (ordinal > 0),
+    text TEXT NOT NULL,
+    duration_min INTEGER CHECK (duration_min >= 0),
+    ingredient_ids INTEGER[] NOT NULL DEFAULT '{}',
+    PRIMARY KEY (recipe_id, ordinal)
+);
+
+-- steps of existing recipes are separated by blank lines or start with a number like "1." or "2)"
+INSERT INTO recipe_steps (recipe_id, ordinal, text)
+SELECT recipes.id,
+    ROW_NUMBER() OVER (PARTITION BY recipes.id ORDER BY step.n),
+    regexp_replace(trim(E' \t\r\n' FROM step.text), E'^\\d{1,3}[.)][ \t]+', '')
+FROM recipes,
+    LATERAL regexp_split_to_table(
+        recipes.instructions,
+        E'\\r?\\n\\s*\\r?\\n|\\r?\\n(?=[ \t]*\\d{1,3}[.)][ \t])'
+    ) WITH ORDINALITY AS step(text, n)
+WHERE trim(E' \t\r\n' FROM step.text) <> '';
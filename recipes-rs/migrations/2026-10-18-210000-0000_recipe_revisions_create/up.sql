@@ -0,0 +1,55 @@
+-- the snapshot holds recipe fields, category names, ingredients and steps as the API shows them,
+-- ingredients keep their id so a restore follows renames, steps refer to them by name
+CREATE TABLE recipe_revisions (
+    id SERIAL PRIMARY KEY,
+    recipe_id INTEGER NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL CHECK (revision > 0),
+    user_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    snapshot JSONB NOT NULL,
+    UNIQUE (recipe_id, revision)
+);
+
+-- existing recipes start with their current state as the first revision
+INSERT INTO recipe_revisions (recipe_id, revision, user_id, snapshot)
+SELECT recipes.id, 1, recipes.owner_id, jsonb_build_object(
+    'name', recipes.name,
+    'instructions', recipes.instructions,
+    'cuisine', recipes.cuisine,
+    'duration_min', recipes.duration_min,
+    'preparation_needed', recipes.preparation_needed,
+    'portions', recipes.portions,
+    'difficulty', recipes.difficulty,
+    'visibility', recipes.visibility::text,
+    'categories', COALESCE((
+        SELECT jsonb_agg(recipe_category.category_name ORDER BY recipe_category.category_name)
+        FROM recipe_category
+        WHERE recipe_category.recipe_id = recipes.id
+    ), '[]'::jsonb),
+    'ingredients', COALESCE((
+        SELECT jsonb_agg(jsonb_build_object(
+            'id', ingredients.id,
+            'name', ingredients.name,
+            'part', recipe_ingredient.part,
+            'quantity', recipe_ingredient.quantity::text,
+            'unit', recipe_ingredient.unit
+        ) ORDER BY recipe_ingredient.part, ingredients.name)
+        FROM recipe_ingredient
+        JOIN ingredients ON ingredients.id = recipe_ingredient.ingredient_id
+        WHERE recipe_ingredient.recipe_id = recipes.id
+    ), '[]'::jsonb),
+    'steps', COALESCE((
+        SELECT jsonb_agg(jsonb_build_object(
+            'text', recipe_steps.text,
+            'duration_min', recipe_steps.duration_min,
+            'ingredients', COALESCE((
+                SELECT jsonb_agg(ingredients.name ORDER BY step_ingredient.n)
+                FROM unnest(recipe_steps.ingredient_ids) WITH ORDINALITY AS step_ingredient(id, n)
+                JOIN ingredients ON ingredients.id = step_ingredient.id
+            ), '[]'::jsonb)
+        ) ORDER BY recipe_steps.ordinal)
+        FROM recipe_steps
+        WHERE recipe_steps.recipe_id = recipes.id
+    ), '[]'::jsonb)
+)
+FROM recipes;
@@ -0,0 +1,12 @@
+CREATE OR REPLACE FUNCTION recipes_update_rating() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE recipes
+    SET (rating_avg, rating_count) = (
+        SELECT AVG(rating), COUNT(*) FROM reviews WHERE reviews.recipe_id = recipes.id
+    )
+    WHERE id IN (OLD.recipe_id, NEW.recipe_id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE recipes DROP COLUMN version;
@@ -0,0 +1,15 @@
+-- the version is the entity tag of a recipe, every change of the recipe increments it
+ALTER TABLE recipes ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- ratings are part of the recipe representation, so reviews change its version too
+CREATE OR REPLACE FUNCTION recipes_update_rating() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE recipes
+    SET (rating_avg, rating_count) = (
+        SELECT AVG(rating), COUNT(*) FROM reviews WHERE reviews.recipe_id = recipes.id
+    ),
+    version = version + 1
+    WHERE id IN (OLD.recipe_id, NEW.recipe_id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,2 @@
+-- the original spellings are not kept, unit codes stay valid units
+SELECT 1;
@@ -0,0 +1,28 @@
+-- recipe ingredient units were free text, store the codes of the units the service knows
+-- unknown units stay as they are, recipes keep them until the ingredient's unit is changed
+UPDATE recipe_ingredient SET unit = CASE
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('mg', 'milligram', 'milligrams') THEN 'mg'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('g', 'gr', 'gram', 'grams', 'gramme', 'grammes') THEN 'g'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('kg', 'kilo', 'kilos', 'kilogram', 'kilograms') THEN 'kg'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('oz', 'ounce', 'ounces') THEN 'oz'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('lb', 'lbs', 'pound', 'pounds') THEN 'lb'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('ml', 'milliliter', 'milliliters', 'millilitre', 'millilitres') THEN 'ml'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('cl', 'centiliter', 'centiliters', 'centilitre', 'centilitres') THEN 'cl'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('dl', 'deciliter', 'deciliters', 'decilitre', 'decilitres') THEN 'dl'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('l', 'liter', 'liters', 'litre', 'litres') THEN 'l'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('tsp', 'teaspoon', 'teaspoons', 'tsps') THEN 'tsp'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('tbsp', 'tablespoon', 'tablespoons', 'tbs', 'tbsps') THEN 'tbsp'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('fl oz', 'floz', 'fluid ounce', 'fluid ounces') THEN 'fl oz'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('cup', 'cups', 'c') THEN 'cup'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('pt', 'pint', 'pints') THEN 'pt'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('qt', 'quart', 'quarts') THEN 'qt'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('gal', 'gallon', 'gallons') THEN 'gal'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('pc', '', 'pcs', 'piece', 'pieces', 'x') THEN 'pc'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('pinch', 'pinches') THEN 'pinch'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('clove', 'cloves') THEN 'clove'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('slice', 'slices') THEN 'slice'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('bunch', 'bunches') THEN 'bunch'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('can', 'cans', 'tin', 'tins') THEN 'can'
+    WHEN lower(rtrim(btrim(unit), '.')) IN ('pkg', 'package', 'packages', 'pack', 'packs') THEN 'pkg'
+    ELSE unit
+END;
@@ -0,0 +1,2 @@
+-- the version is the entity tag of a category, every change of the category increments it
+ALTER TABLE categories ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
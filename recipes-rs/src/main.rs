@@ -5,12 +5,20 @@ use actix_web::{middleware::Logger, web, App, HttpServer};
 use diesel_async::pooled_connection::deadpool::Pool;
 use diesel_async::pooled_connection::AsyncDieselConnectionManager;
 use dotenvy::dotenv;
-use std::{env, io};
+use std::{env, io, sync::Arc};
 use structured_logger::{json::new_writer, Builder};
+use utoipa::OpenApi;
 use utoipa_actix_web::{scope, AppExt};
 use utoipa_swagger_ui::SwaggerUi;
 
-use recipes_web::controllers::{categories::categories_config, recipes::recipes_config};
+use recipes_service::users::{ensure_admin, hash_password};
+use recipes_web::controllers::{
+    api_keys::api_keys_config, categories::categories_config, collections::collections_config,
+    ingredients::ingredients_config, meal_plans::meal_plans_config, pantry::pantry_config,
+    recipes::recipes_config, shopping_lists::shopping_lists_config, units::units_config,
+    users::users_config,
+};
+use recipes_web::{auth::ApiDoc, errors};
 
 const API_PREFIX: &str = "/api/v1";
 
@@ -27,16 +35,31 @@ async fn main() -> io::Result<()> {
     let pool = Pool::builder(pool_manager)
         .build()
         .expect("Unable to build a connection pool");
+    bootstrap_admin(&pool).await;
 
     HttpServer::new(move || {
         App::new()
             .wrap(Logger::default())
             .into_utoipa_app()
+            .openapi(ApiDoc::openapi())
             .app_data(web::Data::new(pool.clone()))
+            .app_data(web::JsonConfig::default().error_handler(errors::json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(errors::query_error_handler))
+            .app_data(web::PathConfig::default().error_handler(errors::path_error_handler))
+            // raw bodies are imported web pages, which are often larger than the default
+            .app_data(web::PayloadConfig::new(5 * 1024 * 1024))
             .service(
                 scope(API_PREFIX)
                     .service(scope("/recipes").configure(recipes_config))
-                    .service(scope("/categories").configure(categories_config)),
+                    .service(scope("/categories").configure(categories_config))
+                    .service(scope("/collections").configure(collections_config))
+                    .service(scope("/ingredients").configure(ingredients_config))
+                    .service(scope("/units").configure(units_config))
+                    .service(scope("/users").configure(users_config))
+                    .service(scope("/api-keys").configure(api_keys_config))
+                    .service(scope("/meal-plans").configure(meal_plans_config))
+                    .service(scope("/pantry").configure(pantry_config))
+                    .service(scope("/shopping-lists").configure(shopping_lists_config)),
             )
             .openapi_service(|api| {
                 SwaggerUi::new("/swagger-ui/{_:.*}").url("/api/openapi.json", api)
@@ -47,3 +70,23 @@ async fn main() -> io::Result<()> {
     .run()
     .await
 }
+
+/// Roles can only be granted by an admin, so the first one is created from `ADMIN_USERNAME` and
+/// `ADMIN_PASSWORD` when both are set.
+async fn bootstrap_admin(pool: &Pool<diesel_async::AsyncPgConnection>) {
+    let (Ok(username), Ok(password)) = (env::var("ADMIN_USERNAME"), env::var("ADMIN_PASSWORD"))
+    else {
+        return;
+    };
+    let password_hash = hash_password(&password).expect("ADMIN_PASSWORD must be a valid password");
+    if let Err(error) = ensure_admin(
+        Arc::new(pool.clone()),
+        username.trim(),
+        &password,
+        &password_hash,
+    )
+    .await
+    {
+        panic!("Unable to create the configured admin: {error}");
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::{DateTime, Utc};
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::{debug, info};
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::models::api_key::{ApiKey, NewApiKey, Scope};
+use super::models::user::{Role, User};
+use super::schema::{api_keys, users};
+use super::users::{hash_token, load_roles, new_token};
+use super::utils::get_connection;
+
+/// API keys start with the prefix so they are not mistaken for session tokens
+pub const API_KEY_PREFIX: &str = "rk_";
+/// length of the key start kept to tell keys apart
+const DISPLAY_PREFIX_LENGTH: usize = 10;
+
+/// Create a key for the user, returns the key itself. Only its hash is stored.
+pub async fn create_api_key(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    name: &str,
+    scopes: &[Scope],
+    expires_at: &Option<DateTime<Utc>>,
+) -> Result<(String, ApiKey), ServiceError> {
+    info!(user_id, name; "Creating API key");
+    if name.trim().is_empty() {
+        return Err(ServiceError::InvalidValue {
+            field: "name",
+            detail: "must not be empty".to_string(),
+        });
+    }
+    if scopes.is_empty() {
+        return Err(ServiceError::InvalidValue {
+            field: "scopes",
+            detail: "at least one scope is required".to_string(),
+        });
+    }
+    if expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        return Err(ServiceError::InvalidValue {
+            field: "expires_at",
+            detail: "must be in the future".to_string(),
+        });
+    }
+
+    let mut unique_scopes: Vec<Scope> = vec![];
+    for scope in scopes {
+        if !unique_scopes.contains(scope) {
+            unique_scopes.push(*scope);
+        }
+    }
+    let key = format!("{API_KEY_PREFIX}{}", new_token());
+    let key_hash = hash_token(&key);
+
+    let mut connection = get_connection(db_pool).await?;
+    let api_key = diesel::insert_into(api_keys::table)
+        .values(NewApiKey {
+            user_id: *user_id,
+            name: name.trim(),
+            key_hash: &key_hash,
+            prefix: &key[..DISPLAY_PREFIX_LENGTH],
+            scopes: &unique_scopes,
+            expires_at: *expires_at,
+        })
+        .returning(ApiKey::as_returning())
+        .get_result(&mut connection)
+        .await?;
+
+    return Ok((key, api_key));
+}
+
+pub async fn list_api_keys(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+) -> Result<Vec<ApiKey>, ServiceError> {
+    info!(user_id; "Listing API keys");
+    let mut connection = get_connection(db_pool).await?;
+    return Ok(api_keys::table
+        .filter(api_keys::user_id.eq(user_id))
+        .select(ApiKey::as_select())
+        .order_by(api_keys::id)
+        .load(&mut connection)
+        .await?);
+}
+
+/// revoke one of the user's keys, keys of other users are not found
+pub async fn revoke_api_key(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    api_key_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(user_id, api_key_id; "Revoking API key");
+    let mut connection = get_connection(db_pool).await?;
+    let deleted = diesel::delete(
+        api_keys::table
+            .find(api_key_id)
+            .filter(api_keys::user_id.eq(user_id)),
+    )
+    .execute(&mut connection)
+    .await?;
+    if deleted == 0 {
+        return Err(ServiceError::DbDiesel(diesel::result::Error::NotFound));
+    }
+
+    return Ok(());
+}
+
+/// Find the owner of a valid key with their roles and the key scopes, records the key use
+pub async fn authenticate_api_key(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    key: &str,
+) -> Result<(User, Vec<Role>, Vec<Scope>), ServiceError> {
+    let mut connection = get_connection(db_pool).await?;
+    let api_key = diesel::update(
+        api_keys::table
+            .filter(api_keys::key_hash.eq(hash_token(key)))
+            .filter(
+                api_keys::expires_at
+                    .is_null()
+                    .or(api_keys::expires_at.gt(now)),
+            ),
+    )
+    .set(api_keys::last_used_at.eq(now))
+    .returning(ApiKey::as_returning())
+    .get_result(&mut connection)
+    .await
+    .optional()?
+    .ok_or(ServiceError::Unauthorized {
+        detail: "Invalid or expired API key",
+    })?;
+    debug!(api_key_id = api_key.id; "Authenticated API key");
+
+    let user = users::table
+        .find(api_key.user_id)
+        .select(User::as_select())
+        .first(&mut connection)
+        .await?;
+    let roles = load_roles(&mut connection, &user.id).await?;
+
+    return Ok((user, roles, api_key.scopes));
+}
@@ -6,17 +6,27 @@ use std::sync::Arc;
 use super::errors::ServiceError;
 use super::models::category::{Category, ChangeCategory, NewCategory, RecipeCategory};
 use super::schema::{categories, recipe_category};
-use super::utils::get_connection;
+use super::utils::{get_connection, Page};
 
 pub async fn list_categories(
     db_pool: Arc<Pool<AsyncPgConnection>>,
-) -> Result<Vec<Category>, ServiceError> {
+    page: &Page,
+) -> Result<(Vec<Category>, i64), ServiceError> {
     info!("Listing categories");
     let mut connection = get_connection(db_pool).await?;
-    return Ok(categories::table
+    let total: i64 = categories::table
+        .count()
+        .get_result(&mut connection)
+        .await?;
+    let all_categories = categories::table
         .select(Category::as_select())
+        .order_by(categories::name)
+        .offset(page.offset())
+        .limit(page.limit)
         .load(&mut connection)
-        .await?);
+        .await?;
+
+    return Ok((all_categories, total));
 }
 
 pub async fn get_category(
@@ -41,41 +51,59 @@ pub async fn create_category(
         .await?);
 }
 
+/// `None` expected versions accept any current version
 pub async fn update_category(
     db_pool: Arc<Pool<AsyncPgConnection>>,
     name: String,
+    expected_versions: &Option<Vec<i32>>,
     change_category: &ChangeCategory,
 ) -> Result<Category, ServiceError> {
-    info!(category = name; "Changing category");
+    info!(category = name, expected_versions:serde; "Changing category");
     let mut connection = get_connection(db_pool).await?;
-    return Ok(diesel::update(categories::table.find(name))
-        .set(change_category)
-        .returning(Category::as_select())
-        .get_result(&mut connection)
-        .await?);
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                lock_category_version(connection, &name, expected_versions).await?;
+
+                return Ok(diesel::update(categories::table.find(&name))
+                    .set((
+                        change_category,
+                        categories::version.eq(categories::version + 1),
+                    ))
+                    .returning(Category::as_select())
+                    .get_result(connection)
+                    .await?);
+            })
+        })
+        .await;
 }
 
 pub async fn delete_category(
     db_pool: Arc<Pool<AsyncPgConnection>>,
     name: String,
+    expected_versions: &Option<Vec<i32>>,
 ) -> Result<(), ServiceError> {
-    info!(category = name; "Deleting category");
+    info!(category = name, expected_versions:serde; "Deleting category");
     let mut connection = get_connection(db_pool).await?;
-    // TODO: return custom error upon rollback
     connection
         .build_transaction()
         .run(|mut connection| {
             Box::pin(async move {
-                let recipe_assoc = get_category_recipes(&mut connection, &name).await?;
+                lock_category_version(connection, &name, expected_versions).await?;
+                let recipe_assoc = get_category_recipes(connection, &name).await?;
                 if !recipe_assoc.is_empty() {
-                    return Err(diesel::result::Error::RollbackTransaction);
+                    return Err(ServiceError::InUse {
+                        resource: "category",
+                        used_by: "recipes",
+                    });
                 }
                 diesel::delete(categories::table.find(name))
                     .execute(&mut connection)
                     .await?;
                 debug!("Removed category");
 
-                return Ok(());
+                return Ok::<(), ServiceError>(());
             })
         })
         .await?;
@@ -83,6 +111,31 @@ pub async fn delete_category(
     return Ok(());
 }
 
+/// Lock the category row until the end of the transaction and check its version is one of
+/// the expected versions.
+async fn lock_category_version(
+    connection: &mut AsyncPgConnection,
+    name: &str,
+    expected_versions: &Option<Vec<i32>>,
+) -> Result<(), ServiceError> {
+    let version: i32 = categories::table
+        .find(name)
+        .select(categories::version)
+        .for_update()
+        .first(connection)
+        .await?;
+    if let Some(expected_versions) = expected_versions {
+        if !expected_versions.contains(&version) {
+            debug!(version, expected_versions:serde; "Category version does not match");
+            return Err(ServiceError::PreconditionFailed {
+                resource: "category",
+            });
+        }
+    }
+
+    return Ok(());
+}
+
 async fn get_category_recipes(
     connection: &mut AsyncPgConnection,
     name: &str,
@@ -92,3 +145,64 @@ async fn get_category_recipes(
         .load(connection)
         .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes_service::test_utils::test_pool;
+
+    #[actix_web::test]
+    async fn changes_only_the_expected_category_version() {
+        let Some(db_pool) = test_pool().await else {
+            return;
+        };
+        let category = create_category(
+            db_pool.clone(),
+            &NewCategory {
+                name: "soups".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(category.version, 1);
+
+        let rename = ChangeCategory {
+            name: Some("stews".to_string()),
+        };
+        let changed = update_category(
+            db_pool.clone(),
+            "soups".to_string(),
+            &Some(vec![1]),
+            &rename,
+        )
+        .await
+        .unwrap();
+        assert_eq!((changed.name.as_str(), changed.version), ("stews", 2));
+
+        // a client that read the category before the rename
+        let stale = update_category(
+            db_pool.clone(),
+            "stews".to_string(),
+            &Some(vec![1]),
+            &rename,
+        )
+        .await;
+        assert!(matches!(
+            stale,
+            Err(ServiceError::PreconditionFailed {
+                resource: "category"
+            })
+        ));
+        let stale = delete_category(db_pool.clone(), "stews".to_string(), &Some(vec![1])).await;
+        assert!(matches!(
+            stale,
+            Err(ServiceError::PreconditionFailed {
+                resource: "category"
+            })
+        ));
+
+        delete_category(db_pool.clone(), "stews".to_string(), &Some(vec![2]))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,345 @@
+use diesel::prelude::*;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::{debug, info};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::models::collection::{Collection, CollectionRecipe, NewCollection};
+use super::models::recipe::Recipe;
+use super::recipes::{filter_recipes, get_accessible_recipe, RecipeAccess, RecipeFilter};
+use super::schema::{collection_recipes, collections, recipes};
+use super::users::new_token;
+use super::utils::get_connection;
+
+/// collections of the user with their recipes in collection order
+pub async fn list_collections(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+) -> Result<Vec<(Collection, Vec<Recipe>)>, ServiceError> {
+    info!(user_id; "Listing collections");
+    let mut connection = get_connection(db_pool).await?;
+    let all_collections = collections::table
+        .filter(collections::user_id.eq(user_id))
+        .select(Collection::as_select())
+        .order_by(collections::name)
+        .load(&mut connection)
+        .await?;
+
+    return load_recipes(&mut connection, &Some(*user_id), all_collections).await;
+}
+
+pub async fn get_collection(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    collection_id: &i32,
+) -> Result<(Collection, Vec<Recipe>), ServiceError> {
+    info!(user_id, collection_id; "Getting collection");
+    let mut connection = get_connection(db_pool).await?;
+    let collection = get_user_collection(&mut connection, user_id, collection_id).await?;
+
+    return load_collection_recipes(&mut connection, &Some(*user_id), collection).await;
+}
+
+/// Collection behind a public link. Only recipes the caller may see anyway are listed, the link
+/// does not grant access to private recipes.
+pub async fn get_shared_collection(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &Option<i32>,
+    share_token: &str,
+) -> Result<(Collection, Vec<Recipe>), ServiceError> {
+    info!(user_id:serde; "Getting shared collection");
+    let mut connection = get_connection(db_pool).await?;
+    let collection = collections::table
+        .filter(collections::share_token.eq(share_token))
+        .select(Collection::as_select())
+        .first(&mut connection)
+        .await?;
+
+    return load_collection_recipes(&mut connection, user_id, collection).await;
+}
+
+pub async fn create_collection(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    name: &str,
+) -> Result<(Collection, Vec<Recipe>), ServiceError> {
+    info!(user_id, name; "Creating collection");
+    let name = check_name(name)?;
+    let mut connection = get_connection(db_pool).await?;
+    let collection = diesel::insert_into(collections::table)
+        .values(NewCollection {
+            user_id: *user_id,
+            name,
+        })
+        .returning(Collection::as_returning())
+        .get_result(&mut connection)
+        .await?;
+
+    return Ok((collection, vec![]));
+}
+
+pub async fn rename_collection(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    collection_id: &i32,
+    name: &str,
+) -> Result<(Collection, Vec<Recipe>), ServiceError> {
+    info!(user_id, collection_id, name; "Renaming collection");
+    let name = check_name(name)?;
+    let mut connection = get_connection(db_pool).await?;
+    let collection = diesel::update(
+        collections::table
+            .find(collection_id)
+            .filter(collections::user_id.eq(user_id)),
+    )
+    .set(collections::name.eq(name))
+    .returning(Collection::as_returning())
+    .get_result(&mut connection)
+    .await?;
+
+    return load_collection_recipes(&mut connection, &Some(*user_id), collection).await;
+}
+
+pub async fn delete_collection(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    collection_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(user_id, collection_id; "Deleting collection");
+    let mut connection = get_connection(db_pool).await?;
+    let deleted = diesel::delete(
+        collections::table
+            .find(collection_id)
+            .filter(collections::user_id.eq(user_id)),
+    )
+    .execute(&mut connection)
+    .await?;
+    if deleted == 0 {
+        return Err(ServiceError::DbDiesel(diesel::result::Error::NotFound));
+    }
+
+    return Ok(());
+}
+
+/// append a recipe to the collection, a recipe already in it keeps its position
+pub async fn add_collection_recipe(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    collection_id: &i32,
+    recipe_id: &i32,
+) -> Result<(Collection, Vec<Recipe>), ServiceError> {
+    info!(user_id, collection_id, recipe_id; "Adding recipe to collection");
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let collection = get_user_collection(connection, user_id, collection_id).await?;
+                get_accessible_recipe(connection, &Some(*user_id), recipe_id, RecipeAccess::Read)
+                    .await?;
+
+                let last_position: Option<i32> = CollectionRecipe::belonging_to(&collection)
+                    .select(diesel::dsl::max(collection_recipes::position))
+                    .get_result(connection)
+                    .await?;
+                diesel::insert_into(collection_recipes::table)
+                    .values(CollectionRecipe {
+                        collection_id: collection.id,
+                        recipe_id: *recipe_id,
+                        position: last_position.map_or(0, |position| position + 1),
+                    })
+                    .on_conflict_do_nothing()
+                    .execute(connection)
+                    .await?;
+
+                return load_collection_recipes(connection, &Some(*user_id), collection).await;
+            })
+        })
+        .await;
+}
+
+pub async fn remove_collection_recipe(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    collection_id: &i32,
+    recipe_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(user_id, collection_id, recipe_id; "Removing recipe from collection");
+    let mut connection = get_connection(db_pool).await?;
+    let collection = get_user_collection(&mut connection, user_id, collection_id).await?;
+    let deleted = diesel::delete(
+        CollectionRecipe::belonging_to(&collection)
+            .filter(collection_recipes::recipe_id.eq(recipe_id)),
+    )
+    .execute(&mut connection)
+    .await?;
+    if deleted == 0 {
+        return Err(ServiceError::DbDiesel(diesel::result::Error::NotFound));
+    }
+
+    return Ok(());
+}
+
+/// Move the given recipes to the start of the collection in the given order. The other recipes
+/// keep their order after them, so recipes hidden from the user need not be listed.
+pub async fn order_collection_recipes(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    collection_id: &i32,
+    recipe_ids: &[i32],
+) -> Result<(Collection, Vec<Recipe>), ServiceError> {
+    info!(user_id, collection_id, recipe_ids:serde; "Ordering collection recipes");
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let collection = get_user_collection(connection, user_id, collection_id).await?;
+                let current: Vec<i32> = CollectionRecipe::belonging_to(&collection)
+                    .select(collection_recipes::recipe_id)
+                    .order_by(collection_recipes::position)
+                    .load(connection)
+                    .await?;
+
+                let mut seen = HashSet::new();
+                for recipe_id in recipe_ids {
+                    if !current.contains(recipe_id) || !seen.insert(*recipe_id) {
+                        return Err(ServiceError::InvalidValue {
+                            field: "recipe_ids",
+                            detail: format!(
+                                "Recipe '{recipe_id}' is not in the collection or listed twice"
+                            ),
+                        });
+                    }
+                }
+                let ordered = recipe_ids
+                    .iter()
+                    .chain(current.iter().filter(|id| !seen.contains(id)));
+                for (position, recipe_id) in ordered.enumerate() {
+                    diesel::update(collection_recipes::table.find((collection.id, recipe_id)))
+                        .set(collection_recipes::position.eq(position as i32))
+                        .execute(connection)
+                        .await?;
+                }
+                debug!(recipes = current.len(); "Reordered collection recipes");
+
+                return load_collection_recipes(connection, &Some(*user_id), collection).await;
+            })
+        })
+        .await;
+}
+
+/// create a new public link to the collection, replacing the previous one
+pub async fn share_collection(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    collection_id: &i32,
+) -> Result<(Collection, Vec<Recipe>), ServiceError> {
+    info!(user_id, collection_id; "Sharing collection");
+    let mut connection = get_connection(db_pool).await?;
+    let collection = diesel::update(
+        collections::table
+            .find(collection_id)
+            .filter(collections::user_id.eq(user_id)),
+    )
+    .set(collections::share_token.eq(new_token()))
+    .returning(Collection::as_returning())
+    .get_result(&mut connection)
+    .await?;
+
+    return load_collection_recipes(&mut connection, &Some(*user_id), collection).await;
+}
+
+/// disable the public link to the collection
+pub async fn unshare_collection(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    collection_id: &i32,
+) -> Result<(Collection, Vec<Recipe>), ServiceError> {
+    info!(user_id, collection_id; "Unsharing collection");
+    let mut connection = get_connection(db_pool).await?;
+    let collection = diesel::update(
+        collections::table
+            .find(collection_id)
+            .filter(collections::user_id.eq(user_id)),
+    )
+    .set(collections::share_token.eq(None::<String>))
+    .returning(Collection::as_returning())
+    .get_result(&mut connection)
+    .await?;
+
+    return load_collection_recipes(&mut connection, &Some(*user_id), collection).await;
+}
+
+async fn get_user_collection(
+    connection: &mut AsyncPgConnection,
+    user_id: &i32,
+    collection_id: &i32,
+) -> Result<Collection, diesel::result::Error> {
+    return collections::table
+        .find(collection_id)
+        .filter(collections::user_id.eq(user_id))
+        .select(Collection::as_select())
+        .first(connection)
+        .await;
+}
+
+async fn load_collection_recipes(
+    connection: &mut AsyncPgConnection,
+    user_id: &Option<i32>,
+    collection: Collection,
+) -> Result<(Collection, Vec<Recipe>), ServiceError> {
+    let mut loaded = load_recipes(connection, user_id, vec![collection]).await?;
+    return Ok(loaded.remove(0));
+}
+
+/// recipes of the collections visible to the user, in collection order
+async fn load_recipes(
+    connection: &mut AsyncPgConnection,
+    user_id: &Option<i32>,
+    all_collections: Vec<Collection>,
+) -> Result<Vec<(Collection, Vec<Recipe>)>, ServiceError> {
+    let recipe_assoc = CollectionRecipe::belonging_to(&all_collections)
+        .select(CollectionRecipe::as_select())
+        .order_by(collection_recipes::position)
+        .load(connection)
+        .await?;
+    let recipe_ids: Vec<i32> = recipe_assoc.iter().map(|assoc| assoc.recipe_id).collect();
+    let filter = RecipeFilter::default();
+    let visible_recipes: Vec<Recipe> = filter_recipes(user_id, &filter)
+        .filter(recipes::id.eq_any(recipe_ids))
+        .select(Recipe::as_select())
+        .load(connection)
+        .await?;
+
+    return Ok(recipe_assoc
+        .grouped_by(&all_collections)
+        .into_iter()
+        .zip(all_collections)
+        .map(|(recipe_assoc, collection)| {
+            let collection_recipes = recipe_assoc
+                .iter()
+                .filter_map(|assoc| {
+                    visible_recipes
+                        .iter()
+                        .position(|recipe| recipe.id == assoc.recipe_id)
+                })
+                .map(|index| visible_recipes[index].clone())
+                .collect();
+            (collection, collection_recipes)
+        })
+        .collect());
+}
+
+fn check_name(name: &str) -> Result<&str, ServiceError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServiceError::InvalidValue {
+            field: "name",
+            detail: "must not be empty".to_string(),
+        });
+    }
+
+    return Ok(name);
+}
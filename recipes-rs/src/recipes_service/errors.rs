@@ -8,4 +8,19 @@ pub enum ServiceError {
     DbPool(#[from] PoolError),
     #[error("Diesel error : {0}")]
     DbDiesel(#[from] DieselError),
+    #[error("The {resource} is still used by {used_by}")]
+    InUse {
+        resource: &'static str,
+        used_by: &'static str,
+    },
+    #[error("Invalid {field}: {detail}")]
+    InvalidValue { field: &'static str, detail: String },
+    #[error("Unauthorized: {detail}")]
+    Unauthorized { detail: &'static str },
+    #[error("Forbidden: {detail}")]
+    Forbidden { detail: &'static str },
+    #[error("Conflict: {detail}")]
+    Conflict { code: &'static str, detail: String },
+    #[error("The {resource} was changed since it was read")]
+    PreconditionFailed { resource: &'static str },
 }
@@ -0,0 +1,54 @@
+use diesel::prelude::*;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::info;
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::models::collection::NewFavourite;
+use super::recipes::{get_accessible_recipe, RecipeAccess};
+use super::schema::favourites;
+use super::utils::get_connection;
+
+/// star a recipe, starring it again does nothing
+pub async fn add_favourite(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(user_id, recipe_id; "Adding favourite");
+    let mut connection = get_connection(db_pool).await?;
+    get_accessible_recipe(
+        &mut connection,
+        &Some(*user_id),
+        recipe_id,
+        RecipeAccess::Read,
+    )
+    .await?;
+    diesel::insert_into(favourites::table)
+        .values(NewFavourite {
+            user_id: *user_id,
+            recipe_id: *recipe_id,
+        })
+        .on_conflict_do_nothing()
+        .execute(&mut connection)
+        .await?;
+
+    return Ok(());
+}
+
+pub async fn remove_favourite(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(user_id, recipe_id; "Removing favourite");
+    let mut connection = get_connection(db_pool).await?;
+    let deleted = diesel::delete(favourites::table.find((user_id, recipe_id)))
+        .execute(&mut connection)
+        .await?;
+    if deleted == 0 {
+        return Err(ServiceError::DbDiesel(diesel::result::Error::NotFound));
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,751 @@
+use bigdecimal::BigDecimal;
+use diesel::prelude::*;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::{debug, info};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::models::category::Category;
+use super::models::ingredient::{NewRecipeIngredient, RecipePart};
+use super::models::recipe::{NewRecipe, Recipe, Visibility};
+use super::models::recipe_step::{NewRecipeStep, RecipeStep};
+use super::quantity::parse_quantity;
+use super::recipes::create_recipe;
+use super::schema::categories;
+use super::steps::{required_instructions_and_steps, split_instructions};
+use super::units::find_unit;
+use super::utils::get_connection;
+
+/// difficulty of imported recipes, schema.org has no such property
+const DEFAULT_DIFFICULTY: i32 = 3;
+/// limits of recipes created through the API, longer texts are shortened and larger numbers
+/// lowered with a warning
+const MAX_NAME_LENGTH: usize = 200;
+const MAX_CUISINE_LENGTH: usize = 100;
+const MAX_INGREDIENT_NAME_LENGTH: usize = 100;
+const MAX_PORTIONS: i32 = 1000;
+/// longest duration taken from a document, a year
+const MAX_DURATION_MIN: i32 = 366 * 24 * 60;
+
+/// document holding a schema.org Recipe
+pub enum ImportSource<'a> {
+    JsonLd(&'a str),
+    /// web page with the recipe in a `<script type="application/ld+json">` element
+    Html(&'a str),
+}
+
+/// ingredient line split into its parts, e.g. "1 1/2 cups flour, sifted"
+#[cfg_attr(test, derive(Debug, PartialEq))]
+struct IngredientLine {
+    quantity: Option<BigDecimal>,
+    /// the quantity was a range like "2-3", only its lower bound is kept
+    range: bool,
+    /// unit code, pieces when the line has no unit
+    unit: &'static str,
+    name: String,
+    /// preparation or other remarks after the name, e.g. "sifted"
+    note: Option<String>,
+}
+
+/// Create a private recipe of the user from a schema.org Recipe. Properties which could not be
+/// mapped to the recipe are reported as warnings instead of failing the import.
+pub async fn import_recipe(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    owner_id: &i32,
+    source: &ImportSource<'_>,
+) -> Result<
+    (
+        (Recipe, Vec<Category>, Vec<RecipePart>, Vec<RecipeStep>),
+        Vec<String>,
+    ),
+    ServiceError,
+> {
+    info!(owner_id; "Importing recipe");
+    let mut warnings = vec![];
+    let documents = match source {
+        ImportSource::JsonLd(text) => {
+            vec![
+                serde_json::from_str(text).map_err(|e| ServiceError::InvalidValue {
+                    field: "document",
+                    detail: format!("Invalid JSON-LD: {e}"),
+                })?,
+            ]
+        }
+        ImportSource::Html(html) => {
+            let mut documents = vec![];
+            for script in json_ld_scripts(html) {
+                match serde_json::from_str(script) {
+                    Ok(document) => documents.push(document),
+                    Err(e) => warnings.push(format!("Skipped invalid JSON-LD script: {e}")),
+                }
+            }
+            documents
+        }
+    };
+    let recipe = documents
+        .iter()
+        .find_map(find_recipe)
+        .ok_or(ServiceError::InvalidValue {
+            field: "document",
+            detail: "No schema.org Recipe found".to_string(),
+        })?;
+    debug!(properties:serde = recipe.keys().collect::<Vec<_>>(); "Found schema.org Recipe");
+
+    let mut name = recipe
+        .get("name")
+        .and_then(first_text)
+        .ok_or(ServiceError::InvalidValue {
+            field: "name",
+            detail: "The recipe has no name".to_string(),
+        })?;
+    if let Some(shortened) = shorten(&name, MAX_NAME_LENGTH) {
+        warnings.push(format!(
+            "Shortened the name to {MAX_NAME_LENGTH} characters"
+        ));
+        name = shortened;
+    }
+
+    let cuisines = recipe.get("recipeCuisine").map(texts).unwrap_or_default();
+    if cuisines.len() > 1 {
+        warnings.push(format!(
+            "Kept the first of the cuisines {}",
+            cuisines.join(", ")
+        ));
+    }
+    let mut cuisine = cuisines.into_iter().next().unwrap_or_default();
+    if let Some(shortened) = shorten(&cuisine, MAX_CUISINE_LENGTH) {
+        warnings.push(format!(
+            "Shortened the cuisine to {MAX_CUISINE_LENGTH} characters"
+        ));
+        cuisine = shortened;
+    }
+
+    let duration_min = match recipe_duration(recipe) {
+        Ok(Some(duration_min)) => duration_min,
+        Ok(None) => {
+            warnings.push("No totalTime, the duration is set to 0".to_string());
+            0
+        }
+        Err(duration) => {
+            warnings.push(format!(
+                "Unknown duration '{duration}', the duration is set to 0"
+            ));
+            0
+        }
+    };
+
+    let portions = match recipe.get("recipeYield") {
+        Some(recipe_yield) => match portions(recipe_yield) {
+            Some(portions) if portions > MAX_PORTIONS => {
+                warnings.push(format!(
+                    "recipeYield '{portions}' is too large, portions are set to {MAX_PORTIONS}"
+                ));
+                MAX_PORTIONS
+            }
+            Some(portions) => portions,
+            None => {
+                warnings.push(format!(
+                    "Unknown recipeYield '{}', portions are set to 1",
+                    texts(recipe_yield).join(", ")
+                ));
+                1
+            }
+        },
+        None => {
+            warnings.push("No recipeYield, portions are set to 1".to_string());
+            1
+        }
+    };
+    warnings.push(format!(
+        "schema.org has no difficulty, it is set to {DEFAULT_DIFFICULTY}"
+    ));
+
+    let lines = recipe
+        .get("recipeIngredient")
+        .or(recipe.get("ingredients"))
+        .map(texts)
+        .unwrap_or_default();
+    let mut ingredient_lines: Vec<IngredientLine> = vec![];
+    for line in &lines {
+        let Some(ingredient) = parse_ingredient_line(line) else {
+            warnings.push(format!("Skipped ingredient '{line}' without a name"));
+            continue;
+        };
+        if ingredient.name.chars().count() > MAX_INGREDIENT_NAME_LENGTH {
+            warnings.push(format!(
+                "Skipped ingredient '{line}' named with over {MAX_INGREDIENT_NAME_LENGTH} characters"
+            ));
+            continue;
+        }
+        // an ingredient is used once per recipe
+        if ingredient_lines
+            .iter()
+            .any(|other| other.name == ingredient.name)
+        {
+            warnings.push(format!("Skipped repeated ingredient '{line}'"));
+            continue;
+        }
+        if ingredient.range {
+            warnings.push(format!("Kept the lower quantity of '{line}'"));
+        }
+        if ingredient.quantity.is_none() {
+            warnings.push(format!("No quantity in '{line}', 1 is assumed"));
+        }
+        if let Some(note) = &ingredient.note {
+            warnings.push(format!("Dropped '{note}' from ingredient '{line}'"));
+        }
+        ingredient_lines.push(ingredient);
+    }
+    let quantities: Vec<BigDecimal> = ingredient_lines
+        .iter()
+        .map(|ingredient| ingredient.quantity.clone().unwrap_or(BigDecimal::from(1)))
+        .collect();
+    let rec_ings: Vec<NewRecipeIngredient> = ingredient_lines
+        .iter()
+        .zip(&quantities)
+        .map(|(ingredient, quantity)| NewRecipeIngredient {
+            name: &ingredient.name,
+            part: 0,
+            quantity,
+            unit: ingredient.unit,
+        })
+        .collect();
+
+    let steps: Vec<NewRecipeStep> = recipe
+        .get("recipeInstructions")
+        .map(instruction_texts)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|text| NewRecipeStep {
+            text,
+            duration_min: None,
+            ingredient_ids: vec![],
+        })
+        .collect();
+    let (instructions, steps) =
+        required_instructions_and_steps(None, (!steps.is_empty()).then_some(steps))?;
+
+    let mut connection = get_connection(db_pool.clone()).await?;
+    let known_categories: Vec<Category> = categories::table
+        .select(Category::as_select())
+        .load(&mut connection)
+        .await?;
+    drop(connection);
+    let mut category_names: Vec<String> = vec![];
+    for category in recipe.get("recipeCategory").map(texts).unwrap_or_default() {
+        // sites often list categories comma separated in one text
+        for category in category.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            match known_categories
+                .iter()
+                .find(|known| known.name.to_lowercase() == category.to_lowercase())
+            {
+                Some(known) if !category_names.contains(&known.name) => {
+                    category_names.push(known.name.clone())
+                }
+                Some(_) => (),
+                None => warnings.push(format!("Unknown category '{category}'")),
+            }
+        }
+    }
+
+    let new_recipe = NewRecipe {
+        name,
+        instructions,
+        cuisine,
+        duration_min,
+        preparation_needed: false,
+        portions,
+        difficulty: DEFAULT_DIFFICULTY,
+        owner_id: Some(*owner_id),
+        visibility: Visibility::Private,
+    };
+    let recipe = create_recipe(db_pool, &new_recipe, &category_names, &rec_ings, &steps).await?;
+    info!(recipe_id = recipe.0.id, warnings:serde; "Imported recipe");
+
+    return Ok((recipe, warnings));
+}
+
+/// contents of the `<script type="application/ld+json">` elements of a page
+fn json_ld_scripts(html: &str) -> Vec<&str> {
+    // ASCII lowercasing keeps the byte offsets of the original
+    let lower = html.to_ascii_lowercase();
+    let mut scripts = vec![];
+    let mut position = 0;
+    while let Some(start) = lower[position..].find("<script").map(|i| i + position) {
+        let Some(tag_end) = lower[start..].find('>').map(|i| i + start + 1) else {
+            break;
+        };
+        let Some(end) = lower[tag_end..].find("</script").map(|i| i + tag_end) else {
+            break;
+        };
+        if lower[start..tag_end].contains("application/ld+json") {
+            scripts.push(&html[tag_end..end]);
+        }
+        position = end;
+    }
+
+    return scripts;
+}
+
+/// first object typed as schema.org Recipe, also within arrays and `@graph`
+fn find_recipe(value: &Value) -> Option<&Map<String, Value>> {
+    return match value {
+        Value::Array(values) => values.iter().find_map(find_recipe),
+        Value::Object(object) => {
+            let is_recipe = object
+                .get("@type")
+                .map(texts)
+                .unwrap_or_default()
+                .iter()
+                .any(|type_name| {
+                    type_name == "Recipe"
+                        || type_name.ends_with("schema.org/Recipe")
+                        || type_name == "schema:Recipe"
+                });
+            if is_recipe {
+                Some(object)
+            } else {
+                object.get("@graph").and_then(find_recipe)
+            }
+        }
+        _ => None,
+    };
+}
+
+/// cleaned texts of a text, number or an array of them, empty texts are left out
+fn texts(value: &Value) -> Vec<String> {
+    let text = match value {
+        Value::Array(values) => return values.iter().flat_map(texts).collect(),
+        Value::String(text) => clean_text(text),
+        Value::Number(number) => number.to_string(),
+        _ => return vec![],
+    };
+    if text.is_empty() {
+        return vec![];
+    }
+
+    return vec![text];
+}
+
+fn first_text(value: &Value) -> Option<String> {
+    return texts(value).into_iter().next();
+}
+
+/// text without HTML tags and entities and with whitespace collapsed
+fn clean_text(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut in_tag = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // a tag starts with a name or a slash, unlike "a < b"
+            '<' if chars
+                .peek()
+                .is_some_and(|next| next.is_ascii_alphabetic() || *next == '/') =>
+            {
+                in_tag = true
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                plain.push(' ');
+            }
+            _ if !in_tag => plain.push(c),
+            _ => (),
+        }
+    }
+
+    return decode_entities(&plain)
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+}
+
+/// decode named entities common in recipes and numeric ones, unknown entities are kept
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "frac12" => Some('½'),
+            "frac14" => Some('¼'),
+            "frac34" => Some('¾'),
+            "deg" => Some('°'),
+            _ => {
+                let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    return decoded;
+}
+
+/// Minutes of `totalTime` or else of `prepTime` and `cookTime`, at most [`MAX_DURATION_MIN`].
+/// The unparsable duration is returned as error.
+fn recipe_duration(recipe: &Map<String, Value>) -> Result<Option<i32>, String> {
+    let minutes = |property: &str| -> Result<Option<i32>, String> {
+        return match recipe.get(property).and_then(first_text) {
+            Some(duration) => parse_duration(&duration).map(Some).ok_or(duration),
+            None => Ok(None),
+        };
+    };
+
+    if let Some(total) = minutes("totalTime")? {
+        return Ok(Some(total));
+    }
+    return match (minutes("prepTime")?, minutes("cookTime")?) {
+        (None, None) => Ok(None),
+        (prep, cook) => Ok(Some(
+            prep.unwrap_or(0)
+                .saturating_add(cook.unwrap_or(0))
+                .min(MAX_DURATION_MIN),
+        )),
+    };
+}
+
+/// Minutes of an ISO 8601 duration like "PT1H30M" or "P1DT2H", rounded. Years and months have
+/// no fixed length and are not supported, durations over [`MAX_DURATION_MIN`] neither.
+fn parse_duration(duration: &str) -> Option<i32> {
+    let rest = duration.trim().strip_prefix(['P', 'p'])?;
+    let mut minutes = 0.0;
+    let mut in_time = false;
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c.to_ascii_uppercase() {
+            'T' if number.is_empty() => in_time = true,
+            '0'..='9' | '.' | ',' => number.push(if c == ',' { '.' } else { c }),
+            designator => {
+                let value: f64 = number.parse().ok()?;
+                number.clear();
+                minutes += value
+                    * match (designator, in_time) {
+                        ('W', false) => 7.0 * 24.0 * 60.0,
+                        ('D', false) => 24.0 * 60.0,
+                        ('H', true) => 60.0,
+                        ('M', true) => 1.0,
+                        ('S', true) => 1.0 / 60.0,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    // also rejects the infinity of overlong numbers
+    if !number.is_empty() || minutes.round() > f64::from(MAX_DURATION_MIN) {
+        return None;
+    }
+
+    return Some(minutes.round() as i32);
+}
+
+/// `text` cut to `max_length` characters, `None` when it is not longer
+fn shorten(text: &str, max_length: usize) -> Option<String> {
+    if text.chars().count() <= max_length {
+        return None;
+    }
+
+    return Some(
+        text.chars()
+            .take(max_length)
+            .collect::<String>()
+            .trim_end()
+            .to_string(),
+    );
+}
+
+/// first positive whole number of the yield, e.g. 4 of "4 servings" or ["4", "4 pancakes"]
+fn portions(recipe_yield: &Value) -> Option<i32> {
+    return texts(recipe_yield).iter().find_map(|text| {
+        let digits: String = text
+            .trim_start_matches(|c: char| !c.is_ascii_digit())
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        return digits.parse().ok().filter(|portions| *portions > 0);
+    });
+}
+
+/// step texts of `recipeInstructions`, which is a text or a list of texts, HowToStep and
+/// HowToSection objects
+fn instruction_texts(instructions: &Value) -> Vec<String> {
+    return match instructions {
+        Value::Array(values) => values.iter().flat_map(instruction_texts).collect(),
+        Value::Object(object) => match object.get("itemListElement") {
+            // HowToSection
+            Some(steps) => instruction_texts(steps),
+            None => object
+                .get("text")
+                .or(object.get("name"))
+                .and_then(first_text)
+                .into_iter()
+                .collect(),
+        },
+        Value::String(text) => {
+            // a single text may separate steps by lines or paragraphs
+            let lines = text
+                .replace("<br", "\n<br")
+                .replace("<p", "\n<p")
+                .replace("<li", "\n<li");
+            split_instructions(
+                &lines
+                    .lines()
+                    .map(clean_text)
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+            )
+        }
+        _ => vec![],
+    };
+}
+
+/// Split an ingredient line into quantity, unit and name. Quantities may be ranges like "2-3"
+/// of which the lower bound is kept. `None` when there is no name.
+fn parse_ingredient_line(line: &str) -> Option<IngredientLine> {
+    let line = separate_quantity(line);
+    let (line, mut note) = match line.find([',', '(']) {
+        Some(index) => (
+            &line[..index],
+            Some(line[index..].trim_matches([',', ' ']).to_string())
+                .filter(|note| !note.is_empty()),
+        ),
+        None => (line.as_str(), None),
+    };
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let mut rest = &words[..];
+    let mut quantity = None;
+    let mut range = false;
+    // mixed numbers take two words, e.g. "1 1/2"
+    for count in [2, 1] {
+        if rest.len() > count {
+            if let Some(parsed) = parse_quantity(&rest[..count].join(" ")) {
+                quantity = Some(parsed);
+                rest = &rest[count..];
+                break;
+            }
+        }
+    }
+    if quantity.is_none() {
+        if let Some(first) = rest.first() {
+            if let Some(lower) = first
+                .split_once(['-', '–'])
+                .and_then(|(lower, _)| parse_quantity(lower))
+            {
+                quantity = Some(lower);
+                range = true;
+                rest = &rest[1..];
+            } else if ["a", "an"].contains(&first.to_lowercase().as_str()) && rest.len() > 1 {
+                quantity = Some(BigDecimal::from(1));
+                rest = &rest[1..];
+            }
+        }
+    } else if rest.len() > 2 && ["-", "–", "to"].contains(&rest[0]) {
+        // upper bound of "2 - 3" or "2 to 3"
+        range = true;
+        rest = &rest[2..];
+    }
+    let quantity = quantity.filter(|quantity| *quantity > BigDecimal::from(0));
+
+    let mut unit = "pc";
+    for count in [2, 1] {
+        if rest.len() > count {
+            if let Some(found) = find_unit(&rest[..count].join(" ")) {
+                unit = found.code;
+                rest = &rest[count..];
+                break;
+            }
+        }
+    }
+    if rest.len() > 1 && rest[0] == "of" {
+        rest = &rest[1..];
+    }
+
+    let mut name = rest.join(" ").to_lowercase();
+    if let Some(stripped) = name.strip_suffix(" to taste") {
+        name = stripped.to_string();
+        note = Some(note.map_or("to taste".to_string(), |note| format!("to taste {note}")));
+    }
+    if name.is_empty() {
+        return None;
+    }
+
+    return Some(IngredientLine {
+        quantity,
+        range,
+        unit,
+        name,
+        note,
+    });
+}
+
+/// cleaned line with a space between a quantity and a glued unit, e.g. "200g" to "200 g"
+fn separate_quantity(line: &str) -> String {
+    let line = clean_text(line);
+    let mut separated = String::with_capacity(line.len() + 1);
+    let mut previous_digit = false;
+    for c in line.chars() {
+        if previous_digit && c.is_alphabetic() {
+            separated.push(' ');
+        }
+        previous_digit = c.is_ascii_digit() || "½⅓⅔¼¾⅕⅖⅗⅘⅙⅚⅛⅜⅝⅞".contains(c);
+        separated.push(c);
+    }
+
+    return separated;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    fn line(
+        quantity: Option<&str>,
+        range: bool,
+        unit: &'static str,
+        name: &str,
+        note: Option<&str>,
+    ) -> Option<IngredientLine> {
+        return Some(IngredientLine {
+            quantity: quantity.map(|quantity| BigDecimal::from_str(quantity).unwrap()),
+            range,
+            unit,
+            name: name.to_string(),
+            note: note.map(str::to_string),
+        });
+    }
+
+    #[test]
+    fn parses_ingredient_lines() {
+        assert_eq!(
+            parse_ingredient_line("1 1/2 cups flour, sifted"),
+            line(Some("1.5"), false, "cup", "flour", Some("sifted"))
+        );
+        assert_eq!(
+            parse_ingredient_line("200g Butter"),
+            line(Some("200"), false, "g", "butter", None)
+        );
+        assert_eq!(
+            parse_ingredient_line("a pinch of salt"),
+            line(Some("1"), false, "pinch", "salt", None)
+        );
+        assert_eq!(
+            parse_ingredient_line("3 eggs"),
+            line(Some("3"), false, "pc", "eggs", None)
+        );
+    }
+
+    #[test]
+    fn keeps_lower_bound_of_ingredient_ranges() {
+        assert_eq!(
+            parse_ingredient_line("2-3 carrots"),
+            line(Some("2"), true, "pc", "carrots", None)
+        );
+        assert_eq!(
+            parse_ingredient_line("2 to 3 tbsp sugar"),
+            line(Some("2"), true, "tbsp", "sugar", None)
+        );
+    }
+
+    #[test]
+    fn parses_ingredient_lines_without_quantity() {
+        assert_eq!(
+            parse_ingredient_line("pepper to taste"),
+            line(None, false, "pc", "pepper", Some("to taste"))
+        );
+        assert_eq!(parse_ingredient_line("(optional), chopped"), None);
+        assert_eq!(parse_ingredient_line(""), None);
+        // out of the column range, the number stays part of the name
+        assert_eq!(
+            parse_ingredient_line("99999999 g flour").and_then(|ingredient| ingredient.quantity),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("PT1H30M"), Some(90));
+        assert_eq!(parse_duration("P1DT2H"), Some(26 * 60));
+        assert_eq!(parse_duration("P1W"), Some(7 * 24 * 60));
+        assert_eq!(parse_duration("pt0,5h"), Some(30));
+        assert_eq!(parse_duration("PT90S"), Some(2));
+        assert_eq!(parse_duration(" PT20M "), Some(20));
+    }
+
+    #[test]
+    fn rejects_unsupported_durations() {
+        assert_eq!(parse_duration("1H"), None);
+        assert_eq!(parse_duration("P1M"), None);
+        assert_eq!(parse_duration("PT1H30"), None);
+        assert_eq!(parse_duration("PTxM"), None);
+        assert_eq!(parse_duration("P367D"), None);
+        assert_eq!(parse_duration(&format!("PT{}M", "9".repeat(400))), None);
+    }
+
+    #[test]
+    fn bounds_summed_durations() {
+        let recipe = json!({"prepTime": "P366D", "cookTime": "P366D"});
+        assert_eq!(
+            recipe_duration(recipe.as_object().unwrap()),
+            Ok(Some(MAX_DURATION_MIN))
+        );
+        let recipe = json!({"prepTime": "PT10M", "cookTime": "PT1H", "totalTime": "PT2H"});
+        assert_eq!(recipe_duration(recipe.as_object().unwrap()), Ok(Some(120)));
+        let recipe = json!({"prepTime": "PT10M", "cookTime": "soon"});
+        assert_eq!(
+            recipe_duration(recipe.as_object().unwrap()),
+            Err("soon".to_string())
+        );
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(
+            decode_entities("salt &amp; pepper &frac12; cup &#176;C &#x2153;"),
+            "salt & pepper ½ cup °C ⅓"
+        );
+        assert_eq!(decode_entities("&unknown; & &;"), "&unknown; & &;");
+        assert_eq!(
+            decode_entities("&#xD800; &#99999999;"),
+            "&#xD800; &#99999999;"
+        );
+    }
+
+    #[test]
+    fn finds_json_ld_scripts() {
+        let html = r#"<html><head>
+            <script src="app.js"></script>
+            <SCRIPT type="application/ld+json">{"@type": "Recipe"}</SCRIPT>
+            <script type='application/ld+json'>[]</script>
+            </head></html>"#;
+        assert_eq!(json_ld_scripts(html), vec![r#"{"@type": "Recipe"}"#, "[]"]);
+        assert!(json_ld_scripts("<script type=\"application/ld+json\">{").is_empty());
+    }
+}
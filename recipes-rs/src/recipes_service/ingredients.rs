@@ -0,0 +1,362 @@
+use bigdecimal::RoundingMode;
+use diesel::define_sql_function;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, Integer};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::{debug, info};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::models::ingredient::{ChangeIngredient, Ingredient, NewIngredient, RecipeIngredient};
+use super::quantity::{max_quantity, QUANTITY_SCALE};
+use super::schema::{ingredients, pantry_items, recipe_ingredient, recipe_steps, recipes};
+use super::units::add_quantities;
+use super::utils::{escape_like, get_connection};
+
+define_sql_function! { fn array_remove(array: Array<Integer>, element: Integer) -> Array<Integer>; }
+define_sql_function! {
+    fn array_replace(array: Array<Integer>, from: Integer, to: Integer) -> Array<Integer>;
+}
+
+pub async fn list_ingredients(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    search: &Option<String>,
+) -> Result<Vec<Ingredient>, ServiceError> {
+    info!("Listing ingredients");
+    let mut connection = get_connection(db_pool).await?;
+    let mut all_ingredients = ingredients::table
+        .select(Ingredient::as_select())
+        .order_by(ingredients::name)
+        .into_boxed();
+
+    if let Some(search) = search {
+        debug!(search; "Searching ingredients by name");
+        all_ingredients =
+            all_ingredients.filter(ingredients::name.ilike(format!("%{}%", escape_like(search))));
+    }
+
+    return Ok(all_ingredients.load(&mut connection).await?);
+}
+
+pub async fn get_ingredient(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    ingredient_id: &i32,
+) -> Result<Ingredient, ServiceError> {
+    info!(ingredient_id; "Getting ingredient");
+    let mut connection = get_connection(db_pool).await?;
+    return Ok(ingredients::table
+        .select(Ingredient::as_select())
+        .find(ingredient_id)
+        .first(&mut connection)
+        .await?);
+}
+
+pub async fn update_ingredient(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    ingredient_id: &i32,
+    change_ingredient: &ChangeIngredient,
+) -> Result<Ingredient, ServiceError> {
+    info!(ingredient_id, ingredient:serde = change_ingredient; "Changing ingredient");
+    // recipes find ingredients by their trimmed name
+    let change_ingredient = &ChangeIngredient {
+        name: change_ingredient
+            .name
+            .as_ref()
+            .map(|name| name.trim().to_string()),
+    };
+    let mut connection = get_connection(db_pool).await?;
+    return Ok(connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let ingredient = diesel::update(ingredients::table.find(ingredient_id))
+                    .set(change_ingredient)
+                    .returning(Ingredient::as_returning())
+                    .get_result(connection)
+                    .await?;
+                bump_recipe_versions(connection, &[ingredient.id]).await?;
+
+                return Ok::<Ingredient, diesel::result::Error>(ingredient);
+            })
+        })
+        .await?);
+}
+
+pub async fn delete_ingredient(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    ingredient_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(ingredient_id; "Deleting ingredient");
+    let mut connection = get_connection(db_pool).await?;
+    connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let recipe_assoc = get_ingredient_recipes(connection, &[*ingredient_id]).await?;
+                let pantry_assoc: i64 = pantry_items::table
+                    .filter(pantry_items::ingredient_id.eq(ingredient_id))
+                    .count()
+                    .get_result(connection)
+                    .await?;
+                if !recipe_assoc.is_empty() {
+                    return Err(ServiceError::InUse {
+                        resource: "ingredient",
+                        used_by: "recipes",
+                    });
+                }
+                if pantry_assoc > 0 {
+                    return Err(ServiceError::InUse {
+                        resource: "ingredient",
+                        used_by: "pantry items",
+                    });
+                }
+                diesel::delete(ingredients::table.find(ingredient_id))
+                    .execute(connection)
+                    .await?;
+                debug!("Removed ingredient");
+
+                return Ok::<(), ServiceError>(());
+            })
+        })
+        .await?;
+
+    return Ok(());
+}
+
+/// Repoint recipe, step and pantry associations of `duplicate_ids` to the canonical ingredient and
+/// remove the duplicates. When a recipe already uses the canonical ingredient, the duplicate's
+/// quantity is added to it. Unknown duplicate ids and recipes whose quantities cannot be added
+/// fail the merge.
+pub async fn merge_ingredients(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    ingredient_id: &i32,
+    duplicate_ids: &Vec<i32>,
+) -> Result<Ingredient, ServiceError> {
+    info!(ingredient_id, duplicates:serde = duplicate_ids; "Merging ingredients");
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let ingredient = ingredients::table
+                    .select(Ingredient::as_select())
+                    .find(ingredient_id)
+                    .first(connection)
+                    .await?;
+                let duplicate_ids: Vec<i32> = duplicate_ids
+                    .iter()
+                    .filter(|id| **id != ingredient.id)
+                    .copied()
+                    .collect();
+                let known_ids: Vec<i32> = ingredients::table
+                    .select(ingredients::id)
+                    .filter(ingredients::id.eq_any(&duplicate_ids))
+                    .load(connection)
+                    .await?;
+                let unknown_ids: Vec<String> = duplicate_ids
+                    .iter()
+                    .filter(|id| !known_ids.contains(id))
+                    .map(i32::to_string)
+                    .collect();
+                if !unknown_ids.is_empty() {
+                    return Err(ServiceError::InvalidValue {
+                        field: "ingredient_ids",
+                        detail: format!("Unknown ingredients {}", unknown_ids.join(", ")),
+                    });
+                }
+                bump_recipe_versions(connection, &duplicate_ids).await?;
+
+                let mut canonical_rows: HashMap<i32, RecipeIngredient> =
+                    get_ingredient_recipes(connection, &[ingredient.id])
+                        .await?
+                        .into_iter()
+                        .map(|rec_ing| (rec_ing.recipe_id, rec_ing))
+                        .collect();
+                let mut conflicting_recipe_ids = BTreeSet::new();
+                for rec_ing in get_ingredient_recipes(connection, &duplicate_ids).await? {
+                    let rec_ing_row = recipe_ingredient::table
+                        .find((rec_ing.recipe_id, rec_ing.ingredient_id));
+                    let Some(canonical) = canonical_rows.get_mut(&rec_ing.recipe_id) else {
+                        diesel::update(rec_ing_row)
+                            .set(recipe_ingredient::ingredient_id.eq(ingredient.id))
+                            .execute(connection)
+                            .await?;
+                        debug!(recipe_id = rec_ing.recipe_id; "Repointed recipe ingredient");
+                        canonical_rows.insert(
+                            rec_ing.recipe_id,
+                            RecipeIngredient {
+                                ingredient_id: ingredient.id,
+                                ..rec_ing
+                            },
+                        );
+                        continue;
+                    };
+
+                    let quantity = add_quantities(
+                        &canonical.quantity,
+                        &canonical.unit,
+                        &rec_ing.quantity,
+                        &rec_ing.unit,
+                    )
+                    .map(|quantity| quantity.with_scale_round(QUANTITY_SCALE, RoundingMode::HalfUp))
+                    .filter(|quantity| quantity <= &max_quantity());
+                    let Some(quantity) = quantity else {
+                        conflicting_recipe_ids.insert(rec_ing.recipe_id);
+                        continue;
+                    };
+                    diesel::update(
+                        recipe_ingredient::table.find((canonical.recipe_id, ingredient.id)),
+                    )
+                    .set(recipe_ingredient::quantity.eq(&quantity))
+                    .execute(connection)
+                    .await?;
+                    diesel::delete(rec_ing_row).execute(connection).await?;
+                    debug!(recipe_id = rec_ing.recipe_id; "Added duplicate recipe ingredient quantity");
+                    canonical.quantity = quantity;
+                }
+                if !conflicting_recipe_ids.is_empty() {
+                    let recipe_ids: Vec<String> =
+                        conflicting_recipe_ids.iter().map(i32::to_string).collect();
+                    return Err(ServiceError::Conflict {
+                        code: "incompatible_quantities",
+                        detail: format!(
+                            "Recipes {} use several of the ingredients in quantities that cannot \
+                             be added",
+                            recipe_ids.join(", ")
+                        ),
+                    });
+                }
+
+                diesel::update(
+                    pantry_items::table.filter(pantry_items::ingredient_id.eq_any(&duplicate_ids)),
+                )
+                .set(pantry_items::ingredient_id.eq(ingredient.id))
+                .execute(connection)
+                .await?;
+
+                for duplicate_id in &duplicate_ids {
+                    // steps naming both would list the canonical ingredient twice
+                    diesel::update(recipe_steps::table.filter(
+                        recipe_steps::ingredient_ids.contains(vec![ingredient.id, *duplicate_id]),
+                    ))
+                    .set(
+                        recipe_steps::ingredient_ids
+                            .eq(array_remove(recipe_steps::ingredient_ids, *duplicate_id)),
+                    )
+                    .execute(connection)
+                    .await?;
+                    diesel::update(
+                        recipe_steps::table
+                            .filter(recipe_steps::ingredient_ids.contains(vec![*duplicate_id])),
+                    )
+                    .set(recipe_steps::ingredient_ids.eq(array_replace(
+                        recipe_steps::ingredient_ids,
+                        *duplicate_id,
+                        ingredient.id,
+                    )))
+                    .execute(connection)
+                    .await?;
+                }
+
+                diesel::delete(ingredients::table.filter(ingredients::id.eq_any(&duplicate_ids)))
+                    .execute(connection)
+                    .await?;
+                debug!(duplicates:serde = duplicate_ids; "Removed duplicate ingredients");
+
+                return Ok(ingredient);
+            })
+        })
+        .await;
+}
+
+/// Recipes show ingredient names, so a cached copy of a recipe using the ingredients is stale.
+async fn bump_recipe_versions(
+    connection: &mut AsyncPgConnection,
+    ingredient_ids: &[i32],
+) -> Result<(), diesel::result::Error> {
+    let bumped = diesel::update(
+        recipes::table.filter(
+            recipes::id.eq_any(
+                recipe_ingredient::table
+                    .select(recipe_ingredient::recipe_id)
+                    .filter(recipe_ingredient::ingredient_id.eq_any(ingredient_ids)),
+            ),
+        ),
+    )
+    .set(recipes::version.eq(recipes::version + 1))
+    .execute(connection)
+    .await?;
+    debug!(bumped; "Bumped versions of recipes using the ingredients");
+
+    return Ok(());
+}
+
+/// get ingredient by its name, creating it when it does not exist yet
+pub async fn get_or_create_ingredient(
+    connection: &mut AsyncPgConnection,
+    name: &str,
+) -> Result<Ingredient, diesel::result::Error> {
+    let ingredient = diesel::insert_into(ingredients::table)
+        .values(&NewIngredient { name })
+        .on_conflict_do_nothing()
+        .returning(Ingredient::as_returning())
+        .get_result(connection)
+        .await
+        .optional()?;
+
+    return match ingredient {
+        Some(ingredient) => {
+            debug!(ingredient:serde; "Created ingredient");
+            Ok(ingredient)
+        }
+        None => {
+            debug!(ingredient = name; "Ingredient already exists");
+            ingredients::table
+                .filter(ingredients::name.eq(name))
+                .select(Ingredient::as_select())
+                .first(connection)
+                .await
+        }
+    };
+}
+
+async fn get_ingredient_recipes(
+    connection: &mut AsyncPgConnection,
+    ingredient_ids: &[i32],
+) -> Result<Vec<RecipeIngredient>, diesel::result::Error> {
+    return recipe_ingredient::table
+        .filter(recipe_ingredient::ingredient_id.eq_any(ingredient_ids))
+        .select(RecipeIngredient::as_select())
+        .load(connection)
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes_service::test_utils::test_pool;
+
+    #[actix_web::test]
+    async fn trims_renamed_ingredients() {
+        let Some(db_pool) = test_pool().await else {
+            return;
+        };
+        let mut connection = get_connection(db_pool.clone()).await.unwrap();
+        let ingredient = get_or_create_ingredient(&mut connection, "flour")
+            .await
+            .unwrap();
+
+        let change_ingredient = ChangeIngredient {
+            name: Some(" wheat flour ".to_string()),
+        };
+        let renamed = update_ingredient(db_pool.clone(), &ingredient.id, &change_ingredient)
+            .await
+            .unwrap();
+        assert_eq!(renamed.name, "wheat flour");
+        let found = get_or_create_ingredient(&mut connection, "wheat flour")
+            .await
+            .unwrap();
+        assert_eq!(found.id, ingredient.id);
+    }
+}
@@ -0,0 +1,227 @@
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::{debug, info};
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::models::meal_plan::{ChangeMealPlanEntry, MealPlanEntry, MealSlot, NewMealPlanEntry};
+use super::models::recipe::Recipe;
+use super::recipes::{get_accessible_recipe, RecipeAccess};
+use super::schema::{meal_plan_entries, recipes};
+use super::utils::{get_connection, shift_date};
+
+/// longest listed date range in days
+pub const MAX_RANGE_DAYS: i64 = 366;
+
+/// meal plan entries of the user between the dates, both inclusive, with recipe names
+pub async fn list_meal_plan(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    from: &NaiveDate,
+    to: &NaiveDate,
+) -> Result<Vec<(MealPlanEntry, String)>, ServiceError> {
+    info!(user_id, from:serde, to:serde; "Listing meal plan");
+    if to < from || (*to - *from).num_days() >= MAX_RANGE_DAYS {
+        return Err(ServiceError::InvalidValue {
+            field: "to",
+            detail: format!("must be within {MAX_RANGE_DAYS} days after 'from'"),
+        });
+    }
+
+    let mut connection = get_connection(db_pool).await?;
+    return Ok(load_entries(&mut connection, user_id, from, to).await?);
+}
+
+pub async fn add_meal_plan_entry(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    date: &NaiveDate,
+    slot: &MealSlot,
+    recipe_id: &i32,
+    portions: &Option<i32>,
+) -> Result<(MealPlanEntry, String), ServiceError> {
+    info!(user_id, date:serde, slot:serde, recipe_id; "Adding meal plan entry");
+    let mut connection = get_connection(db_pool).await?;
+    let recipe = get_plannable_recipe(&mut connection, user_id, recipe_id).await?;
+    // recipes stored before portions were validated may have any number of them
+    let portions = portions.unwrap_or(recipe.portions.clamp(1, 1000));
+
+    let entry = diesel::insert_into(meal_plan_entries::table)
+        .values(NewMealPlanEntry {
+            user_id: *user_id,
+            date: *date,
+            slot: *slot,
+            recipe_id: recipe.id,
+            portions,
+        })
+        .returning(MealPlanEntry::as_returning())
+        .get_result(&mut connection)
+        .await?;
+
+    return Ok((entry, recipe.name));
+}
+
+pub async fn update_meal_plan_entry(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    entry_id: &i32,
+    change_entry: &ChangeMealPlanEntry,
+) -> Result<(MealPlanEntry, String), ServiceError> {
+    info!(user_id, entry_id, entry:serde = change_entry; "Changing meal plan entry");
+    let mut connection = get_connection(db_pool).await?;
+    if let Some(recipe_id) = &change_entry.recipe_id {
+        get_plannable_recipe(&mut connection, user_id, recipe_id).await?;
+    }
+
+    let user_entry = meal_plan_entries::table
+        .find(entry_id)
+        .filter(meal_plan_entries::user_id.eq(user_id));
+    // diesel refuses to run an update without any column to set
+    let entry = if change_entry.is_empty() {
+        user_entry
+            .select(MealPlanEntry::as_select())
+            .first(&mut connection)
+            .await?
+    } else {
+        diesel::update(user_entry)
+            .set(change_entry)
+            .returning(MealPlanEntry::as_returning())
+            .get_result(&mut connection)
+            .await?
+    };
+    let recipe_name = recipes::table
+        .find(entry.recipe_id)
+        .select(recipes::name)
+        .first(&mut connection)
+        .await?;
+
+    return Ok((entry, recipe_name));
+}
+
+pub async fn delete_meal_plan_entry(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    entry_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(user_id, entry_id; "Deleting meal plan entry");
+    let mut connection = get_connection(db_pool).await?;
+    let deleted = diesel::delete(
+        meal_plan_entries::table
+            .find(entry_id)
+            .filter(meal_plan_entries::user_id.eq(user_id)),
+    )
+    .execute(&mut connection)
+    .await?;
+    if deleted == 0 {
+        return Err(ServiceError::DbDiesel(diesel::result::Error::NotFound));
+    }
+
+    return Ok(());
+}
+
+/// Copy the seven days starting at `from` to the seven days starting at `to`. With `replace`
+/// the entries already planned in the target week are removed first.
+pub async fn copy_meal_plan_week(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    from: &NaiveDate,
+    to: &NaiveDate,
+    replace: &bool,
+) -> Result<Vec<(MealPlanEntry, String)>, ServiceError> {
+    info!(user_id, from:serde, to:serde, replace; "Copying meal plan week");
+    if to == from {
+        return Err(ServiceError::InvalidValue {
+            field: "to",
+            detail: "must differ from 'from'".to_string(),
+        });
+    }
+    let from_end = shift_date(from, 6, "from")?;
+    let to_end = shift_date(to, 6, "to")?;
+    let shift = (*to - *from).num_days();
+
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let source_entries: Vec<MealPlanEntry> = meal_plan_entries::table
+                    .filter(meal_plan_entries::user_id.eq(user_id))
+                    .filter(meal_plan_entries::date.between(*from, from_end))
+                    .select(MealPlanEntry::as_select())
+                    .load(connection)
+                    .await?;
+
+                if *replace {
+                    let removed = diesel::delete(
+                        meal_plan_entries::table
+                            .filter(meal_plan_entries::user_id.eq(user_id))
+                            .filter(meal_plan_entries::date.between(*to, to_end)),
+                    )
+                    .execute(connection)
+                    .await?;
+                    debug!(removed; "Removed entries of the target week");
+                }
+
+                let new_entries: Vec<NewMealPlanEntry> = source_entries
+                    .iter()
+                    .map(|entry| {
+                        return Ok(NewMealPlanEntry {
+                            user_id: *user_id,
+                            date: shift_date(&entry.date, shift, "to")?,
+                            slot: entry.slot,
+                            recipe_id: entry.recipe_id,
+                            portions: entry.portions,
+                        });
+                    })
+                    .collect::<Result<_, ServiceError>>()?;
+                diesel::insert_into(meal_plan_entries::table)
+                    .values(&new_entries)
+                    .execute(connection)
+                    .await?;
+                debug!(copied = new_entries.len(); "Copied entries");
+
+                return Ok(load_entries(connection, user_id, to, &to_end).await?);
+            })
+        })
+        .await;
+}
+
+async fn load_entries(
+    connection: &mut AsyncPgConnection,
+    user_id: &i32,
+    from: &NaiveDate,
+    to: &NaiveDate,
+) -> Result<Vec<(MealPlanEntry, String)>, diesel::result::Error> {
+    return meal_plan_entries::table
+        .inner_join(recipes::table)
+        .filter(meal_plan_entries::user_id.eq(user_id))
+        .filter(meal_plan_entries::date.between(from, to))
+        .select((MealPlanEntry::as_select(), recipes::name))
+        .order_by((
+            meal_plan_entries::date,
+            meal_plan_entries::slot,
+            meal_plan_entries::id,
+        ))
+        .load(connection)
+        .await;
+}
+
+/// the recipe must exist and the user must see it
+async fn get_plannable_recipe(
+    connection: &mut AsyncPgConnection,
+    user_id: &i32,
+    recipe_id: &i32,
+) -> Result<Recipe, ServiceError> {
+    return match get_accessible_recipe(connection, &Some(*user_id), recipe_id, RecipeAccess::Read)
+        .await
+    {
+        Err(ServiceError::DbDiesel(diesel::result::Error::NotFound)) => {
+            Err(ServiceError::InvalidValue {
+                field: "recipe_id",
+                detail: format!("Recipe '{recipe_id}' does not exist"),
+            })
+        }
+        result => result,
+    };
+}
@@ -1,6 +1,25 @@
+pub mod api_keys;
 pub mod categories;
+pub mod collections;
 pub mod errors;
+pub mod favourites;
+pub mod import;
+pub mod ingredients;
+pub mod meal_plans;
 pub mod models;
+pub mod pantry;
+pub mod quantity;
 pub mod recipes;
+pub mod reviews;
+pub mod revisions;
+pub mod scaling;
 pub mod schema;
+pub mod search;
+pub mod shares;
+pub mod shopping_lists;
+pub mod steps;
+#[cfg(test)]
+pub mod test_utils;
+pub mod units;
+pub mod users;
 pub mod utils;
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    serialize::{self, IsNull, Output, ToSql},
+    sql_types::Text,
+    Associations, Identifiable, Insertable, Queryable, Selectable,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use utoipa::ToSchema;
+
+use crate::recipes_service::models::user::User;
+use crate::recipes_service::schema::api_keys;
+
+/// what a machine client may do, sessions of users are not limited by scopes
+#[derive(
+    AsExpression, FromSqlRow, ToSchema, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+#[diesel(sql_type = Text)]
+pub enum Scope {
+    /// read recipes and categories
+    #[serde(rename = "read:recipes")]
+    ReadRecipes,
+    /// create, change and delete recipes and categories
+    #[serde(rename = "write:recipes")]
+    WriteRecipes,
+    /// administer users and API keys
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug)]
+#[diesel(table_name = api_keys)]
+#[diesel(belongs_to(User))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ApiKey {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub key_hash: String,
+    /// start of the key to tell keys apart, the key itself is not stored
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = api_keys)]
+pub struct NewApiKey<'a> {
+    pub user_id: i32,
+    pub name: &'a str,
+    pub key_hash: &'a str,
+    pub prefix: &'a str,
+    pub scopes: &'a Vec<Scope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            Scope::ReadRecipes => "read:recipes",
+            Scope::WriteRecipes => "write:recipes",
+            Scope::Admin => "admin",
+        };
+    }
+}
+
+impl ToSql<Text, Pg> for Scope {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        out.write_all(self.as_str().as_bytes())?;
+        return Ok(IsNull::No);
+    }
+}
+
+impl FromSql<Text, Pg> for Scope {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        return match bytes.as_bytes() {
+            b"read:recipes" => Ok(Scope::ReadRecipes),
+            b"write:recipes" => Ok(Scope::WriteRecipes),
+            b"admin" => Ok(Scope::Admin),
+            _ => Err("Unrecognized API key scope".into()),
+        };
+    }
+}
@@ -10,17 +10,20 @@ use utoipa::ToSchema;
 #[diesel(primary_key(name))]
 pub struct Category {
     pub name: String,
+    pub version: i32,
 }
 
 #[derive(Insertable, ToSchema, Serialize, Deserialize)]
 #[diesel(table_name = categories)]
 pub struct NewCategory {
+    #[schema(min_length = 1, max_length = 50)]
     pub name: String,
 }
 
 #[derive(AsChangeset, ToSchema, Deserialize)]
 #[diesel(table_name = categories)]
 pub struct ChangeCategory {
+    #[schema(min_length = 1, max_length = 50)]
     pub name: Option<String>,
 }
 
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use diesel::{Associations, Identifiable, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::recipes_service::models::{recipe::Recipe, user::User};
+use crate::recipes_service::schema::{collection_recipes, collections, favourites};
+
+/// recipe starred by a user
+#[derive(Insertable)]
+#[diesel(table_name = favourites)]
+pub struct NewFavourite {
+    pub user_id: i32,
+    pub recipe_id: i32,
+}
+
+/// named list of recipes of a user, e.g. "Weeknight"
+#[derive(Queryable, Selectable, Identifiable, Associations, Serialize, Debug)]
+#[diesel(table_name = collections)]
+#[diesel(belongs_to(User))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Collection {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    /// token of the public read-only link, `None` when not shared
+    pub share_token: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = collections)]
+pub struct NewCollection<'a> {
+    pub user_id: i32,
+    pub name: &'a str,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Associations, Insertable, Serialize, Debug)]
+#[diesel(table_name = collection_recipes)]
+#[diesel(belongs_to(Collection))]
+#[diesel(belongs_to(Recipe))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(collection_id, recipe_id))]
+pub struct CollectionRecipe {
+    pub collection_id: i32,
+    pub recipe_id: i32,
+    /// order of the recipe in the collection, from 0
+    pub position: i32,
+}
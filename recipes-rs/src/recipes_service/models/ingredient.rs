@@ -1,12 +1,12 @@
-use serde::Serialize;
-use std::i16;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 use crate::recipes_service::models::recipe::Recipe;
 use crate::recipes_service::schema::{ingredients, recipe_ingredient};
 use bigdecimal::BigDecimal;
-use diesel::{Associations, Identifiable, Insertable, Queryable, Selectable};
+use diesel::{prelude::AsChangeset, Associations, Identifiable, Insertable, Queryable, Selectable};
 
-#[derive(Queryable, Selectable, Serialize)]
+#[derive(Queryable, Selectable, Identifiable, Serialize, Clone, Debug)]
 #[diesel(table_name = ingredients)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Ingredient {
@@ -20,7 +20,14 @@ pub struct NewIngredient<'a> {
     pub name: &'a str,
 }
 
-#[derive(Identifiable, Queryable, Selectable, Associations, Insertable)]
+#[derive(AsChangeset, ToSchema, Deserialize, Serialize)]
+#[diesel(table_name = ingredients)]
+pub struct ChangeIngredient {
+    #[schema(min_length = 1, max_length = 100)]
+    pub name: Option<String>,
+}
+
+#[derive(Identifiable, Queryable, Selectable, Associations, Insertable, Debug)]
 #[diesel(table_name = recipe_ingredient)]
 #[diesel(belongs_to(Recipe))]
 #[diesel(belongs_to(Ingredient))]
@@ -39,6 +46,13 @@ pub struct RecipeIngredient {
 pub struct NewRecipeIngredient<'a> {
     pub name: &'a str,
     pub part: i16,
-    pub quantity: i32,
+    pub quantity: &'a BigDecimal,
     pub unit: &'a str,
 }
+
+/// ingredients of a single recipe part (e.g. dough, filling), ordered by ingredient name
+#[derive(Debug)]
+pub struct RecipePart {
+    pub part: i16,
+    pub ingredients: Vec<(RecipeIngredient, Ingredient)>,
+}
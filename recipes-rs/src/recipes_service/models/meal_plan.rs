@@ -0,0 +1,96 @@
+use chrono::NaiveDate;
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    prelude::*,
+    serialize::{self, IsNull, Output, ToSql},
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use utoipa::ToSchema;
+
+use crate::recipes_service::models::{recipe::Recipe, user::User};
+use crate::recipes_service::schema::{meal_plan_entries, sql_types};
+
+#[derive(
+    AsExpression, FromSqlRow, ToSchema, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+#[diesel(sql_type = sql_types::MealSlot)]
+#[serde(rename_all = "snake_case")]
+pub enum MealSlot {
+    Breakfast,
+    Lunch,
+    Dinner,
+    Snack,
+}
+
+/// recipe planned for a meal of a day
+#[derive(Queryable, Selectable, Identifiable, Associations, Serialize, Debug)]
+#[diesel(table_name = meal_plan_entries)]
+#[diesel(belongs_to(User))]
+#[diesel(belongs_to(Recipe))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MealPlanEntry {
+    pub id: i32,
+    pub user_id: i32,
+    pub date: NaiveDate,
+    pub slot: MealSlot,
+    pub recipe_id: i32,
+    pub portions: i32,
+}
+
+#[derive(Insertable, Serialize)]
+#[diesel(table_name = meal_plan_entries)]
+pub struct NewMealPlanEntry {
+    pub user_id: i32,
+    pub date: NaiveDate,
+    pub slot: MealSlot,
+    pub recipe_id: i32,
+    pub portions: i32,
+}
+
+#[derive(AsChangeset, ToSchema, Deserialize, Serialize)]
+#[diesel(table_name = meal_plan_entries)]
+pub struct ChangeMealPlanEntry {
+    pub date: Option<NaiveDate>,
+    pub slot: Option<MealSlot>,
+    pub recipe_id: Option<i32>,
+    #[schema(minimum = 1, maximum = 1000)]
+    pub portions: Option<i32>,
+}
+
+impl ChangeMealPlanEntry {
+    /// true when no column would be changed by the changeset
+    pub fn is_empty(&self) -> bool {
+        return self.date.is_none()
+            && self.slot.is_none()
+            && self.recipe_id.is_none()
+            && self.portions.is_none();
+    }
+}
+
+impl ToSql<sql_types::MealSlot, Pg> for MealSlot {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value: &[u8] = match self {
+            MealSlot::Breakfast => b"breakfast",
+            MealSlot::Lunch => b"lunch",
+            MealSlot::Dinner => b"dinner",
+            MealSlot::Snack => b"snack",
+        };
+        out.write_all(value)?;
+        return Ok(IsNull::No);
+    }
+}
+
+impl FromSql<sql_types::MealSlot, Pg> for MealSlot {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        return match bytes.as_bytes() {
+            b"breakfast" => Ok(MealSlot::Breakfast),
+            b"lunch" => Ok(MealSlot::Lunch),
+            b"dinner" => Ok(MealSlot::Dinner),
+            b"snack" => Ok(MealSlot::Snack),
+            _ => Err("Unrecognized meal slot".into()),
+        };
+    }
+}
@@ -1,4 +1,14 @@
+pub mod api_key;
 pub mod category;
+pub mod collection;
 pub mod image;
 pub mod ingredient;
+pub mod meal_plan;
+pub mod pantry_item;
 pub mod recipe;
+pub mod recipe_revision;
+pub mod recipe_share;
+pub mod recipe_step;
+pub mod review;
+pub mod shopping_list;
+pub mod user;
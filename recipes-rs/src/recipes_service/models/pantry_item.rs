@@ -0,0 +1,52 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use diesel::{prelude::AsChangeset, Associations, Identifiable, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::recipes_service::models::{ingredient::Ingredient, user::User};
+use crate::recipes_service::schema::pantry_items;
+
+/// amount of an ingredient a user has at home
+#[derive(Queryable, Selectable, Identifiable, Associations, Serialize, Debug)]
+#[diesel(table_name = pantry_items)]
+#[diesel(belongs_to(User))]
+#[diesel(belongs_to(Ingredient))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PantryItem {
+    pub id: i32,
+    pub user_id: i32,
+    pub ingredient_id: i32,
+    pub quantity: BigDecimal,
+    pub unit: String,
+    pub expires_on: Option<NaiveDate>,
+}
+
+#[derive(Insertable, Serialize)]
+#[diesel(table_name = pantry_items)]
+pub struct NewPantryItem {
+    pub user_id: i32,
+    pub ingredient_id: i32,
+    pub quantity: BigDecimal,
+    pub unit: String,
+    pub expires_on: Option<NaiveDate>,
+}
+
+/// replaces all columns of the item, a missing expiry is cleared
+#[derive(AsChangeset, Serialize)]
+#[diesel(table_name = pantry_items)]
+#[diesel(treat_none_as_null = true)]
+pub struct ChangePantryItem {
+    pub ingredient_id: i32,
+    pub quantity: BigDecimal,
+    pub unit: String,
+    pub expires_on: Option<NaiveDate>,
+}
+
+/// helper to build PantryItem insert and changeset
+#[derive(Serialize)]
+pub struct NewPantryIngredient<'a> {
+    pub name: &'a str,
+    pub quantity: &'a BigDecimal,
+    pub unit: &'a str,
+    pub expires_on: Option<NaiveDate>,
+}
@@ -1,9 +1,44 @@
-use crate::recipes_service::schema::recipes;
-use diesel::{prelude::AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use crate::recipes_service::schema::{recipes, sql_types::RecipeVisibility};
+use bigdecimal::BigDecimal;
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    prelude::AsChangeset,
+    serialize::{self, IsNull, Output, ToSql},
+    Identifiable, Insertable, Queryable, Selectable,
+};
 use serde::{Deserialize, Serialize};
+use std::io::Write;
 use utoipa::ToSchema;
 
-#[derive(Queryable, Selectable, Identifiable, ToSchema, Serialize)]
+/// who may see a recipe besides its owner
+#[derive(
+    AsExpression,
+    FromSqlRow,
+    ToSchema,
+    Serialize,
+    Deserialize,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Debug,
+)]
+#[diesel(sql_type = RecipeVisibility)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    /// only the owner
+    #[default]
+    Private,
+    /// the owner and users the recipe is shared with
+    Shared,
+    /// everyone, including anonymous callers
+    Public,
+}
+
+#[derive(Queryable, Selectable, Identifiable, ToSchema, Serialize, Clone)]
 #[diesel(table_name = recipes)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Recipe {
@@ -15,6 +50,15 @@ pub struct Recipe {
     pub preparation_needed: bool,
     pub portions: i32,
     pub difficulty: i32,
+    /// `None` for recipes created before users existed
+    pub owner_id: Option<i32>,
+    pub visibility: Visibility,
+    /// average of review ratings, `None` without reviews
+    #[schema(value_type = Option<String>)]
+    pub rating_avg: Option<BigDecimal>,
+    pub rating_count: i32,
+    /// incremented on every change, the entity tag of the recipe
+    pub version: i32,
 }
 
 #[derive(Insertable, ToSchema, Serialize, Deserialize)]
@@ -27,6 +71,8 @@ pub struct NewRecipe {
     pub preparation_needed: bool,
     pub portions: i32,
     pub difficulty: i32,
+    pub owner_id: Option<i32>,
+    pub visibility: Visibility,
 }
 
 #[derive(ToSchema, AsChangeset, Deserialize)]
@@ -39,4 +85,28 @@ pub struct ChangeRecipe {
     pub preparation_needed: Option<bool>,
     pub portions: Option<i32>,
     pub difficulty: Option<i32>,
+    pub visibility: Option<Visibility>,
+}
+
+impl ToSql<RecipeVisibility, Pg> for Visibility {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value: &[u8] = match self {
+            Visibility::Private => b"private",
+            Visibility::Shared => b"shared",
+            Visibility::Public => b"public",
+        };
+        out.write_all(value)?;
+        return Ok(IsNull::No);
+    }
+}
+
+impl FromSql<RecipeVisibility, Pg> for Visibility {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        return match bytes.as_bytes() {
+            b"private" => Ok(Visibility::Private),
+            b"shared" => Ok(Visibility::Shared),
+            b"public" => Ok(Visibility::Public),
+            _ => Err("Unrecognized recipe visibility".into()),
+        };
+    }
 }
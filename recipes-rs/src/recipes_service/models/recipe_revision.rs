@@ -0,0 +1,70 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use diesel::{Associations, Identifiable, Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::recipes_service::models::recipe::{Recipe, Visibility};
+use crate::recipes_service::schema::recipe_revisions;
+
+/// state of a recipe after a create, update or restore, numbered from 1 per recipe
+#[derive(Queryable, Selectable, Identifiable, Associations, Serialize, Debug)]
+#[diesel(table_name = recipe_revisions)]
+#[diesel(belongs_to(Recipe))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RecipeRevision {
+    pub id: i32,
+    pub recipe_id: i32,
+    pub revision: i32,
+    /// author of the change, `None` when the user was removed
+    pub user_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    /// serialized RecipeSnapshot
+    pub snapshot: serde_json::Value,
+}
+
+#[derive(Insertable, Serialize)]
+#[diesel(table_name = recipe_revisions)]
+pub struct NewRecipeRevision {
+    pub recipe_id: i32,
+    pub revision: i32,
+    pub user_id: Option<i32>,
+    pub snapshot: serde_json::Value,
+}
+
+/// recipe fields, categories, ingredients and steps at the time of a revision
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct RecipeSnapshot {
+    pub name: String,
+    pub instructions: String,
+    pub cuisine: String,
+    pub duration_min: i32,
+    pub preparation_needed: bool,
+    pub portions: i32,
+    pub difficulty: i32,
+    pub visibility: Visibility,
+    /// category names, ordered by name
+    pub categories: Vec<String>,
+    /// ordered by part and ingredient name
+    pub ingredients: Vec<SnapshotIngredient>,
+    pub steps: Vec<SnapshotStep>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct SnapshotIngredient {
+    /// followed on restore so renamed ingredients are found
+    pub id: i32,
+    pub name: String,
+    pub part: i16,
+    #[schema(value_type = String)]
+    pub quantity: BigDecimal,
+    pub unit: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug)]
+pub struct SnapshotStep {
+    pub text: String,
+    pub duration_min: Option<i32>,
+    /// names of the ingredients used in the step
+    pub ingredients: Vec<String>,
+}
@@ -0,0 +1,57 @@
+use crate::recipes_service::models::{recipe::Recipe, user::User};
+use crate::recipes_service::schema::{recipe_shares, sql_types::ShareAccess};
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    prelude::*,
+    serialize::{self, IsNull, Output, ToSql},
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use utoipa::ToSchema;
+
+/// what a user the recipe is shared with may do
+#[derive(
+    AsExpression, FromSqlRow, ToSchema, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug,
+)]
+#[diesel(sql_type = ShareAccess)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    Read,
+    /// change the recipe and its image, but not delete or share it
+    Edit,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Associations, Insertable, Serialize, Debug)]
+#[diesel(table_name = recipe_shares)]
+#[diesel(belongs_to(Recipe))]
+#[diesel(belongs_to(User))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(recipe_id, user_id))]
+pub struct RecipeShare {
+    pub recipe_id: i32,
+    pub user_id: i32,
+    pub access: Access,
+}
+
+impl ToSql<ShareAccess, Pg> for Access {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value: &[u8] = match self {
+            Access::Read => b"read",
+            Access::Edit => b"edit",
+        };
+        out.write_all(value)?;
+        return Ok(IsNull::No);
+    }
+}
+
+impl FromSql<ShareAccess, Pg> for Access {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        return match bytes.as_bytes() {
+            b"read" => Ok(Access::Read),
+            b"edit" => Ok(Access::Edit),
+            _ => Err("Unrecognized share access".into()),
+        };
+    }
+}
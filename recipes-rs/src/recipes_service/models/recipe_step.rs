@@ -0,0 +1,30 @@
+use diesel::{Associations, Identifiable, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::recipes_service::models::recipe::Recipe;
+use crate::recipes_service::schema::recipe_steps;
+
+/// numbered instruction step of a recipe
+#[derive(Queryable, Selectable, Identifiable, Associations, Insertable, Serialize, Debug)]
+#[diesel(table_name = recipe_steps)]
+#[diesel(belongs_to(Recipe))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(recipe_id, ordinal))]
+pub struct RecipeStep {
+    pub recipe_id: i32,
+    /// position of the step, from 1
+    pub ordinal: i32,
+    pub text: String,
+    /// time the step takes, e.g. for a timer
+    pub duration_min: Option<i32>,
+    /// ingredients of the recipe used in the step
+    pub ingredient_ids: Vec<i32>,
+}
+
+/// helper to build RecipeStep insert, numbered by its position
+#[derive(Serialize, Debug)]
+pub struct NewRecipeStep {
+    pub text: String,
+    pub duration_min: Option<i32>,
+    pub ingredient_ids: Vec<i32>,
+}
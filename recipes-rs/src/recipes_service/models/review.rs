@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use diesel::{prelude::AsChangeset, Associations, Identifiable, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::recipes_service::models::{recipe::Recipe, user::User};
+use crate::recipes_service::schema::reviews;
+
+/// rating of a recipe by a user, one per user and recipe
+#[derive(Queryable, Selectable, Identifiable, Associations, Serialize, Debug)]
+#[diesel(table_name = reviews)]
+#[diesel(belongs_to(Recipe))]
+#[diesel(belongs_to(User))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(recipe_id, user_id))]
+pub struct Review {
+    pub recipe_id: i32,
+    pub user_id: i32,
+    /// 1 to 5 stars
+    pub rating: i16,
+    pub text: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Serialize)]
+#[diesel(table_name = reviews)]
+pub struct NewReview<'a> {
+    pub recipe_id: i32,
+    pub user_id: i32,
+    pub rating: i16,
+    pub text: Option<&'a str>,
+}
+
+/// replaces rating and text, a missing text is cleared
+#[derive(AsChangeset)]
+#[diesel(table_name = reviews)]
+#[diesel(treat_none_as_null = true)]
+pub struct ChangeReview<'a> {
+    pub rating: i16,
+    pub text: Option<&'a str>,
+    pub updated_at: DateTime<Utc>,
+}
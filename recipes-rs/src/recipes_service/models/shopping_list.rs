@@ -0,0 +1,47 @@
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use diesel::{Associations, Identifiable, Insertable, Queryable, Selectable};
+use serde::Serialize;
+
+use crate::recipes_service::models::user::User;
+use crate::recipes_service::schema::{shopping_list_items, shopping_lists};
+
+#[derive(Queryable, Selectable, Identifiable, Associations, Serialize, Debug)]
+#[diesel(table_name = shopping_lists)]
+#[diesel(belongs_to(User))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ShoppingList {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = shopping_lists)]
+pub struct NewShoppingList<'a> {
+    pub user_id: i32,
+    pub name: &'a str,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Associations, Serialize, Debug)]
+#[diesel(table_name = shopping_list_items)]
+#[diesel(belongs_to(ShoppingList))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ShoppingListItem {
+    pub id: i32,
+    pub shopping_list_id: i32,
+    pub name: String,
+    pub quantity: BigDecimal,
+    pub unit: String,
+    pub checked: bool,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = shopping_list_items)]
+pub struct NewShoppingListItem {
+    pub shopping_list_id: i32,
+    pub name: String,
+    pub quantity: BigDecimal,
+    pub unit: String,
+}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    pg::{Pg, PgValue},
+    serialize::{self, IsNull, Output, ToSql},
+    Associations, Identifiable, Insertable, Queryable, Selectable,
+};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use utoipa::ToSchema;
+
+use crate::recipes_service::schema::{sessions, sql_types, user_roles, users};
+
+#[derive(
+    AsExpression,
+    FromSqlRow,
+    ToSchema,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+)]
+#[diesel(sql_type = sql_types::UserRole)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// manages users and their roles
+    Admin,
+    /// maintains the category catalogue
+    Editor,
+    Viewer,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Serialize, Debug)]
+#[diesel(table_name = users)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct User {
+    pub id: i32,
+    pub username: String,
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = users)]
+pub struct NewUser<'a> {
+    pub username: &'a str,
+    pub password_hash: &'a str,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Associations, Debug)]
+#[diesel(table_name = sessions)]
+#[diesel(belongs_to(User))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(token_hash))]
+pub struct Session {
+    pub token_hash: String,
+    pub user_id: i32,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = sessions)]
+pub struct NewSession<'a> {
+    pub token_hash: &'a str,
+    pub user_id: i32,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Queryable, Selectable, Identifiable, Associations, Insertable, Debug)]
+#[diesel(table_name = user_roles)]
+#[diesel(belongs_to(User))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(primary_key(user_id, role))]
+pub struct UserRole {
+    pub user_id: i32,
+    pub role: Role,
+}
+
+impl ToSql<sql_types::UserRole, Pg> for Role {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value: &[u8] = match self {
+            Role::Admin => b"admin",
+            Role::Editor => b"editor",
+            Role::Viewer => b"viewer",
+        };
+        out.write_all(value)?;
+        return Ok(IsNull::No);
+    }
+}
+
+impl FromSql<sql_types::UserRole, Pg> for Role {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        return match bytes.as_bytes() {
+            b"admin" => Ok(Role::Admin),
+            b"editor" => Ok(Role::Editor),
+            b"viewer" => Ok(Role::Viewer),
+            _ => Err("Unrecognized user role".into()),
+        };
+    }
+}
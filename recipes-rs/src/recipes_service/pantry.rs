@@ -0,0 +1,516 @@
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDate, Utc};
+use diesel::dsl::{case_when, count_star};
+use diesel::prelude::*;
+use diesel::sql_types::Integer;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::{debug, info};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::ingredients::get_or_create_ingredient;
+use super::models::ingredient::Ingredient;
+use super::models::pantry_item::{
+    ChangePantryItem, NewPantryIngredient, NewPantryItem, PantryItem,
+};
+use super::models::recipe::Recipe;
+use super::recipes::{filter_recipes, RecipeFilter};
+use super::schema::{ingredients, pantry_items, recipe_ingredient, recipes};
+use super::units::{find_unit, to_base_amount, to_kind_amount};
+use super::utils::{get_connection, Page};
+
+/// Most recipes ranked in memory. Candidates are preselected in SQL by the share of their
+/// ingredients the pantry has at all, quantities are compared only for them.
+const MAX_COOKABLE_CANDIDATES: i64 = 500;
+
+/// recipe ranked by how much of it can be cooked from the pantry
+pub struct CookableRecipe {
+    pub recipe: Recipe,
+    /// number of recipe ingredients the pantry has enough of
+    pub available: usize,
+    /// number of all recipe ingredients
+    pub total: usize,
+    /// names of ingredients to buy, ordered by name
+    pub missing: Vec<String>,
+    /// earliest expiry of the pantry items the recipe uses
+    pub expires_on: Option<NaiveDate>,
+}
+
+impl CookableRecipe {
+    /// fraction of the recipe ingredients available in the pantry
+    pub fn fraction(&self) -> f64 {
+        return self.available as f64 / self.total as f64;
+    }
+}
+
+/// pantry items of the user, soonest expiring first
+pub async fn list_pantry_items(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+) -> Result<Vec<(PantryItem, Ingredient)>, ServiceError> {
+    info!(user_id; "Listing pantry items");
+    let mut connection = get_connection(db_pool).await?;
+    return Ok(pantry_items::table
+        .inner_join(ingredients::table)
+        .filter(pantry_items::user_id.eq(user_id))
+        .select((PantryItem::as_select(), Ingredient::as_select()))
+        .order_by((
+            pantry_items::expires_on.asc().nulls_last(),
+            ingredients::name,
+            pantry_items::id,
+        ))
+        .load(&mut connection)
+        .await?);
+}
+
+pub async fn get_pantry_item(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    item_id: &i32,
+) -> Result<(PantryItem, Ingredient), ServiceError> {
+    info!(user_id, item_id; "Getting pantry item");
+    let mut connection = get_connection(db_pool).await?;
+    return Ok(pantry_items::table
+        .inner_join(ingredients::table)
+        .filter(pantry_items::id.eq(item_id))
+        .filter(pantry_items::user_id.eq(user_id))
+        .select((PantryItem::as_select(), Ingredient::as_select()))
+        .first(&mut connection)
+        .await?);
+}
+
+/// add an item to the pantry, the ingredient is created when it does not exist yet
+pub async fn add_pantry_item(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    pantry_ingredient: &NewPantryIngredient<'_>,
+) -> Result<(PantryItem, Ingredient), ServiceError> {
+    info!(user_id, pantry_item:serde = pantry_ingredient; "Adding pantry item");
+    let unit = check_pantry_ingredient(pantry_ingredient)?;
+
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let ingredient =
+                    get_or_create_ingredient(connection, pantry_ingredient.name.trim()).await?;
+                let item = diesel::insert_into(pantry_items::table)
+                    .values(NewPantryItem {
+                        user_id: *user_id,
+                        ingredient_id: ingredient.id,
+                        quantity: pantry_ingredient.quantity.clone(),
+                        unit: unit.to_string(),
+                        expires_on: pantry_ingredient.expires_on,
+                    })
+                    .returning(PantryItem::as_returning())
+                    .get_result(connection)
+                    .await?;
+
+                return Ok((item, ingredient));
+            })
+        })
+        .await;
+}
+
+/// replace ingredient, quantity, unit and expiry of a pantry item
+pub async fn update_pantry_item(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    item_id: &i32,
+    pantry_ingredient: &NewPantryIngredient<'_>,
+) -> Result<(PantryItem, Ingredient), ServiceError> {
+    info!(user_id, item_id, pantry_item:serde = pantry_ingredient; "Changing pantry item");
+    let unit = check_pantry_ingredient(pantry_ingredient)?;
+
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let ingredient =
+                    get_or_create_ingredient(connection, pantry_ingredient.name.trim()).await?;
+                let item = diesel::update(
+                    pantry_items::table
+                        .find(item_id)
+                        .filter(pantry_items::user_id.eq(user_id)),
+                )
+                .set(ChangePantryItem {
+                    ingredient_id: ingredient.id,
+                    quantity: pantry_ingredient.quantity.clone(),
+                    unit: unit.to_string(),
+                    expires_on: pantry_ingredient.expires_on,
+                })
+                .returning(PantryItem::as_returning())
+                .get_result(connection)
+                .await?;
+
+                return Ok((item, ingredient));
+            })
+        })
+        .await;
+}
+
+pub async fn delete_pantry_item(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    item_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(user_id, item_id; "Deleting pantry item");
+    let mut connection = get_connection(db_pool).await?;
+    let deleted = diesel::delete(
+        pantry_items::table
+            .find(item_id)
+            .filter(pantry_items::user_id.eq(user_id)),
+    )
+    .execute(&mut connection)
+    .await?;
+    if deleted == 0 {
+        return Err(ServiceError::DbDiesel(diesel::result::Error::NotFound));
+    }
+
+    return Ok(());
+}
+
+/// Rank recipes visible to the user by the fraction of their ingredients available in the
+/// pantry. Expired items do not count. Recipes using none of the pantry are left out, ties
+/// prefer recipes using up the soonest expiring items. Only the best
+/// [`MAX_COOKABLE_CANDIDATES`] recipes by ingredient presence are ranked, so the returned total
+/// never exceeds it.
+pub async fn list_cookable_recipes(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    filter: &RecipeFilter,
+    page: &Page,
+) -> Result<(Vec<CookableRecipe>, i64), ServiceError> {
+    info!(user_id, filter:serde; "Listing cookable recipes");
+    let mut connection = get_connection(db_pool).await?;
+
+    let today = Utc::now().date_naive();
+    let pantry: Vec<PantryItem> = pantry_items::table
+        .filter(pantry_items::user_id.eq(user_id))
+        .filter(
+            pantry_items::expires_on
+                .is_null()
+                .or(pantry_items::expires_on.ge(today)),
+        )
+        .select(PantryItem::as_select())
+        .load(&mut connection)
+        .await?;
+    let mut pantry_by_ingredient: HashMap<i32, Vec<&PantryItem>> = HashMap::new();
+    for item in &pantry {
+        pantry_by_ingredient
+            .entry(item.ingredient_id)
+            .or_default()
+            .push(item);
+    }
+    let pantry_ingredient_ids: Vec<i32> = pantry_by_ingredient.keys().copied().collect();
+    debug!(items = pantry.len(), ingredients = pantry_ingredient_ids.len(); "Loaded pantry");
+
+    // per mille of the recipe ingredients in the pantry, ignoring quantities
+    let present_share = diesel::dsl::sum(
+        case_when::<_, _, Integer>(
+            recipe_ingredient::ingredient_id.eq_any(&pantry_ingredient_ids),
+            1,
+        )
+        .otherwise(0),
+    )
+    .assume_not_null()
+        * 1000_i64
+        / count_star();
+    let candidate_ids: Vec<i32> = recipe_ingredient::table
+        .filter(
+            recipe_ingredient::recipe_id.eq_any(
+                filter_recipes(&Some(*user_id), filter)
+                    .filter(
+                        recipes::id.eq_any(
+                            recipe_ingredient::table
+                                .select(recipe_ingredient::recipe_id)
+                                .filter(
+                                    recipe_ingredient::ingredient_id.eq_any(&pantry_ingredient_ids),
+                                ),
+                        ),
+                    )
+                    .select(recipes::id),
+            ),
+        )
+        .group_by(recipe_ingredient::recipe_id)
+        .select(recipe_ingredient::recipe_id)
+        .order_by((present_share.desc(), recipe_ingredient::recipe_id))
+        .limit(MAX_COOKABLE_CANDIDATES)
+        .load(&mut connection)
+        .await?;
+    let candidates: Vec<Recipe> = recipes::table
+        .filter(recipes::id.eq_any(&candidate_ids))
+        .select(Recipe::as_select())
+        .load(&mut connection)
+        .await?;
+    let rows: Vec<(i32, i32, String, BigDecimal, String)> = recipe_ingredient::table
+        .inner_join(ingredients::table)
+        .filter(recipe_ingredient::recipe_id.eq_any(&candidate_ids))
+        .select((
+            recipe_ingredient::recipe_id,
+            recipe_ingredient::ingredient_id,
+            ingredients::name,
+            recipe_ingredient::quantity,
+            recipe_ingredient::unit,
+        ))
+        .order_by(ingredients::name)
+        .load(&mut connection)
+        .await?;
+
+    let cookable = rank_cookable(candidates, &rows, &pantry_by_ingredient);
+    let total = cookable.len() as i64;
+    let cookable = cookable
+        .into_iter()
+        .skip(page.offset() as usize)
+        .take(page.limit as usize)
+        .collect();
+
+    return Ok((cookable, total));
+}
+
+/// Rank candidates by how many of their ingredients, rows of `(recipe_id, ingredient_id, name,
+/// quantity, unit)` ordered by name, the pantry has enough of. Recipes without any are left out.
+fn rank_cookable(
+    candidates: Vec<Recipe>,
+    rows: &[(i32, i32, String, BigDecimal, String)],
+    pantry_by_ingredient: &HashMap<i32, Vec<&PantryItem>>,
+) -> Vec<CookableRecipe> {
+    let mut cookable: Vec<CookableRecipe> = candidates
+        .into_iter()
+        .map(|recipe| {
+            let mut cookable = CookableRecipe {
+                available: 0,
+                total: 0,
+                missing: vec![],
+                expires_on: None,
+                recipe,
+            };
+            for (_, ingredient_id, name, quantity, unit) in
+                rows.iter().filter(|row| row.0 == cookable.recipe.id)
+            {
+                cookable.total += 1;
+                let items = pantry_by_ingredient
+                    .get(ingredient_id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                if !has_enough(items, name, quantity, unit) {
+                    cookable.missing.push(name.clone());
+                    continue;
+                }
+                cookable.available += 1;
+                cookable.expires_on = items
+                    .iter()
+                    .filter_map(|item| item.expires_on)
+                    .chain(cookable.expires_on)
+                    .min();
+            }
+            cookable
+        })
+        .filter(|cookable| cookable.available > 0)
+        .collect();
+
+    cookable.sort_by(|a, b| {
+        // compare fractions without floating point, both totals are positive
+        return (b.available * a.total)
+            .cmp(&(a.available * b.total))
+            .then_with(|| match (a.expires_on, b.expires_on) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            })
+            .then_with(|| a.missing.len().cmp(&b.missing.len()))
+            .then_with(|| a.recipe.id.cmp(&b.recipe.id));
+    });
+
+    return cookable;
+}
+
+/// Whether pantry items cover the quantity of the ingredient. Mass and volume units convert
+/// within their kind and between them by the density of the ingredient, other units compare
+/// only with the same unit. Without any comparable item, e.g. eggs counted in the recipe but
+/// weighed in the pantry, the ingredient is missing.
+fn has_enough(items: &[&PantryItem], name: &str, quantity: &BigDecimal, unit: &str) -> bool {
+    let (comparable, needed): (Vec<BigDecimal>, BigDecimal) = match to_base_amount(quantity, unit) {
+        Some((kind, needed)) => (
+            items
+                .iter()
+                .filter_map(|item| to_kind_amount(&item.quantity, &item.unit, kind, name))
+                .collect(),
+            needed,
+        ),
+        None => (
+            items
+                .iter()
+                .filter(|item| item.unit == unit)
+                .map(|item| item.quantity.clone())
+                .collect(),
+            quantity.clone(),
+        ),
+    };
+    if comparable.is_empty() {
+        return false;
+    }
+
+    return comparable.into_iter().sum::<BigDecimal>() >= needed;
+}
+
+/// validate a pantry item, returns the canonical unit
+fn check_pantry_ingredient(
+    pantry_ingredient: &NewPantryIngredient<'_>,
+) -> Result<&'static str, ServiceError> {
+    if pantry_ingredient.name.trim().is_empty() {
+        return Err(ServiceError::InvalidValue {
+            field: "name",
+            detail: "must not be empty".to_string(),
+        });
+    }
+    if pantry_ingredient.quantity <= &BigDecimal::from(0) {
+        return Err(ServiceError::InvalidValue {
+            field: "quantity",
+            detail: "must be positive".to_string(),
+        });
+    }
+    let unit = find_unit(pantry_ingredient.unit).ok_or_else(|| ServiceError::InvalidValue {
+        field: "unit",
+        detail: format!("Unknown unit '{}'", pantry_ingredient.unit),
+    })?;
+
+    return Ok(unit.code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes_service::test_utils::{decimal, recipe};
+
+    fn item(ingredient_id: i32, quantity: &str, unit: &str) -> PantryItem {
+        return PantryItem {
+            id: ingredient_id,
+            user_id: 1,
+            ingredient_id,
+            quantity: decimal(quantity),
+            unit: unit.to_string(),
+            expires_on: None,
+        };
+    }
+
+    fn enough(items: &[PantryItem], quantity: &str, unit: &str) -> bool {
+        let items: Vec<&PantryItem> = items.iter().collect();
+        return has_enough(&items, "flour", &decimal(quantity), unit);
+    }
+
+    /// ids of the ranked recipes, recipe ingredients given as `(recipe_id, ingredient_id)` of 1 pc
+    fn ranked(recipe_ingredients: &[(i32, i32)], pantry: &[PantryItem]) -> Vec<i32> {
+        let mut recipe_ids: Vec<i32> = recipe_ingredients.iter().map(|(id, _)| *id).collect();
+        recipe_ids.dedup();
+        let rows: Vec<(i32, i32, String, BigDecimal, String)> = recipe_ingredients
+            .iter()
+            .map(|(recipe_id, ingredient_id)| {
+                let name = format!("ingredient {ingredient_id}");
+                return (
+                    *recipe_id,
+                    *ingredient_id,
+                    name,
+                    decimal("1"),
+                    "pc".to_string(),
+                );
+            })
+            .collect();
+        let mut pantry_by_ingredient: HashMap<i32, Vec<&PantryItem>> = HashMap::new();
+        for item in pantry {
+            pantry_by_ingredient
+                .entry(item.ingredient_id)
+                .or_default()
+                .push(item);
+        }
+
+        let candidates = recipe_ids.into_iter().map(recipe).collect();
+        return rank_cookable(candidates, &rows, &pantry_by_ingredient)
+            .iter()
+            .map(|cookable| cookable.recipe.id)
+            .collect();
+    }
+
+    #[test]
+    fn compares_quantities_within_a_unit_kind() {
+        let pantry = [item(1, "0.5", "kg"), item(1, "200", "g")];
+        assert!(enough(&pantry, "700", "g"));
+        assert!(!enough(&pantry, "701", "g"));
+        assert!(enough(&[item(1, "1", "l")], "4", "cup"));
+        assert!(!enough(&[item(1, "1", "l")], "5", "cup"));
+    }
+
+    #[test]
+    fn compares_counted_quantities_by_unit() {
+        assert!(enough(&[item(1, "3", "pc")], "2", "pc"));
+        assert!(!enough(&[item(1, "1", "pc")], "2", "pc"));
+        assert!(!enough(
+            &[item(1, "1", "clove"), item(1, "5", "pc")],
+            "2",
+            "clove"
+        ));
+    }
+
+    #[test]
+    fn converts_between_mass_and_volume_by_density() {
+        // flour weighs 0.53 g/ml
+        assert!(enough(&[item(1, "1", "l")], "530", "g"));
+        assert!(!enough(&[item(1, "1", "l")], "531", "g"));
+        assert!(enough(
+            &[item(1, "0.5", "l"), item(1, "265", "g")],
+            "530",
+            "g"
+        ));
+        let pantry = [item(1, "1", "l")];
+        let items: Vec<&PantryItem> = pantry.iter().collect();
+        assert!(!has_enough(&items, "eggs", &decimal("1"), "g"));
+    }
+
+    #[test]
+    fn counts_ingredients_without_comparable_items_as_missing() {
+        // eggs counted in the recipe but weighed in the pantry
+        assert!(!enough(&[item(1, "100", "g")], "6", "pc"));
+        assert!(!enough(&[], "1", "pc"));
+    }
+
+    #[test]
+    fn ranks_by_available_fraction() {
+        let pantry = [item(1, "1", "pc"), item(2, "1", "pc")];
+        // 1/3, 2/2, 1/2 and 0/1 of the ingredients available
+        let recipe_ingredients = [
+            (1, 1),
+            (1, 3),
+            (1, 4),
+            (2, 1),
+            (2, 2),
+            (3, 2),
+            (3, 3),
+            (4, 3),
+        ];
+        assert_eq!(ranked(&recipe_ingredients, &pantry), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn breaks_ties_by_expiry_and_missing_ingredients() {
+        let mut expiring = item(2, "1", "pc");
+        expiring.expires_on = NaiveDate::from_ymd_opt(2026, 10, 20);
+        let pantry = [item(1, "1", "pc"), expiring, item(5, "1", "pc")];
+        // all 1/2, recipe 3 uses the expiring item, recipe 1 misses fewer ingredients than 2
+        let recipe_ingredients = [
+            (1, 1),
+            (1, 3),
+            (2, 1),
+            (2, 3),
+            (2, 4),
+            (2, 5),
+            (3, 2),
+            (3, 3),
+        ];
+        assert_eq!(ranked(&recipe_ingredients, &pantry), vec![3, 1, 2]);
+    }
+}
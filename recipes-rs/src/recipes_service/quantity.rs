@@ -0,0 +1,163 @@
+use bigdecimal::{BigDecimal, RoundingMode};
+use std::str::FromStr;
+
+/// number of decimal places stored in `recipe_ingredient.quantity`
+pub const QUANTITY_SCALE: i64 = 3;
+
+/// largest quantity fitting the NUMERIC(10, 3) quantity columns
+pub fn max_quantity() -> BigDecimal {
+    return BigDecimal::new(9_999_999_999_i64.into(), QUANTITY_SCALE);
+}
+
+const VULGAR_FRACTIONS: [(char, i64, i64); 15] = [
+    ('½', 1, 2),
+    ('⅓', 1, 3),
+    ('⅔', 2, 3),
+    ('¼', 1, 4),
+    ('¾', 3, 4),
+    ('⅕', 1, 5),
+    ('⅖', 2, 5),
+    ('⅗', 3, 5),
+    ('⅘', 4, 5),
+    ('⅙', 1, 6),
+    ('⅚', 5, 6),
+    ('⅛', 1, 8),
+    ('⅜', 3, 8),
+    ('⅝', 5, 8),
+    ('⅞', 7, 8),
+];
+
+/// Parse a quantity written as a decimal ("1.5", "1,5"), a fraction ("3/4"), a mixed number
+/// ("1 1/2") or with unicode fractions ("1½"). The result is rounded to [`QUANTITY_SCALE`].
+/// Signs, exponents and quantities above [`max_quantity`] are rejected.
+pub fn parse_quantity(text: &str) -> Option<BigDecimal> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    // split off a trailing unicode fraction, e.g. "1½" or "1 ½"
+    let mut quantity = BigDecimal::from(0);
+    let mut rest = text;
+    if let Some(last) = text.chars().last() {
+        if let Some((_, numerator, denominator)) =
+            VULGAR_FRACTIONS.iter().find(|(c, _, _)| *c == last)
+        {
+            quantity += BigDecimal::from(*numerator) / BigDecimal::from(*denominator);
+            rest = text[..text.len() - last.len_utf8()].trim_end();
+        }
+    }
+
+    let mut words = rest.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (None, _, _) => {}
+        (Some(number), None, _) => quantity += parse_number(number)?,
+        // mixed number "1 1/2"
+        (Some(whole), Some(fraction), None) if fraction.contains('/') => {
+            quantity += parse_decimal(whole)? + parse_fraction(fraction)?
+        }
+        _ => return None,
+    }
+
+    // bound before rounding, which allocates digits up to the scale
+    if quantity > max_quantity() {
+        return None;
+    }
+    let quantity = quantity.with_scale_round(QUANTITY_SCALE, RoundingMode::HalfUp);
+    if quantity > max_quantity() {
+        return None;
+    }
+
+    return Some(quantity);
+}
+
+fn parse_number(text: &str) -> Option<BigDecimal> {
+    if text.contains('/') {
+        return parse_fraction(text);
+    }
+
+    return parse_decimal(text);
+}
+
+/// plain decimal of digits and one separator, without sign or exponent
+fn parse_decimal(text: &str) -> Option<BigDecimal> {
+    let text = text.replace(',', ".");
+    let valid = text.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && text.chars().filter(|c| *c == '.').count() <= 1
+        && text.chars().any(|c| c.is_ascii_digit());
+    if !valid {
+        return None;
+    }
+
+    return BigDecimal::from_str(&text).ok();
+}
+
+fn parse_fraction(text: &str) -> Option<BigDecimal> {
+    let (numerator, denominator) = text.split_once('/')?;
+    let numerator = parse_decimal(numerator)?;
+    let denominator = parse_decimal(denominator)?;
+    if denominator == BigDecimal::from(0) {
+        return None;
+    }
+
+    return Some(numerator / denominator);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantity(text: &str) -> Option<String> {
+        return parse_quantity(text).map(|quantity| quantity.normalized().to_string());
+    }
+
+    #[test]
+    fn parses_decimals() {
+        assert_eq!(quantity("2"), Some("2".to_string()));
+        assert_eq!(quantity(" 1.5 "), Some("1.5".to_string()));
+        assert_eq!(quantity("1,25"), Some("1.25".to_string()));
+        assert_eq!(quantity(".5"), Some("0.5".to_string()));
+    }
+
+    #[test]
+    fn parses_fractions() {
+        assert_eq!(quantity("3/4"), Some("0.75".to_string()));
+        assert_eq!(quantity("1 1/2"), Some("1.5".to_string()));
+        assert_eq!(quantity("1/3"), Some("0.333".to_string()));
+        assert_eq!(quantity("2/3"), Some("0.667".to_string()));
+    }
+
+    #[test]
+    fn parses_unicode_fractions() {
+        assert_eq!(quantity("½"), Some("0.5".to_string()));
+        assert_eq!(quantity("1½"), Some("1.5".to_string()));
+        assert_eq!(quantity("2 ¾"), Some("2.75".to_string()));
+    }
+
+    #[test]
+    fn rejects_invalid_text() {
+        assert_eq!(quantity(""), None);
+        assert_eq!(quantity("some"), None);
+        assert_eq!(quantity("1/0"), None);
+        assert_eq!(quantity("1 2 3"), None);
+        assert_eq!(quantity("1.2.3"), None);
+    }
+
+    #[test]
+    fn rejects_signs_and_exponents() {
+        assert_eq!(quantity("-1"), None);
+        assert_eq!(quantity("+1"), None);
+        assert_eq!(quantity("-1 1/2"), None);
+        assert_eq!(quantity("1 -1/2"), None);
+        assert_eq!(quantity("1e3"), None);
+        assert_eq!(quantity("1e999999999"), None);
+    }
+
+    #[test]
+    fn bounds_magnitude() {
+        assert_eq!(quantity("9999999.999"), Some("9999999.999".to_string()));
+        assert_eq!(quantity("10000000"), None);
+        // rounds up past the column limit
+        assert_eq!(quantity("9999999.9999"), None);
+    }
+}
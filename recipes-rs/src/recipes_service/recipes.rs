@@ -1,55 +1,171 @@
+use bigdecimal::BigDecimal;
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::sql_types::{Float, Nullable, Text};
+use diesel::upsert::excluded;
 use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
 use log::{debug, info};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::ToSchema;
 
 use super::errors::ServiceError;
+use super::ingredients::get_or_create_ingredient;
 use super::models::category::{Category, RecipeCategory};
 use super::models::image::{Image, NewImage, UpdateImage};
-use super::models::ingredient::{Ingredient, NewIngredient, NewRecipeIngredient, RecipeIngredient};
-use super::models::recipe::{ChangeRecipe, NewRecipe, Recipe};
+use super::models::ingredient::{Ingredient, NewRecipeIngredient, RecipeIngredient, RecipePart};
+use super::models::recipe::{ChangeRecipe, NewRecipe, Recipe, Visibility};
+use super::models::recipe_share::{Access, RecipeShare};
+use super::models::recipe_step::{NewRecipeStep, RecipeStep};
+use super::models::user::Role;
+use super::revisions::record_revision;
+use super::scaling::scale_parts;
 use super::schema::categories;
+use super::schema::collection_recipes;
+use super::schema::collections;
+use super::schema::favourites;
 use super::schema::images;
 use super::schema::ingredients;
 use super::schema::recipe_category;
 use super::schema::recipe_ingredient;
+use super::schema::recipe_shares;
+use super::schema::recipe_steps;
 use super::schema::recipes;
-use super::utils::get_connection;
+use super::schema::user_roles;
+use super::search::{
+    any_word_query, coalesce, escape_html, search_config, to_tsquery, ts_headline, ts_rank,
+    Matches, HEADLINE_OPTIONS,
+};
+use super::units::find_unit;
+use super::users::load_roles;
+use super::utils::{get_connection, Page};
 
+/// filters of the recipe listing, `None` means not filtered
+#[derive(Default, Serialize)]
+pub struct RecipeFilter {
+    /// full-text search in name, instructions and ingredient names
+    pub q: Option<String>,
+    pub category: Option<String>,
+    pub cuisine: Option<String>,
+    pub min_duration: Option<i32>,
+    pub max_duration: Option<i32>,
+    /// lowest average review rating, excludes recipes without reviews
+    pub min_rating: Option<BigDecimal>,
+    /// only recipes starred by the user
+    pub favourites: bool,
+    /// only recipes in the collection of the user
+    pub collection: Option<i32>,
+}
+
+#[derive(Deserialize, ToSchema, Serialize, Default, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeSort {
+    #[default]
+    Id,
+    /// full-text search rank of the recipe text and its best matching ingredient name, `desc` puts
+    /// the best matches first and is the default order of this sort, same as `id` without search
+    /// query
+    Relevance,
+    Name,
+    DurationMin,
+    Difficulty,
+    /// average review rating, recipes without reviews come last in both orders
+    Rating,
+}
+
+#[derive(Deserialize, ToSchema, Serialize, Default, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// roles which may edit recipes without owner, admins also as owner
+const ORPHAN_MAINTAINERS: [Role; 2] = [Role::Admin, Role::Editor];
+
+/// what a user may do with a recipe, ordered from the least
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum RecipeAccess {
+    None,
+    Read,
+    Edit,
+    Owner,
+}
+
+/// list recipes visible to the user, `None` user is an anonymous caller
 pub async fn list_recipes(
     db_pool: Arc<Pool<AsyncPgConnection>>,
-    category_fitler: &Option<String>,
-    cuisine_filter: &Option<String>,
-    min_duration: &Option<i32>,
-    max_duration: &Option<i32>,
-) -> Result<Vec<(Recipe, Vec<Category>)>, ServiceError> {
-    info!("Listing recipes");
+    user_id: &Option<i32>,
+    filter: &RecipeFilter,
+    sort: &RecipeSort,
+    order: &SortOrder,
+    page: &Page,
+) -> Result<(Vec<(Recipe, Vec<Category>, Option<String>)>, i64), ServiceError> {
+    info!(user_id:serde, filter:serde, sort:serde, order:serde; "Listing recipes");
     let mut connection = get_connection(db_pool).await?;
-    let mut all_recipes = recipes::table.select(Recipe::as_select()).into_boxed();
 
-    if let Some(cuisine) = cuisine_filter {
-        debug!(cuisine; "Filtering by cuisine");
-        all_recipes = all_recipes.filter(recipes::cuisine.eq(cuisine));
-    }
-    if let Some(category) = category_fitler {
-        debug!(category; "Filtering by category");
-        let filtered_recipe_ids: Vec<i32> = recipe_category::table
-            .select(recipe_category::recipe_id)
-            .filter(recipe_category::category_name.eq(category))
-            .load(&mut connection)
-            .await?;
+    let total: i64 = filter_recipes(user_id, filter)
+        .count()
+        .get_result(&mut connection)
+        .await?;
 
-        all_recipes = all_recipes.filter(recipes::id.eq_any(filtered_recipe_ids));
-    }
-    if let Some(min_duration) = min_duration {
-        debug!(min_duration; "Filtering on minimum duration");
-        all_recipes = all_recipes.filter(recipes::duration_min.ge(min_duration));
-    }
-    if let Some(max_duration) = max_duration {
-        debug!(max_duration; "Filtering on maximum duration");
-        all_recipes = all_recipes.filter(recipes::duration_min.le(max_duration));
-    }
-    let all_recipes = all_recipes.load(&mut connection).await?;
+    let tsquery = filter.q.as_deref().and_then(any_word_query);
+    let snippet: Box<dyn BoxableExpression<recipes::table, Pg, SqlType = Nullable<Text>>> =
+        match &tsquery {
+            Some(tsquery) => Box::new(
+                ts_headline(
+                    search_config(),
+                    escape_html(recipes::instructions),
+                    to_tsquery(search_config(), tsquery.clone()),
+                    HEADLINE_OPTIONS,
+                )
+                .nullable(),
+            ),
+            None => Box::new(None::<String>.into_sql::<Nullable<Text>>()),
+        };
+
+    let mut all_recipes = filter_recipes(user_id, filter).select((Recipe::as_select(), snippet));
+    all_recipes = match (sort, order, &tsquery) {
+        (RecipeSort::Id, _, _) | (RecipeSort::Relevance, _, None) => all_recipes,
+        (RecipeSort::Relevance, SortOrder::Asc, Some(tsquery)) => {
+            all_recipes.order_by(relevance(tsquery).asc())
+        }
+        (RecipeSort::Relevance, SortOrder::Desc, Some(tsquery)) => {
+            all_recipes.order_by(relevance(tsquery).desc())
+        }
+        (RecipeSort::Name, SortOrder::Asc, _) => all_recipes.order_by(recipes::name.asc()),
+        (RecipeSort::Name, SortOrder::Desc, _) => all_recipes.order_by(recipes::name.desc()),
+        (RecipeSort::DurationMin, SortOrder::Asc, _) => {
+            all_recipes.order_by(recipes::duration_min.asc())
+        }
+        (RecipeSort::DurationMin, SortOrder::Desc, _) => {
+            all_recipes.order_by(recipes::duration_min.desc())
+        }
+        (RecipeSort::Difficulty, SortOrder::Asc, _) => {
+            all_recipes.order_by(recipes::difficulty.asc())
+        }
+        (RecipeSort::Difficulty, SortOrder::Desc, _) => {
+            all_recipes.order_by(recipes::difficulty.desc())
+        }
+        (RecipeSort::Rating, SortOrder::Asc, _) => {
+            all_recipes.order_by(recipes::rating_avg.asc().nulls_last())
+        }
+        (RecipeSort::Rating, SortOrder::Desc, _) => {
+            all_recipes.order_by(recipes::rating_avg.desc().nulls_last())
+        }
+    };
+    // id is the tie-breaker so pages are stable
+    all_recipes = match order {
+        SortOrder::Asc => all_recipes.then_order_by(recipes::id.asc()),
+        SortOrder::Desc => all_recipes.then_order_by(recipes::id.desc()),
+    };
+    let all_recipes = all_recipes.offset(page.offset()).limit(page.limit);
+    let (all_recipes, snippets): (Vec<Recipe>, Vec<Option<String>>) = all_recipes
+        .load::<(Recipe, Option<String>)>(&mut connection)
+        .await?
+        .into_iter()
+        .unzip();
 
     let category_assoc = RecipeCategory::belonging_to(&all_recipes)
         .inner_join(categories::table)
@@ -57,49 +173,175 @@ pub async fn list_recipes(
         .load(&mut connection)
         .await?;
 
-    return Ok(category_assoc
+    let recipes_categories = category_assoc
         .grouped_by(&all_recipes)
         .into_iter()
         .zip(all_recipes)
-        .map(|(category_assoc, recipe)| {
+        .zip(snippets)
+        .map(|((category_assoc, recipe), snippet)| {
             (
                 recipe,
                 category_assoc
                     .into_iter()
                     .map(|(_, category)| category)
                     .collect(),
+                snippet,
             )
         })
-        .collect());
+        .collect();
+
+    return Ok((recipes_categories, total));
+}
+
+/// Full-text rank of a recipe for the query. Recipes are also found through their ingredient
+/// names, so the rank of the best matching ingredient is added to the rank of the recipe text.
+fn relevance(tsquery: &str) -> Box<dyn BoxableExpression<recipes::table, Pg, SqlType = Float>> {
+    let best_ingredient_rank = recipe_ingredient::table
+        .inner_join(ingredients::table)
+        .filter(recipe_ingredient::recipe_id.eq(recipes::id))
+        .select(diesel::dsl::max(ts_rank(
+            ingredients::search_vector,
+            to_tsquery(search_config(), tsquery.to_string()),
+        )))
+        .single_value();
+    return Box::new(
+        ts_rank(
+            recipes::search_vector,
+            to_tsquery(search_config(), tsquery.to_string()),
+        ) + coalesce(best_ingredient_rank, 0.0),
+    );
+}
+
+/// recipes visible to the user and matching the filter
+pub fn filter_recipes<'a>(
+    user_id: &Option<i32>,
+    filter: &'a RecipeFilter,
+) -> recipes::BoxedQuery<'a, Pg> {
+    let mut filtered_recipes = recipes::table.into_boxed();
+
+    filtered_recipes = match user_id {
+        None => filtered_recipes.filter(recipes::visibility.eq(Visibility::Public)),
+        Some(user_id) => filtered_recipes.filter(
+            recipes::visibility
+                .eq(Visibility::Public)
+                .or(recipes::owner_id.is_not_distinct_from(*user_id))
+                .or(recipes::visibility.eq(Visibility::Shared).and(
+                    recipes::id.eq_any(
+                        recipe_shares::table
+                            .select(recipe_shares::recipe_id)
+                            .filter(recipe_shares::user_id.eq(*user_id)),
+                    ),
+                ))
+                // recipes without owner are looked after by admins and editors
+                .or(recipes::owner_id.is_null().and(diesel::dsl::exists(
+                    user_roles::table
+                        .filter(user_roles::user_id.eq(*user_id))
+                        .filter(user_roles::role.eq_any(ORPHAN_MAINTAINERS)),
+                ))),
+        ),
+    };
+
+    if let Some(tsquery) = filter.q.as_deref().and_then(any_word_query) {
+        let ingredient_matches = recipe_ingredient::table
+            .inner_join(ingredients::table)
+            .filter(Matches::new(
+                ingredients::search_vector,
+                to_tsquery(search_config(), tsquery.clone()),
+            ))
+            .select(recipe_ingredient::recipe_id);
+        filtered_recipes = filtered_recipes.filter(
+            Matches::new(recipes::search_vector, to_tsquery(search_config(), tsquery))
+                .or(recipes::id.eq_any(ingredient_matches)),
+        );
+    }
+
+    if let Some(cuisine) = &filter.cuisine {
+        filtered_recipes = filtered_recipes.filter(recipes::cuisine.eq(cuisine));
+    }
+    if let Some(category) = &filter.category {
+        filtered_recipes = filtered_recipes.filter(
+            recipes::id.eq_any(
+                recipe_category::table
+                    .select(recipe_category::recipe_id)
+                    .filter(recipe_category::category_name.eq(category)),
+            ),
+        );
+    }
+    if let Some(min_duration) = filter.min_duration {
+        filtered_recipes = filtered_recipes.filter(recipes::duration_min.ge(min_duration));
+    }
+    if let Some(max_duration) = filter.max_duration {
+        filtered_recipes = filtered_recipes.filter(recipes::duration_min.le(max_duration));
+    }
+    if let Some(min_rating) = &filter.min_rating {
+        filtered_recipes = filtered_recipes.filter(recipes::rating_avg.ge(min_rating));
+    }
+    // anonymous callers have neither favourites nor collections
+    if filter.favourites {
+        filtered_recipes = filtered_recipes.filter(
+            recipes::id.eq_any(
+                favourites::table
+                    .select(favourites::recipe_id)
+                    .filter(favourites::user_id.nullable().eq(*user_id)),
+            ),
+        );
+    }
+    if let Some(collection_id) = filter.collection {
+        filtered_recipes = filtered_recipes.filter(
+            recipes::id.eq_any(
+                collection_recipes::table
+                    .inner_join(collections::table)
+                    .select(collection_recipes::recipe_id)
+                    .filter(collections::id.eq(collection_id))
+                    .filter(collections::user_id.nullable().eq(*user_id)),
+            ),
+        );
+    }
+
+    return filtered_recipes;
 }
 
 pub async fn get_recipe(
     db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &Option<i32>,
     recipe_id: &i32,
-) -> Result<(Recipe, Vec<Category>), ServiceError> {
-    info!(recipe_id; "Getting recipe");
+) -> Result<(Recipe, Vec<Category>, Vec<RecipePart>, Vec<RecipeStep>), ServiceError> {
+    info!(recipe_id, user_id:serde; "Getting recipe");
     let mut connection = get_connection(db_pool).await?;
-    let recipe = recipes::table
-        .select(Recipe::as_select())
-        .find(recipe_id)
-        .first(&mut connection)
-        .await?;
+    let recipe =
+        get_accessible_recipe(&mut connection, user_id, recipe_id, RecipeAccess::Read).await?;
 
-    let categories = RecipeCategory::belonging_to(&recipe)
-        .inner_join(categories::table)
-        .select(Category::as_select())
-        .load(&mut connection)
-        .await?;
+    let categories = get_recipe_categories(&mut connection, &recipe).await?;
+    let parts = get_recipe_parts(&mut connection, &recipe).await?;
+    let steps = get_recipe_steps(&mut connection, &recipe).await?;
 
-    return Ok((recipe, categories));
+    return Ok((recipe, categories, parts, steps));
+}
+
+/// get recipe with ingredient quantities scaled to the number of portions
+pub async fn get_scaled_recipe(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &Option<i32>,
+    recipe_id: &i32,
+    portions: &i32,
+) -> Result<(Recipe, Vec<Category>, Vec<RecipePart>, Vec<RecipeStep>), ServiceError> {
+    let (mut recipe, categories, mut parts, steps) =
+        get_recipe(db_pool, user_id, recipe_id).await?;
+    debug!(recipe_id, from = recipe.portions, to = portions; "Scaling recipe");
+    scale_parts(&mut parts, recipe.portions, *portions)?;
+    recipe.portions = *portions;
+
+    return Ok((recipe, categories, parts, steps));
 }
 
 pub async fn get_recipe_image(
     db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &Option<i32>,
     recipe_id: &i32,
 ) -> Result<Image, ServiceError> {
-    info!(recipe_id; "Getting recipe image");
+    info!(recipe_id, user_id:serde; "Getting recipe image");
     let mut connection = get_connection(db_pool).await?;
+    get_accessible_recipe(&mut connection, user_id, recipe_id, RecipeAccess::Read).await?;
     let image = images::table
         .filter(images::recipe_id.eq(recipe_id))
         .select(Image::as_select())
@@ -109,13 +351,30 @@ pub async fn get_recipe_image(
     return Ok(image);
 }
 
+/// whether the recipe has an image, access to the recipe is not checked
+pub async fn has_recipe_image(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    recipe_id: &i32,
+) -> Result<bool, ServiceError> {
+    debug!(recipe_id; "Checking recipe image");
+    let mut connection = get_connection(db_pool).await?;
+    let has_image = diesel::select(diesel::dsl::exists(
+        images::table.filter(images::recipe_id.eq(recipe_id)),
+    ))
+    .get_result(&mut connection)
+    .await?;
+
+    return Ok(has_image);
+}
+
 pub async fn create_recipe(
     db_pool: Arc<Pool<AsyncPgConnection>>,
     new_recipe: &NewRecipe,
     categories_names: &Vec<String>,
     rec_ings: &Vec<NewRecipeIngredient<'_>>,
-) -> Result<(Recipe, Vec<Category>), ServiceError> {
-    info!(new_recipe:serde, categories:serde = categories_names, ingredients: serde = rec_ings; "Creating recipe");
+    steps: &Vec<NewRecipeStep>,
+) -> Result<(Recipe, Vec<Category>, Vec<RecipePart>, Vec<RecipeStep>), ServiceError> {
+    info!(new_recipe:serde, categories:serde = categories_names, ingredients: serde = rec_ings, steps:serde; "Creating recipe");
     let mut connection = get_connection(db_pool).await?;
     // create a recipe, associate it to categories and create and associate ingredients
     // if category does not exists -- fail
@@ -131,39 +390,8 @@ pub async fn create_recipe(
                     .await?;
                 debug!(recipe:serde; "Created recipe");
 
-                let mut rec_ings_assoc: Vec<RecipeIngredient> = vec![];
-                for rec_ing in rec_ings {
-                    let ing: Ingredient = match diesel::insert_into(ingredients::table)
-                        .values(&NewIngredient { name: rec_ing.name })
-                        .on_conflict_do_nothing()
-                        .returning(Ingredient::as_returning())
-                        .get_result(&mut connection)
-                        .await
-                        .optional()?
-                    {
-                        Some(ingredient) => {
-                            debug!(ingredient:serde; "Created ingredient");
-                            ingredient
-                        }
-                        None => {
-                            debug!(ingredient = rec_ing.name; "Ingredient already exists");
-                            ingredients::table
-                                .filter(ingredients::name.eq(rec_ing.name))
-                                .select(Ingredient::as_select())
-                                .first(&mut connection)
-                                .await?
-                        }
-                    };
-
-                    rec_ings_assoc.push(RecipeIngredient {
-                        recipe_id: recipe.id,
-                        ingredient_id: ing.id,
-                        part: rec_ing.part,
-                        quantity: rec_ing.quantity.into(),
-                        //TODO: can get rid of to string?
-                        unit: rec_ing.unit.to_string(),
-                    });
-                }
+                let rec_ings_assoc =
+                    build_recipe_ingredients(connection, recipe.id, rec_ings).await?;
                 diesel::insert_into(recipe_ingredient::table)
                     .values(&rec_ings_assoc)
                     .execute(&mut connection)
@@ -182,107 +410,229 @@ pub async fn create_recipe(
                     .await?;
                 debug!(recipe_categories:serde = rec_cats; "Associated categories with recipe");
 
-                let categories = get_recipe_categories(&mut connection, &recipe).await?;
-                return Ok((recipe, categories));
+                replace_recipe_steps(connection, &recipe, steps).await?;
+
+                let categories = get_recipe_categories(connection, &recipe).await?;
+                let parts = get_recipe_parts(connection, &recipe).await?;
+                let steps = get_recipe_steps(connection, &recipe).await?;
+                record_revision(
+                    connection,
+                    &new_recipe.owner_id,
+                    &recipe,
+                    &categories,
+                    &parts,
+                    &steps,
+                )
+                .await?;
+                return Ok((recipe, categories, parts, steps));
             })
         })
         .await;
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn update_recipe(
     db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
     recipe_id: &i32,
+    expected_versions: &Option<Vec<i32>>,
     change_recipe: &ChangeRecipe,
     rec_cats: &Option<Vec<String>>,
-) -> Result<(Recipe, Vec<Category>), ServiceError> {
-    // add/remove ingredient associations
-    info!(recipe_id; "Changing recipe");
+    rec_ings: &Option<Vec<NewRecipeIngredient<'_>>>,
+    steps: &Option<Vec<NewRecipeStep>>,
+) -> Result<(Recipe, Vec<Category>, Vec<RecipePart>, Vec<RecipeStep>), ServiceError> {
+    info!(recipe_id, user_id, expected_versions:serde; "Changing recipe");
     let mut connection = get_connection(db_pool).await?;
     return connection
         .build_transaction()
-        .run(|mut connection| {
+        .run(|connection| {
             Box::pin(async move {
-                let recipe = diesel::update(recipes::table.find(recipe_id))
-                    .set(change_recipe)
-                    .returning(Recipe::as_returning())
-                    .get_result(&mut connection)
-                    .await?;
-                debug!(recipe:serde; "Selected recipe");
-
-                if let Some(rec_cats) = rec_cats {
-                    debug!(categories:serde = rec_cats; "Updating categories");
-                    // remove rec_cats that are not present in categories and create new ones
-                    diesel::delete(
-                        RecipeCategory::belonging_to(&recipe)
-                            .filter(recipe_category::category_name.ne_all(rec_cats)),
-                    )
-                    .execute(&mut connection)
-                    .await?;
-
-                    let all_rec_cats: Vec<RecipeCategory> = rec_cats
-                        .into_iter()
-                        .map(|c| RecipeCategory {
-                            recipe_id: recipe.id,
-                            category_name: c.to_string(),
-                        })
-                        .collect();
-                    diesel::insert_into(recipe_category::table)
-                        .values(&all_rec_cats)
-                        .on_conflict_do_nothing()
-                        .execute(&mut connection)
-                        .await?;
-                }
-
-                let categories = get_recipe_categories(&mut connection, &recipe).await?;
-                return Ok((recipe, categories));
+                return apply_recipe_update(
+                    connection,
+                    user_id,
+                    recipe_id,
+                    expected_versions,
+                    change_recipe,
+                    rec_cats,
+                    rec_ings,
+                    steps,
+                )
+                .await;
             })
         })
         .await;
 }
 
+/// Change the recipe within the transaction of the caller and record the result as a new
+/// revision. `None` categories, ingredients and steps are left as they are, `None` expected
+/// versions accept any current version.
+#[allow(clippy::too_many_arguments)]
+pub async fn apply_recipe_update(
+    connection: &mut AsyncPgConnection,
+    user_id: &i32,
+    recipe_id: &i32,
+    expected_versions: &Option<Vec<i32>>,
+    change_recipe: &ChangeRecipe,
+    rec_cats: &Option<Vec<String>>,
+    rec_ings: &Option<Vec<NewRecipeIngredient<'_>>>,
+    steps: &Option<Vec<NewRecipeStep>>,
+) -> Result<(Recipe, Vec<Category>, Vec<RecipePart>, Vec<RecipeStep>), ServiceError> {
+    let current =
+        get_accessible_recipe(connection, &Some(*user_id), recipe_id, RecipeAccess::Edit).await?;
+    // only the owner decides who sees the recipe
+    if change_recipe
+        .visibility
+        .is_some_and(|visibility| visibility != current.visibility)
+    {
+        get_accessible_recipe(connection, &Some(*user_id), recipe_id, RecipeAccess::Owner).await?;
+    }
+    lock_recipe_version(connection, recipe_id, expected_versions).await?;
+
+    let recipe = diesel::update(recipes::table.find(recipe_id))
+        .set((change_recipe, recipes::version.eq(recipes::version + 1)))
+        .returning(Recipe::as_returning())
+        .get_result(connection)
+        .await?;
+    debug!(recipe:serde; "Updated recipe");
+
+    if let Some(rec_cats) = rec_cats {
+        debug!(categories:serde = rec_cats; "Updating categories");
+        // remove rec_cats that are not present in categories and create new ones
+        diesel::delete(
+            RecipeCategory::belonging_to(&recipe)
+                .filter(recipe_category::category_name.ne_all(rec_cats)),
+        )
+        .execute(connection)
+        .await?;
+
+        let all_rec_cats: Vec<RecipeCategory> = rec_cats
+            .iter()
+            .map(|c| RecipeCategory {
+                recipe_id: recipe.id,
+                category_name: c.to_string(),
+            })
+            .collect();
+        diesel::insert_into(recipe_category::table)
+            .values(&all_rec_cats)
+            .on_conflict_do_nothing()
+            .execute(connection)
+            .await?;
+    }
+
+    if let Some(rec_ings) = rec_ings {
+        debug!(ingredients:serde = rec_ings; "Updating ingredients");
+        // the list replaces current ingredients -- remove missing, upsert the rest
+        let all_rec_ings = build_recipe_ingredients(connection, recipe.id, rec_ings).await?;
+        let ingredient_ids: Vec<i32> = all_rec_ings.iter().map(|r| r.ingredient_id).collect();
+        diesel::delete(
+            RecipeIngredient::belonging_to(&recipe)
+                .filter(recipe_ingredient::ingredient_id.ne_all(ingredient_ids)),
+        )
+        .execute(connection)
+        .await?;
+
+        diesel::insert_into(recipe_ingredient::table)
+            .values(&all_rec_ings)
+            .on_conflict((
+                recipe_ingredient::recipe_id,
+                recipe_ingredient::ingredient_id,
+            ))
+            .do_update()
+            .set((
+                recipe_ingredient::part.eq(excluded(recipe_ingredient::part)),
+                recipe_ingredient::quantity.eq(excluded(recipe_ingredient::quantity)),
+                recipe_ingredient::unit.eq(excluded(recipe_ingredient::unit)),
+            ))
+            .execute(connection)
+            .await?;
+    }
+
+    match steps {
+        Some(steps) => replace_recipe_steps(connection, &recipe, steps).await?,
+        None if rec_ings.is_some() => prune_step_ingredients(connection, &recipe).await?,
+        None => (),
+    }
+
+    let categories = get_recipe_categories(connection, &recipe).await?;
+    let parts = get_recipe_parts(connection, &recipe).await?;
+    let steps = get_recipe_steps(connection, &recipe).await?;
+    record_revision(
+        connection,
+        &Some(*user_id),
+        &recipe,
+        &categories,
+        &parts,
+        &steps,
+    )
+    .await?;
+    return Ok((recipe, categories, parts, steps));
+}
+
 pub async fn change_recipe_image(
     db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
     recipe_id: &i32,
     image_bytes: &'_ Vec<u8>,
     image_type: &mime::Mime,
 ) -> Result<(), ServiceError> {
-    info!(recipe_id; "Changing recipe image");
+    info!(recipe_id, user_id; "Changing recipe image");
     let mut connection = get_connection(db_pool).await?;
-    let recipe = recipes::table
-        .select(Recipe::as_select())
-        .find(recipe_id)
-        .first(&mut connection)
-        .await?;
+    let recipe = get_accessible_recipe(
+        &mut connection,
+        &Some(*user_id),
+        recipe_id,
+        RecipeAccess::Edit,
+    )
+    .await?;
 
-    diesel::insert_into(images::table)
-        .values(&NewImage {
-            recipe_id: recipe.id,
-            bytes: image_bytes,
-            type_: image_type.essence_str(),
-        })
-        .on_conflict(images::recipe_id)
-        .do_update()
-        .set(&UpdateImage {
-            bytes: image_bytes,
-            type_: image_type.essence_str(),
-        })
-        .execute(&mut connection)
-        .await?;
-    debug!(recipe_id; "Created/Updated recipe image");
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                diesel::insert_into(images::table)
+                    .values(&NewImage {
+                        recipe_id: recipe.id,
+                        bytes: image_bytes,
+                        type_: image_type.essence_str(),
+                    })
+                    .on_conflict(images::recipe_id)
+                    .do_update()
+                    .set(&UpdateImage {
+                        bytes: image_bytes,
+                        type_: image_type.essence_str(),
+                    })
+                    .execute(connection)
+                    .await?;
+                debug!(recipe_id; "Created/Updated recipe image");
 
-    return Ok(());
+                // the JSON-LD representation links the image
+                diesel::update(recipes::table.find(recipe.id))
+                    .set(recipes::version.eq(recipes::version + 1))
+                    .execute(connection)
+                    .await?;
+
+                return Ok(());
+            })
+        })
+        .await;
 }
 
 pub async fn delete_recipe(
     db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
     recipe_id: &i32,
+    expected_versions: &Option<Vec<i32>>,
 ) -> Result<(), ServiceError> {
-    info!(recipe_id; "Deleting recipe");
+    info!(recipe_id, user_id, expected_versions:serde; "Deleting recipe");
     let mut connection = get_connection(db_pool).await?;
     return connection
         .build_transaction()
         .run(|mut connection| {
             Box::pin(async move {
+                get_accessible_recipe(connection, &Some(*user_id), recipe_id, RecipeAccess::Owner)
+                    .await?;
+                lock_recipe_version(connection, recipe_id, expected_versions).await?;
+
                 diesel::delete(
                     recipe_category::table.filter(recipe_category::recipe_id.eq(&recipe_id)),
                 )
@@ -297,6 +647,11 @@ pub async fn delete_recipe(
                 .await?;
                 debug!("Removed ingredient associations");
 
+                diesel::delete(images::table.filter(images::recipe_id.eq(&recipe_id)))
+                    .execute(&mut connection)
+                    .await?;
+                debug!("Removed recipe image");
+
                 diesel::delete(recipes::table.find(&recipe_id))
                     .execute(&mut connection)
                     .await?;
@@ -308,6 +663,136 @@ pub async fn delete_recipe(
         .await;
 }
 
+/// Get the recipe if the user has at least the required access to it. Recipes the user
+/// cannot see at all are reported as not found so their existence is not revealed.
+pub async fn get_accessible_recipe(
+    connection: &mut AsyncPgConnection,
+    user_id: &Option<i32>,
+    recipe_id: &i32,
+    required_access: RecipeAccess,
+) -> Result<Recipe, ServiceError> {
+    let recipe = recipes::table
+        .select(Recipe::as_select())
+        .find(recipe_id)
+        .first(connection)
+        .await?;
+
+    let share = match user_id {
+        Some(user_id) if recipe.owner_id != Some(*user_id) => RecipeShare::belonging_to(&recipe)
+            .filter(recipe_shares::user_id.eq(user_id))
+            .select(RecipeShare::as_select())
+            .first(connection)
+            .await
+            .optional()?,
+        _ => None,
+    };
+    let shared_access = match (recipe.visibility, share.map(|s| s.access)) {
+        (Visibility::Private, _) | (_, None) => RecipeAccess::None,
+        (_, Some(Access::Read)) => RecipeAccess::Read,
+        (_, Some(Access::Edit)) => RecipeAccess::Edit,
+    };
+    // recipes without owner, from before users existed or of removed users, would be stuck
+    let orphan_access = match (recipe.owner_id, user_id) {
+        (None, Some(user_id)) => {
+            let roles = load_roles(connection, user_id).await?;
+            if roles.contains(&Role::Admin) {
+                RecipeAccess::Owner
+            } else if roles.contains(&Role::Editor) {
+                RecipeAccess::Edit
+            } else {
+                RecipeAccess::None
+            }
+        }
+        _ => RecipeAccess::None,
+    };
+    let access = if user_id.is_some() && recipe.owner_id == *user_id {
+        RecipeAccess::Owner
+    } else if recipe.visibility == Visibility::Public {
+        shared_access.max(orphan_access).max(RecipeAccess::Read)
+    } else {
+        shared_access.max(orphan_access)
+    };
+
+    if access == RecipeAccess::None {
+        return Err(ServiceError::DbDiesel(diesel::result::Error::NotFound));
+    }
+    if access < required_access {
+        return Err(ServiceError::Forbidden {
+            detail: match required_access {
+                RecipeAccess::Owner => "Only the owner of the recipe can do this",
+                _ => "The recipe is not shared with you for editing",
+            },
+        });
+    }
+
+    return Ok(recipe);
+}
+
+/// Lock the recipe row until the end of the transaction and check its version is one of the
+/// expected versions, so a change made since the caller read the recipe is not overwritten.
+async fn lock_recipe_version(
+    connection: &mut AsyncPgConnection,
+    recipe_id: &i32,
+    expected_versions: &Option<Vec<i32>>,
+) -> Result<(), ServiceError> {
+    let version: i32 = recipes::table
+        .find(recipe_id)
+        .select(recipes::version)
+        .for_update()
+        .first(connection)
+        .await?;
+    if let Some(expected_versions) = expected_versions {
+        if !expected_versions.contains(&version) {
+            debug!(version, expected_versions:serde; "Recipe version does not match");
+            return Err(ServiceError::PreconditionFailed { resource: "recipe" });
+        }
+    }
+
+    return Ok(());
+}
+
+/// get or create ingredients by name and prepare their association with the recipe
+async fn build_recipe_ingredients(
+    connection: &mut AsyncPgConnection,
+    recipe_id: i32,
+    rec_ings: &Vec<NewRecipeIngredient<'_>>,
+) -> Result<Vec<RecipeIngredient>, ServiceError> {
+    // units stored before the unit catalogue and unknown to it stay valid while the recipe
+    // keeps using them
+    let stored_units: Vec<String> = recipe_ingredient::table
+        .filter(recipe_ingredient::recipe_id.eq(recipe_id))
+        .select(recipe_ingredient::unit)
+        .distinct()
+        .load(connection)
+        .await?;
+    let mut rec_ings_assoc: Vec<RecipeIngredient> = vec![];
+    for rec_ing in rec_ings {
+        let unit = match find_unit(rec_ing.unit) {
+            Some(unit) => unit.code.to_string(),
+            None if stored_units.iter().any(|unit| unit == rec_ing.unit) => {
+                rec_ing.unit.to_string()
+            }
+            None => {
+                return Err(ServiceError::InvalidValue {
+                    field: "unit",
+                    detail: format!("Unknown unit '{}'", rec_ing.unit),
+                })
+            }
+        };
+        let ing = get_or_create_ingredient(connection, rec_ing.name.trim()).await?;
+
+        rec_ings_assoc.push(RecipeIngredient {
+            recipe_id,
+            ingredient_id: ing.id,
+            part: rec_ing.part,
+            quantity: rec_ing.quantity.clone(),
+            unit,
+        });
+    }
+
+    return Ok(rec_ings_assoc);
+}
+
 async fn get_recipe_categories(
     connection: &mut AsyncPgConnection,
     recipe: &Recipe,
@@ -318,3 +803,296 @@ async fn get_recipe_categories(
         .load(connection)
         .await;
 }
+
+async fn get_recipe_steps(
+    connection: &mut AsyncPgConnection,
+    recipe: &Recipe,
+) -> Result<Vec<RecipeStep>, diesel::result::Error> {
+    return RecipeStep::belonging_to(recipe)
+        .select(RecipeStep::as_select())
+        .order_by(recipe_steps::ordinal)
+        .load(connection)
+        .await;
+}
+
+/// replace all steps of the recipe, steps may only refer to ingredients of the recipe
+async fn replace_recipe_steps(
+    connection: &mut AsyncPgConnection,
+    recipe: &Recipe,
+    steps: &[NewRecipeStep],
+) -> Result<(), ServiceError> {
+    let ingredient_ids: Vec<i32> = RecipeIngredient::belonging_to(recipe)
+        .select(recipe_ingredient::ingredient_id)
+        .load(connection)
+        .await?;
+    let mut all_steps: Vec<RecipeStep> = vec![];
+    for (index, step) in steps.iter().enumerate() {
+        if let Some(unknown) = step
+            .ingredient_ids
+            .iter()
+            .find(|id| !ingredient_ids.contains(id))
+        {
+            return Err(ServiceError::InvalidValue {
+                field: "ingredient_ids",
+                detail: format!("Ingredient '{unknown}' is not an ingredient of the recipe"),
+            });
+        }
+        all_steps.push(RecipeStep {
+            recipe_id: recipe.id,
+            ordinal: index as i32 + 1,
+            text: step.text.trim().to_string(),
+            duration_min: step.duration_min,
+            ingredient_ids: step.ingredient_ids.clone(),
+        });
+    }
+
+    diesel::delete(RecipeStep::belonging_to(recipe))
+        .execute(connection)
+        .await?;
+    diesel::insert_into(recipe_steps::table)
+        .values(&all_steps)
+        .execute(connection)
+        .await?;
+    debug!(steps = all_steps.len(); "Replaced recipe steps");
+
+    return Ok(());
+}
+
+/// drop references of steps to ingredients no longer in the recipe
+async fn prune_step_ingredients(
+    connection: &mut AsyncPgConnection,
+    recipe: &Recipe,
+) -> Result<(), ServiceError> {
+    let ingredient_ids: Vec<i32> = RecipeIngredient::belonging_to(recipe)
+        .select(recipe_ingredient::ingredient_id)
+        .load(connection)
+        .await?;
+    for step in get_recipe_steps(connection, recipe).await? {
+        if step
+            .ingredient_ids
+            .iter()
+            .all(|id| ingredient_ids.contains(id))
+        {
+            continue;
+        }
+        let kept: Vec<i32> = step
+            .ingredient_ids
+            .into_iter()
+            .filter(|id| ingredient_ids.contains(id))
+            .collect();
+        diesel::update(recipe_steps::table.find((step.recipe_id, step.ordinal)))
+            .set(recipe_steps::ingredient_ids.eq(kept))
+            .execute(connection)
+            .await?;
+    }
+
+    return Ok(());
+}
+
+async fn get_recipe_parts(
+    connection: &mut AsyncPgConnection,
+    recipe: &Recipe,
+) -> Result<Vec<RecipePart>, diesel::result::Error> {
+    let rec_ings: Vec<(RecipeIngredient, Ingredient)> = RecipeIngredient::belonging_to(recipe)
+        .inner_join(ingredients::table)
+        .select((RecipeIngredient::as_select(), Ingredient::as_select()))
+        .order_by((recipe_ingredient::part, ingredients::name))
+        .load(connection)
+        .await?;
+
+    // rows are ordered by part so consecutive rows of the same part form one group
+    let mut parts: Vec<RecipePart> = vec![];
+    for (rec_ing, ingredient) in rec_ings {
+        match parts.last_mut() {
+            Some(part) if part.part == rec_ing.part => part.ingredients.push((rec_ing, ingredient)),
+            _ => parts.push(RecipePart {
+                part: rec_ing.part,
+                ingredients: vec![(rec_ing, ingredient)],
+            }),
+        }
+    }
+
+    return Ok(parts);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes_service::test_utils::{new_recipe, test_pool};
+    use crate::recipes_service::users::create_user;
+    use diesel_async::SimpleAsyncConnection;
+
+    fn ingredients<'a>(
+        quantity: &'a BigDecimal,
+        units: &[&'a str],
+    ) -> Vec<NewRecipeIngredient<'a>> {
+        return units
+            .iter()
+            .zip(["flour", "sugar", "salt"])
+            .map(|(unit, name)| NewRecipeIngredient {
+                name,
+                part: 0,
+                quantity,
+                unit,
+            })
+            .collect();
+    }
+
+    async fn stored_units(db_pool: Arc<Pool<AsyncPgConnection>>, recipe_id: i32) -> Vec<String> {
+        let mut connection = get_connection(db_pool).await.unwrap();
+        return recipe_ingredient::table
+            .inner_join(ingredients::table)
+            .filter(recipe_ingredient::recipe_id.eq(recipe_id))
+            .select(recipe_ingredient::unit)
+            .order_by(ingredients::name)
+            .load(&mut connection)
+            .await
+            .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn deletes_recipe_with_image() {
+        let Some(db_pool) = test_pool().await else {
+            return;
+        };
+        let (user, _) = create_user(db_pool.clone(), "cook", "hash").await.unwrap();
+        let (recipe, ..) = create_recipe(
+            db_pool.clone(),
+            &new_recipe(user.id),
+            &vec![],
+            &vec![],
+            &vec![],
+        )
+        .await
+        .unwrap();
+        change_recipe_image(
+            db_pool.clone(),
+            &user.id,
+            &recipe.id,
+            &vec![0x89, 0x50, 0x4e, 0x47],
+            &mime::IMAGE_PNG,
+        )
+        .await
+        .unwrap();
+
+        delete_recipe(db_pool.clone(), &user.id, &recipe.id, &None)
+            .await
+            .unwrap();
+        assert!(!has_recipe_image(db_pool.clone(), &recipe.id).await.unwrap());
+        assert!(matches!(
+            get_recipe(db_pool, &Some(user.id), &recipe.id).await,
+            Err(ServiceError::DbDiesel(diesel::result::Error::NotFound))
+        ));
+    }
+
+    /// replace the ingredients of the recipe by flour, sugar and salt in the units
+    async fn replace_ingredients(
+        db_pool: Arc<Pool<AsyncPgConnection>>,
+        user_id: i32,
+        recipe_id: i32,
+        units: &[&str],
+    ) -> Result<(), ServiceError> {
+        let quantity = BigDecimal::from(2);
+        let change_recipe = ChangeRecipe {
+            name: None,
+            instructions: None,
+            cuisine: None,
+            duration_min: None,
+            preparation_needed: None,
+            portions: None,
+            difficulty: None,
+            visibility: None,
+        };
+        update_recipe(
+            db_pool,
+            &user_id,
+            &recipe_id,
+            &None,
+            &change_recipe,
+            &None,
+            &Some(ingredients(&quantity, units)),
+            &None,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    #[actix_web::test]
+    async fn keeps_unknown_units_the_recipe_already_uses() {
+        let Some(db_pool) = test_pool().await else {
+            return;
+        };
+        let (user, _) = create_user(db_pool.clone(), "cook", "hash").await.unwrap();
+        let (recipe, ..) = create_recipe(
+            db_pool.clone(),
+            &new_recipe(user.id),
+            &vec![],
+            &vec![],
+            &vec![],
+        )
+        .await
+        .unwrap();
+        replace_ingredients(db_pool.clone(), user.id, recipe.id, &["g"])
+            .await
+            .unwrap();
+        // stored before units were checked
+        let mut connection = get_connection(db_pool.clone()).await.unwrap();
+        connection
+            .batch_execute("UPDATE recipe_ingredient SET unit = 'handful'")
+            .await
+            .unwrap();
+
+        replace_ingredients(db_pool.clone(), user.id, recipe.id, &["handful", "Tbsp."])
+            .await
+            .unwrap();
+        assert_eq!(
+            stored_units(db_pool.clone(), recipe.id).await,
+            vec!["handful", "tbsp"]
+        );
+        assert!(matches!(
+            replace_ingredients(db_pool, user.id, recipe.id, &["handful", "bucket"]).await,
+            Err(ServiceError::InvalidValue { field: "unit", .. })
+        ));
+    }
+
+    #[actix_web::test]
+    async fn migrates_known_unit_spellings_to_codes() {
+        let Some(db_pool) = test_pool().await else {
+            return;
+        };
+        let (user, _) = create_user(db_pool.clone(), "cook", "hash").await.unwrap();
+        let (recipe, ..) = create_recipe(
+            db_pool.clone(),
+            &new_recipe(user.id),
+            &vec![],
+            &vec![],
+            &vec![],
+        )
+        .await
+        .unwrap();
+        replace_ingredients(db_pool.clone(), user.id, recipe.id, &["g", "g", "g"])
+            .await
+            .unwrap();
+        let mut connection = get_connection(db_pool.clone()).await.unwrap();
+        connection
+            .batch_execute(
+                "UPDATE recipe_ingredient SET unit = CASE \
+                 (SELECT name FROM ingredients WHERE id = ingredient_id) \
+                 WHEN 'flour' THEN ' Grams ' WHEN 'salt' THEN 'handful' ELSE 'Tbsp.' END",
+            )
+            .await
+            .unwrap();
+
+        connection
+            .batch_execute(include_str!(
+                "../../migrations/2026-10-18-230000-0000_recipe_ingredient_unit_codes/up.sql"
+            ))
+            .await
+            .unwrap();
+        // flour, salt, sugar
+        assert_eq!(
+            stored_units(db_pool, recipe.id).await,
+            vec!["g", "handful", "tbsp"]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::info;
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::models::review::{ChangeReview, NewReview, Review};
+use super::models::user::User;
+use super::recipes::{get_accessible_recipe, RecipeAccess};
+use super::schema::{recipes, reviews, users};
+use super::utils::{get_connection, Page};
+
+/// reviews of a recipe visible to the user with their authors, latest first
+pub async fn list_reviews(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &Option<i32>,
+    recipe_id: &i32,
+    page: &Page,
+) -> Result<(Vec<(Review, User)>, i64), ServiceError> {
+    info!(recipe_id, user_id:serde; "Listing reviews");
+    let mut connection = get_connection(db_pool).await?;
+    let recipe =
+        get_accessible_recipe(&mut connection, user_id, recipe_id, RecipeAccess::Read).await?;
+
+    let total: i64 = Review::belonging_to(&recipe)
+        .count()
+        .get_result(&mut connection)
+        .await?;
+    let all_reviews = Review::belonging_to(&recipe)
+        .inner_join(users::table)
+        .select((Review::as_select(), User::as_select()))
+        .order_by((reviews::updated_at.desc(), reviews::user_id))
+        .offset(page.offset())
+        .limit(page.limit)
+        .load(&mut connection)
+        .await?;
+
+    return Ok((all_reviews, total));
+}
+
+/// review a recipe, each user can review a recipe once
+pub async fn create_review(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+    rating: &i16,
+    text: &Option<String>,
+) -> Result<Review, ServiceError> {
+    info!(recipe_id, user_id, rating; "Creating review");
+    check_rating(rating)?;
+
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                lock_rated_recipe(connection, recipe_id).await?;
+                get_accessible_recipe(connection, &Some(*user_id), recipe_id, RecipeAccess::Read)
+                    .await?;
+                let review = diesel::insert_into(reviews::table)
+                    .values(NewReview {
+                        recipe_id: *recipe_id,
+                        user_id: *user_id,
+                        rating: *rating,
+                        text: review_text(text),
+                    })
+                    .returning(Review::as_returning())
+                    .get_result(connection)
+                    .await?;
+
+                return Ok(review);
+            })
+        })
+        .await;
+}
+
+/// change the review of the user
+pub async fn update_review(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+    rating: &i16,
+    text: &Option<String>,
+) -> Result<Review, ServiceError> {
+    info!(recipe_id, user_id, rating; "Changing review");
+    check_rating(rating)?;
+
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                lock_rated_recipe(connection, recipe_id).await?;
+                get_accessible_recipe(connection, &Some(*user_id), recipe_id, RecipeAccess::Read)
+                    .await?;
+                let review = diesel::update(reviews::table.find((recipe_id, user_id)))
+                    .set(ChangeReview {
+                        rating: *rating,
+                        text: review_text(text),
+                        updated_at: Utc::now(),
+                    })
+                    .returning(Review::as_returning())
+                    .get_result(connection)
+                    .await?;
+
+                return Ok(review);
+            })
+        })
+        .await;
+}
+
+/// remove the review of the user
+pub async fn delete_review(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(recipe_id, user_id; "Deleting review");
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                lock_rated_recipe(connection, recipe_id).await?;
+                let deleted = diesel::delete(reviews::table.find((recipe_id, user_id)))
+                    .execute(connection)
+                    .await?;
+                if deleted == 0 {
+                    return Err(ServiceError::DbDiesel(diesel::result::Error::NotFound));
+                }
+
+                return Ok(());
+            })
+        })
+        .await;
+}
+
+/// Lock the recipe row until the end of the transaction. The rating trigger recomputes the
+/// aggregates from all reviews, concurrent changes would each miss the other review.
+async fn lock_rated_recipe(
+    connection: &mut AsyncPgConnection,
+    recipe_id: &i32,
+) -> Result<(), diesel::result::Error> {
+    recipes::table
+        .find(recipe_id)
+        .select(recipes::id)
+        .for_update()
+        .execute(connection)
+        .await?;
+    return Ok(());
+}
+
+fn check_rating(rating: &i16) -> Result<(), ServiceError> {
+    if !(1..=5).contains(rating) {
+        return Err(ServiceError::InvalidValue {
+            field: "rating",
+            detail: "must be from 1 to 5".to_string(),
+        });
+    }
+
+    return Ok(());
+}
+
+/// blank texts are stored as no text
+fn review_text(text: &Option<String>) -> Option<&str> {
+    return text
+        .as_deref()
+        .map(str::trim)
+        .filter(|text| !text.is_empty());
+}
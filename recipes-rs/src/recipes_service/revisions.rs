@@ -0,0 +1,527 @@
+use diesel::prelude::*;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::{debug, info};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use super::errors::ServiceError;
+use super::ingredients::get_or_create_ingredient;
+use super::models::category::Category;
+use super::models::ingredient::{Ingredient, NewRecipeIngredient, RecipePart};
+use super::models::recipe::{ChangeRecipe, Recipe};
+use super::models::recipe_revision::{
+    NewRecipeRevision, RecipeRevision, RecipeSnapshot, SnapshotIngredient, SnapshotStep,
+};
+use super::models::recipe_step::{NewRecipeStep, RecipeStep};
+use super::recipes::{apply_recipe_update, get_accessible_recipe, RecipeAccess};
+use super::schema::{ingredients, recipe_revisions};
+use super::utils::{get_connection, Page};
+
+/// field of the snapshot that differs between two revisions
+#[derive(Serialize, ToSchema, Debug)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: serde_json::Value,
+    pub to: serde_json::Value,
+}
+
+/// revisions of a recipe, latest first
+pub async fn list_revisions(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+    page: &Page,
+) -> Result<(Vec<RecipeRevision>, i64), ServiceError> {
+    info!(recipe_id, user_id; "Listing recipe revisions");
+    let mut connection = get_connection(db_pool).await?;
+    // history may hold content the owner removed on purpose, only editors see it
+    let recipe = get_accessible_recipe(
+        &mut connection,
+        &Some(*user_id),
+        recipe_id,
+        RecipeAccess::Edit,
+    )
+    .await?;
+
+    let total: i64 = RecipeRevision::belonging_to(&recipe)
+        .count()
+        .get_result(&mut connection)
+        .await?;
+    let all_revisions = RecipeRevision::belonging_to(&recipe)
+        .select(RecipeRevision::as_select())
+        .order_by(recipe_revisions::revision.desc())
+        .offset(page.offset())
+        .limit(page.limit)
+        .load(&mut connection)
+        .await?;
+
+    return Ok((all_revisions, total));
+}
+
+pub async fn get_revision(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+    revision: &i32,
+) -> Result<(RecipeRevision, RecipeSnapshot), ServiceError> {
+    info!(recipe_id, user_id, revision; "Getting recipe revision");
+    let mut connection = get_connection(db_pool).await?;
+    get_accessible_recipe(
+        &mut connection,
+        &Some(*user_id),
+        recipe_id,
+        RecipeAccess::Edit,
+    )
+    .await?;
+
+    return Ok(load_revision(&mut connection, recipe_id, revision).await?);
+}
+
+/// fields that changed from one revision to the other, ordered by field name
+pub async fn diff_revisions(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+    from: &i32,
+    to: &i32,
+) -> Result<Vec<FieldChange>, ServiceError> {
+    info!(recipe_id, user_id, from, to; "Comparing recipe revisions");
+    let mut connection = get_connection(db_pool).await?;
+    get_accessible_recipe(
+        &mut connection,
+        &Some(*user_id),
+        recipe_id,
+        RecipeAccess::Edit,
+    )
+    .await?;
+
+    let (from_revision, _) = load_revision(&mut connection, recipe_id, from).await?;
+    let (to_revision, _) = load_revision(&mut connection, recipe_id, to).await?;
+
+    return Ok(diff_snapshots(
+        from_revision.snapshot,
+        to_revision.snapshot,
+    )?);
+}
+
+/// top-level fields of two serialized snapshots with different values, ordered by field name
+fn diff_snapshots(
+    from: serde_json::Value,
+    to: serde_json::Value,
+) -> Result<Vec<FieldChange>, diesel::result::Error> {
+    let (serde_json::Value::Object(from_fields), serde_json::Value::Object(mut to_fields)) =
+        (from, to)
+    else {
+        return Err(diesel::result::Error::DeserializationError(
+            "Recipe snapshot is not an object".into(),
+        ));
+    };
+
+    let mut changes = vec![];
+    for (field, from_value) in from_fields {
+        let to_value = to_fields.remove(&field).unwrap_or_default();
+        if from_value != to_value {
+            changes.push(FieldChange {
+                field,
+                from: from_value,
+                to: to_value,
+            });
+        }
+    }
+    for (field, to_value) in to_fields {
+        changes.push(FieldChange {
+            field,
+            from: serde_json::Value::Null,
+            to: to_value,
+        });
+    }
+    changes.sort_by(|a, b| a.field.cmp(&b.field));
+
+    return Ok(changes);
+}
+
+/// Bring the recipe back to the state of an earlier revision, which is recorded as a new
+/// revision. Visibility is not restored, it is up to the owner and not part of the content.
+/// Ingredients renamed since keep their current name. Ingredients merged or removed since the
+/// revision are not brought back, the restore fails instead.
+pub async fn restore_revision(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+    revision: &i32,
+    expected_versions: &Option<Vec<i32>>,
+) -> Result<(Recipe, Vec<Category>, Vec<RecipePart>, Vec<RecipeStep>), ServiceError> {
+    info!(recipe_id, user_id, revision, expected_versions:serde; "Restoring recipe revision");
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                get_accessible_recipe(connection, &Some(*user_id), recipe_id, RecipeAccess::Edit)
+                    .await?;
+                let (_, snapshot) = load_revision(connection, recipe_id, revision).await?;
+                let ingredients = current_ingredients(connection, &snapshot).await?;
+
+                let change_recipe = ChangeRecipe {
+                    name: Some(snapshot.name.clone()),
+                    instructions: Some(snapshot.instructions.clone()),
+                    cuisine: Some(snapshot.cuisine.clone()),
+                    duration_min: Some(snapshot.duration_min),
+                    preparation_needed: Some(snapshot.preparation_needed),
+                    portions: Some(snapshot.portions),
+                    difficulty: Some(snapshot.difficulty),
+                    visibility: None,
+                };
+                let rec_ings: Vec<NewRecipeIngredient> = snapshot
+                    .ingredients
+                    .iter()
+                    .map(|ingredient| NewRecipeIngredient {
+                        name: &ingredients[&ingredient.name].name,
+                        part: ingredient.part,
+                        quantity: &ingredient.quantity,
+                        unit: &ingredient.unit,
+                    })
+                    .collect();
+                // steps refer to ingredients by their name in the snapshot
+                let steps: Vec<NewRecipeStep> = snapshot
+                    .steps
+                    .iter()
+                    .map(|step| NewRecipeStep {
+                        text: step.text.clone(),
+                        duration_min: step.duration_min,
+                        ingredient_ids: step
+                            .ingredients
+                            .iter()
+                            .filter_map(|name| ingredients.get(name))
+                            .map(|ingredient| ingredient.id)
+                            .collect(),
+                    })
+                    .collect();
+
+                return apply_recipe_update(
+                    connection,
+                    user_id,
+                    recipe_id,
+                    expected_versions,
+                    &change_recipe,
+                    &Some(snapshot.categories.clone()),
+                    &Some(rec_ings),
+                    &Some(steps),
+                )
+                .await;
+            })
+        })
+        .await;
+}
+
+/// Current ingredients of the snapshot by their name in the snapshot. Ingredients are found by
+/// id, following renames. Ingredients removed or merged away since the revision are created
+/// again with their name in the snapshot.
+async fn current_ingredients(
+    connection: &mut AsyncPgConnection,
+    snapshot: &RecipeSnapshot,
+) -> Result<HashMap<String, Ingredient>, ServiceError> {
+    let ids: Vec<i32> = snapshot
+        .ingredients
+        .iter()
+        .map(|ingredient| ingredient.id)
+        .collect();
+    let found: Vec<Ingredient> = ingredients::table
+        .filter(ingredients::id.eq_any(&ids))
+        .select(Ingredient::as_select())
+        .load(connection)
+        .await?;
+
+    let mut current: HashMap<String, Ingredient> = HashMap::new();
+    for snapshot_ingredient in &snapshot.ingredients {
+        let ingredient = match found
+            .iter()
+            .find(|ingredient| ingredient.id == snapshot_ingredient.id)
+        {
+            Some(ingredient) => ingredient.clone(),
+            None => get_or_create_ingredient(connection, &snapshot_ingredient.name).await?,
+        };
+        current.insert(snapshot_ingredient.name.clone(), ingredient);
+    }
+
+    return Ok(current);
+}
+
+/// Record the current state of the recipe as its next revision. Nothing is recorded when the
+/// state equals the latest revision.
+pub async fn record_revision(
+    connection: &mut AsyncPgConnection,
+    user_id: &Option<i32>,
+    recipe: &Recipe,
+    categories: &[Category],
+    parts: &[RecipePart],
+    steps: &[RecipeStep],
+) -> Result<(), diesel::result::Error> {
+    let snapshot = serde_json::to_value(build_snapshot(recipe, categories, parts, steps))
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+    let latest: Option<RecipeRevision> = RecipeRevision::belonging_to(recipe)
+        .select(RecipeRevision::as_select())
+        .order_by(recipe_revisions::revision.desc())
+        .first(connection)
+        .await
+        .optional()?;
+    if latest
+        .as_ref()
+        .is_some_and(|latest| latest.snapshot == snapshot)
+    {
+        debug!(recipe_id = recipe.id; "Recipe unchanged, no revision recorded");
+        return Ok(());
+    }
+
+    let revision = latest.map_or(1, |latest| latest.revision + 1);
+    diesel::insert_into(recipe_revisions::table)
+        .values(NewRecipeRevision {
+            recipe_id: recipe.id,
+            revision,
+            user_id: *user_id,
+            snapshot,
+        })
+        .execute(connection)
+        .await?;
+    debug!(recipe_id = recipe.id, revision; "Recorded recipe revision");
+
+    return Ok(());
+}
+
+fn build_snapshot(
+    recipe: &Recipe,
+    categories: &[Category],
+    parts: &[RecipePart],
+    steps: &[RecipeStep],
+) -> RecipeSnapshot {
+    let mut category_names: Vec<String> = categories.iter().map(|c| c.name.clone()).collect();
+    category_names.sort();
+
+    let ingredient_names: HashMap<i32, &str> = parts
+        .iter()
+        .flat_map(|part| part.ingredients.iter())
+        .map(|(_, ingredient)| (ingredient.id, ingredient.name.as_str()))
+        .collect();
+
+    return RecipeSnapshot {
+        name: recipe.name.clone(),
+        instructions: recipe.instructions.clone(),
+        cuisine: recipe.cuisine.clone(),
+        duration_min: recipe.duration_min,
+        preparation_needed: recipe.preparation_needed,
+        portions: recipe.portions,
+        difficulty: recipe.difficulty,
+        visibility: recipe.visibility,
+        categories: category_names,
+        ingredients: parts
+            .iter()
+            .flat_map(|part| part.ingredients.iter())
+            .map(|(rec_ing, ingredient)| SnapshotIngredient {
+                id: ingredient.id,
+                name: ingredient.name.clone(),
+                part: rec_ing.part,
+                quantity: rec_ing.quantity.clone(),
+                unit: rec_ing.unit.clone(),
+            })
+            .collect(),
+        steps: steps
+            .iter()
+            .map(|step| SnapshotStep {
+                text: step.text.clone(),
+                duration_min: step.duration_min,
+                ingredients: step
+                    .ingredient_ids
+                    .iter()
+                    .filter_map(|id| ingredient_names.get(id).map(|name| name.to_string()))
+                    .collect(),
+            })
+            .collect(),
+    };
+}
+
+async fn load_revision(
+    connection: &mut AsyncPgConnection,
+    recipe_id: &i32,
+    revision: &i32,
+) -> Result<(RecipeRevision, RecipeSnapshot), diesel::result::Error> {
+    let recipe_revision = recipe_revisions::table
+        .filter(recipe_revisions::recipe_id.eq(recipe_id))
+        .filter(recipe_revisions::revision.eq(revision))
+        .select(RecipeRevision::as_select())
+        .first(connection)
+        .await?;
+    let snapshot = serde_json::from_value(recipe_revision.snapshot.clone())
+        .map_err(|e| diesel::result::Error::DeserializationError(Box::new(e)))?;
+
+    return Ok((recipe_revision, snapshot));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes_service::ingredients::{merge_ingredients, update_ingredient};
+    use crate::recipes_service::models::ingredient::ChangeIngredient;
+    use crate::recipes_service::models::recipe::Visibility;
+    use crate::recipes_service::recipes::create_recipe;
+    use crate::recipes_service::test_utils::{new_recipe, part, recipe, test_pool};
+    use crate::recipes_service::users::create_user;
+    use bigdecimal::BigDecimal;
+    use serde_json::json;
+
+    fn step(ordinal: i32, ingredient_ids: Vec<i32>) -> RecipeStep {
+        return RecipeStep {
+            recipe_id: 1,
+            ordinal,
+            text: format!("step {ordinal}"),
+            duration_min: None,
+            ingredient_ids,
+        };
+    }
+
+    fn fields(changes: &[FieldChange]) -> Vec<&str> {
+        return changes.iter().map(|change| change.field.as_str()).collect();
+    }
+
+    #[test]
+    fn snapshots_ingredients_with_ids_and_steps_with_names() {
+        let categories = [
+            Category {
+                name: "sweet".to_string(),
+                version: 1,
+            },
+            Category {
+                name: "breakfast".to_string(),
+                version: 1,
+            },
+        ];
+        let parts = [
+            part(0, &[(7, "flour", "200", "g"), (3, "milk", "300", "ml")]),
+            part(1, &[(9, "sugar", "1", "tbsp")]),
+        ];
+        // an id of no recipe ingredient is left out
+        let steps = [step(1, vec![7, 3]), step(2, vec![9, 42])];
+
+        let snapshot = build_snapshot(&recipe(1), &categories, &parts, &steps);
+        assert_eq!(snapshot.name, "Pancakes");
+        assert_eq!(snapshot.visibility, Visibility::Public);
+        assert_eq!(snapshot.categories, vec!["breakfast", "sweet"]);
+        let ingredients: Vec<(i32, &str, i16)> = snapshot
+            .ingredients
+            .iter()
+            .map(|ingredient| (ingredient.id, ingredient.name.as_str(), ingredient.part))
+            .collect();
+        assert_eq!(
+            ingredients,
+            vec![(7, "flour", 0), (3, "milk", 0), (9, "sugar", 1)]
+        );
+        assert_eq!(snapshot.steps[0].ingredients, vec!["flour", "milk"]);
+        assert_eq!(snapshot.steps[1].ingredients, vec!["sugar"]);
+    }
+
+    #[test]
+    fn diffs_changed_fields_by_name() {
+        let parts = [part(0, &[(7, "flour", "200", "g")])];
+        let from = build_snapshot(&recipe(1), &[], &parts, &[]);
+        let mut changed = recipe(1);
+        changed.portions = 6;
+        changed.name = "Crêpes".to_string();
+        let parts = [part(0, &[(7, "flour", "300", "g")])];
+        let to = build_snapshot(&changed, &[], &parts, &[]);
+
+        let changes = diff_snapshots(
+            serde_json::to_value(from).unwrap(),
+            serde_json::to_value(to).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(fields(&changes), vec!["ingredients", "name", "portions"]);
+        assert_eq!(changes[1].from, json!("Pancakes"));
+        assert_eq!(changes[1].to, json!("Crêpes"));
+        assert_eq!(changes[2].from, json!(4));
+        assert_eq!(changes[2].to, json!(6));
+    }
+
+    #[test]
+    fn diffs_fields_present_in_one_snapshot() {
+        let changes = diff_snapshots(
+            json!({"name": "a", "removed": 1}),
+            json!({"name": "a", "added": true}),
+        )
+        .unwrap();
+        assert_eq!(fields(&changes), vec!["added", "removed"]);
+        assert_eq!(changes[0].from, serde_json::Value::Null);
+        assert_eq!(changes[1].to, serde_json::Value::Null);
+        assert!(diff_snapshots(json!({}), json!({})).unwrap().is_empty());
+        assert!(diff_snapshots(json!([]), json!({})).is_err());
+    }
+
+    #[actix_web::test]
+    async fn restores_renamed_and_merged_ingredients() {
+        let Some(db_pool) = test_pool().await else {
+            return;
+        };
+        let (user, _) = create_user(db_pool.clone(), "cook", "hash").await.unwrap();
+        let quantity = BigDecimal::from(100);
+        let rec_ings: Vec<NewRecipeIngredient> = ["flour", "sugar", "salt"]
+            .into_iter()
+            .map(|name| NewRecipeIngredient {
+                name,
+                part: 0,
+                quantity: &quantity,
+                unit: "g",
+            })
+            .collect();
+        let (recipe, _, parts, _) = create_recipe(
+            db_pool.clone(),
+            &new_recipe(user.id),
+            &vec![],
+            &rec_ings,
+            &vec![],
+        )
+        .await
+        .unwrap();
+        let ids: HashMap<String, i32> = parts[0]
+            .ingredients
+            .iter()
+            .map(|(_, ingredient)| (ingredient.name.clone(), ingredient.id))
+            .collect();
+
+        let rename = ChangeIngredient {
+            name: Some("wheat flour".to_string()),
+        };
+        update_ingredient(db_pool.clone(), &ids["flour"], &rename)
+            .await
+            .unwrap();
+        merge_ingredients(db_pool.clone(), &ids["salt"], &vec![ids["sugar"]])
+            .await
+            .unwrap();
+
+        let (_, _, parts, _) = restore_revision(db_pool, &user.id, &recipe.id, &1, &None)
+            .await
+            .unwrap();
+        let restored: Vec<(&str, &BigDecimal)> = parts[0]
+            .ingredients
+            .iter()
+            .map(|(rec_ing, ingredient)| (ingredient.name.as_str(), &rec_ing.quantity))
+            .collect();
+        assert_eq!(
+            restored,
+            vec![
+                ("salt", &quantity),
+                ("sugar", &quantity),
+                ("wheat flour", &quantity)
+            ]
+        );
+        // the renamed ingredient is followed, the merged one is created again
+        let restored_id = |name: &str| {
+            return parts[0]
+                .ingredients
+                .iter()
+                .find(|(_, ingredient)| ingredient.name == name)
+                .map(|(_, ingredient)| ingredient.id);
+        };
+        assert_eq!(restored_id("wheat flour"), Some(ids["flour"]));
+        assert_ne!(restored_id("sugar"), Some(ids["sugar"]));
+    }
+}
@@ -0,0 +1,138 @@
+use bigdecimal::{BigDecimal, RoundingMode};
+
+use super::errors::ServiceError;
+use super::models::ingredient::RecipePart;
+use super::units::{find_unit, UnitKind};
+
+/// Scale ingredient quantities of a recipe cooked for `portions` to `target_portions`.
+pub fn scale_parts(
+    parts: &mut [RecipePart],
+    portions: i32,
+    target_portions: i32,
+) -> Result<(), ServiceError> {
+    if portions <= 0 {
+        return Err(ServiceError::InvalidValue {
+            field: "portions",
+            detail: "Recipe does not specify a positive number of portions".to_string(),
+        });
+    }
+    if target_portions <= 0 {
+        return Err(ServiceError::InvalidValue {
+            field: "portions",
+            detail: "Requested number of portions must be positive".to_string(),
+        });
+    }
+
+    let factor = BigDecimal::from(target_portions) / BigDecimal::from(portions);
+    for part in parts.iter_mut() {
+        for (rec_ing, _) in part.ingredients.iter_mut() {
+            let scaled = &rec_ing.quantity * &factor;
+            rec_ing.quantity = round_quantity(&scaled, &rec_ing.unit);
+        }
+    }
+
+    return Ok(());
+}
+
+/// Round a scaled quantity to a precision that makes sense in a kitchen for the given unit.
+pub fn round_quantity(quantity: &BigDecimal, unit: &str) -> BigDecimal {
+    let rounded = match find_unit(unit).map(|unit| (unit.code, unit.kind)) {
+        // small units -- whole numbers, one decimal for tiny amounts
+        Some(("g" | "ml", _)) if quantity >= &BigDecimal::from(10) => round_to_step(quantity, 1),
+        Some(("g" | "ml", _)) => round_to_step(quantity, 10),
+        // spoons and cups are measured in quarters
+        Some(("tsp" | "tbsp" | "cup", _)) => round_to_step(quantity, 4),
+        // countable items can be halved
+        Some((_, UnitKind::Count)) => round_to_step(quantity, 2),
+        _ => round_to_step(quantity, 100),
+    };
+
+    return rounded.normalized();
+}
+
+/// round to the nearest `1 / steps`, never rounding a non-zero quantity to zero
+fn round_to_step(quantity: &BigDecimal, steps: i64) -> BigDecimal {
+    let steps = BigDecimal::from(steps);
+    let rounded = (quantity * &steps).with_scale_round(0, RoundingMode::HalfUp) / &steps;
+    if rounded == BigDecimal::from(0) && quantity > &BigDecimal::from(0) {
+        return BigDecimal::from(1) / steps;
+    }
+
+    return rounded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes_service::test_utils::{decimal, part};
+
+    fn scaled(quantities: &[(&str, &str)], portions: i32, target_portions: i32) -> Vec<String> {
+        let ingredients: Vec<(i32, &str, &str, &str)> = quantities
+            .iter()
+            .zip(1..)
+            .map(|((quantity, unit), id)| (id, "ingredient", *quantity, *unit))
+            .collect();
+        let mut parts = vec![part(0, &ingredients)];
+        scale_parts(&mut parts, portions, target_portions).unwrap();
+        return parts[0]
+            .ingredients
+            .iter()
+            .map(|(rec_ing, _)| rec_ing.quantity.to_string())
+            .collect();
+    }
+
+    #[test]
+    fn scales_up_and_down() {
+        assert_eq!(
+            scaled(&[("200", "g"), ("2", "pc"), ("1", "cup")], 4, 8),
+            vec!["400", "4", "2"]
+        );
+        assert_eq!(
+            scaled(&[("200", "g"), ("2", "pc"), ("1", "cup")], 4, 2),
+            vec!["100", "1", "0.5"]
+        );
+    }
+
+    #[test]
+    fn scales_by_fractional_factors() {
+        // 2/3 of the recipe
+        assert_eq!(
+            scaled(
+                &[("250", "g"), ("3", "pc"), ("1", "tbsp"), ("1", "l")],
+                3,
+                2
+            ),
+            vec!["167", "2", "0.75", "0.67"]
+        );
+    }
+
+    #[test]
+    fn rounds_to_kitchen_precision() {
+        assert_eq!(round_quantity(&decimal("12.6"), "g"), decimal("13"));
+        assert_eq!(round_quantity(&decimal("2.46"), "ml"), decimal("2.5"));
+        assert_eq!(round_quantity(&decimal("0.3"), "tsp"), decimal("0.25"));
+        assert_eq!(round_quantity(&decimal("1.3"), "pc"), decimal("1.5"));
+        assert_eq!(round_quantity(&decimal("1.234"), "kg"), decimal("1.23"));
+        assert_eq!(
+            round_quantity(&decimal("1.234"), "handful"),
+            decimal("1.23")
+        );
+    }
+
+    #[test]
+    fn keeps_small_quantities_above_zero() {
+        assert_eq!(round_quantity(&decimal("0.01"), "g"), decimal("0.1"));
+        assert_eq!(round_quantity(&decimal("0.01"), "tsp"), decimal("0.25"));
+        assert_eq!(round_quantity(&decimal("0.1"), "pc"), decimal("0.5"));
+        assert_eq!(round_quantity(&decimal("0.001"), "kg"), decimal("0.01"));
+        assert_eq!(scaled(&[("1", "pinch")], 1000, 1), vec!["0.5"]);
+    }
+
+    #[test]
+    fn rejects_non_positive_portions() {
+        let mut parts = vec![part(0, &[(1, "flour", "1", "g")])];
+        assert!(scale_parts(&mut parts, 0, 2).is_err());
+        assert!(scale_parts(&mut parts, 2, 0).is_err());
+        assert!(scale_parts(&mut parts, 2, -1).is_err());
+    }
+}
@@ -1,8 +1,71 @@
 // @generated automatically by Diesel CLI.
 
+pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "meal_slot"))]
+    pub struct MealSlot;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "recipe_visibility"))]
+    pub struct RecipeVisibility;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "share_access"))]
+    pub struct ShareAccess;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "tsvector", schema = "pg_catalog"))]
+    pub struct Tsvector;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "user_role"))]
+    pub struct UserRole;
+}
+
+diesel::table! {
+    api_keys (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
+        key_hash -> Varchar,
+        prefix -> Varchar,
+        scopes -> Array<Text>,
+        created_at -> Timestamptz,
+        expires_at -> Nullable<Timestamptz>,
+        last_used_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     categories (name) {
         name -> Varchar,
+        version -> Int4,
+    }
+}
+
+diesel::table! {
+    collection_recipes (collection_id, recipe_id) {
+        collection_id -> Int4,
+        recipe_id -> Int4,
+        position -> Int4,
+    }
+}
+
+diesel::table! {
+    collections (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
+        share_token -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    favourites (user_id, recipe_id) {
+        user_id -> Int4,
+        recipe_id -> Int4,
+        created_at -> Timestamptz,
     }
 }
 
@@ -17,9 +80,38 @@ diesel::table! {
 }
 
 diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Tsvector;
+
     ingredients (id) {
         id -> Int4,
         name -> Varchar,
+        search_vector -> Tsvector,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::MealSlot;
+
+    meal_plan_entries (id) {
+        id -> Int4,
+        user_id -> Int4,
+        date -> Date,
+        slot -> MealSlot,
+        recipe_id -> Int4,
+        portions -> Int4,
+    }
+}
+
+diesel::table! {
+    pantry_items (id) {
+        id -> Int4,
+        user_id -> Int4,
+        ingredient_id -> Int4,
+        quantity -> Numeric,
+        unit -> Varchar,
+        expires_on -> Nullable<Date>,
     }
 }
 
@@ -41,6 +133,42 @@ diesel::table! {
 }
 
 diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ShareAccess;
+
+    recipe_shares (recipe_id, user_id) {
+        recipe_id -> Int4,
+        user_id -> Int4,
+        access -> ShareAccess,
+    }
+}
+
+diesel::table! {
+    recipe_revisions (id) {
+        id -> Int4,
+        recipe_id -> Int4,
+        revision -> Int4,
+        user_id -> Nullable<Int4>,
+        created_at -> Timestamptz,
+        snapshot -> Jsonb,
+    }
+}
+
+diesel::table! {
+    recipe_steps (recipe_id, ordinal) {
+        recipe_id -> Int4,
+        ordinal -> Int4,
+        text -> Text,
+        duration_min -> Nullable<Int4>,
+        ingredient_ids -> Array<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::Tsvector;
+    use super::sql_types::RecipeVisibility;
+
     recipes (id) {
         id -> Int4,
         name -> Varchar,
@@ -50,20 +178,122 @@ diesel::table! {
         preparation_needed -> Bool,
         portions -> Int4,
         difficulty -> Int4,
+        search_vector -> Tsvector,
+        owner_id -> Nullable<Int4>,
+        visibility -> RecipeVisibility,
+        rating_avg -> Nullable<Numeric>,
+        rating_count -> Int4,
+        version -> Int4,
+    }
+}
+
+diesel::table! {
+    reviews (recipe_id, user_id) {
+        recipe_id -> Int4,
+        user_id -> Int4,
+        rating -> Int2,
+        text -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    sessions (token_hash) {
+        token_hash -> Varchar,
+        user_id -> Int4,
+        created_at -> Timestamptz,
+        expires_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::UserRole;
+
+    user_roles (user_id, role) {
+        user_id -> Int4,
+        role -> UserRole,
+    }
+}
+
+diesel::table! {
+    shopping_list_items (id) {
+        id -> Int4,
+        shopping_list_id -> Int4,
+        name -> Varchar,
+        quantity -> Numeric,
+        unit -> Varchar,
+        checked -> Bool,
+    }
+}
+
+diesel::table! {
+    shopping_lists (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    users (id) {
+        id -> Int4,
+        username -> Varchar,
+        password_hash -> Varchar,
+        created_at -> Timestamptz,
     }
 }
 
+diesel::joinable!(api_keys -> users (user_id));
+diesel::joinable!(collection_recipes -> collections (collection_id));
+diesel::joinable!(collection_recipes -> recipes (recipe_id));
+diesel::joinable!(collections -> users (user_id));
+diesel::joinable!(favourites -> recipes (recipe_id));
+diesel::joinable!(favourites -> users (user_id));
 diesel::joinable!(images -> recipes (recipe_id));
+diesel::joinable!(meal_plan_entries -> recipes (recipe_id));
+diesel::joinable!(meal_plan_entries -> users (user_id));
+diesel::joinable!(pantry_items -> ingredients (ingredient_id));
+diesel::joinable!(pantry_items -> users (user_id));
 diesel::joinable!(recipe_category -> categories (category_name));
 diesel::joinable!(recipe_category -> recipes (recipe_id));
 diesel::joinable!(recipe_ingredient -> ingredients (ingredient_id));
 diesel::joinable!(recipe_ingredient -> recipes (recipe_id));
+diesel::joinable!(recipe_revisions -> recipes (recipe_id));
+diesel::joinable!(recipe_revisions -> users (user_id));
+diesel::joinable!(recipe_shares -> recipes (recipe_id));
+diesel::joinable!(recipe_shares -> users (user_id));
+diesel::joinable!(recipe_steps -> recipes (recipe_id));
+diesel::joinable!(recipes -> users (owner_id));
+diesel::joinable!(reviews -> recipes (recipe_id));
+diesel::joinable!(reviews -> users (user_id));
+diesel::joinable!(sessions -> users (user_id));
+diesel::joinable!(shopping_list_items -> shopping_lists (shopping_list_id));
+diesel::joinable!(shopping_lists -> users (user_id));
+diesel::joinable!(user_roles -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    api_keys,
     categories,
+    collection_recipes,
+    collections,
+    favourites,
     images,
     ingredients,
+    meal_plan_entries,
+    pantry_items,
     recipe_category,
     recipe_ingredient,
+    recipe_revisions,
+    recipe_shares,
+    recipe_steps,
     recipes,
+    reviews,
+    sessions,
+    shopping_list_items,
+    shopping_lists,
+    user_roles,
+    users,
 );
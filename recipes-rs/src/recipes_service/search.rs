@@ -0,0 +1,61 @@
+use diesel::define_sql_function;
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
+use diesel::sql_types::{Float, Nullable, Text};
+
+use super::schema::sql_types::Tsvector;
+
+#[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+#[diesel(postgres_type(name = "tsquery", schema = "pg_catalog"))]
+pub struct Tsquery;
+
+#[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+#[diesel(postgres_type(name = "regconfig", schema = "pg_catalog"))]
+pub struct Regconfig;
+
+diesel::infix_operator!(Matches, " @@ ", backend: diesel::pg::Pg);
+
+define_sql_function! { fn to_tsquery(config: Regconfig, query: Text) -> Tsquery; }
+define_sql_function! { fn ts_rank(vector: Tsvector, query: Tsquery) -> Float; }
+define_sql_function! { fn coalesce(value: Nullable<Float>, default: Float) -> Float; }
+define_sql_function! {
+    fn ts_headline(config: Regconfig, document: Text, query: Tsquery, options: Text) -> Text;
+}
+
+define_sql_function! { fn replace(string: Text, from: Text, to: Text) -> Text; }
+
+type ReplaceText<T> = replace<T, &'static str, &'static str>;
+
+/// Options of `ts_headline` used for search result snippets. Matches are wrapped in `<mark>`,
+/// so the document must be escaped with [`escape_html`] first.
+pub const HEADLINE_OPTIONS: &str = "StartSel=<mark>, StopSel=</mark>, MaxFragments=2";
+
+/// text with the HTML special characters `&`, `<` and `>` escaped
+pub fn escape_html<T>(text: T) -> ReplaceText<ReplaceText<ReplaceText<T>>>
+where
+    T: diesel::expression::AsExpression<Text>,
+{
+    return replace(
+        replace(replace(text, "&", "&amp;"), "<", "&lt;"),
+        ">",
+        "&gt;",
+    );
+}
+
+/// text search configuration the `search_vector` columns are generated with
+pub fn search_config() -> SqlLiteral<Regconfig> {
+    return sql("'english'::regconfig");
+}
+
+/// build a `to_tsquery` input matching any word of the free text, `None` when there is no word
+pub fn any_word_query(text: &str) -> Option<String> {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    return Some(words.join(" | "));
+}
@@ -0,0 +1,124 @@
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::info;
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::models::recipe_share::{Access, RecipeShare};
+use super::models::user::User;
+use super::recipes::{get_accessible_recipe, RecipeAccess};
+use super::schema::{recipe_shares, users};
+use super::utils::get_connection;
+
+/// users the recipe is shared with, only for the owner of the recipe
+pub async fn list_recipe_shares(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+) -> Result<Vec<(RecipeShare, User)>, ServiceError> {
+    info!(recipe_id, user_id; "Listing recipe shares");
+    let mut connection = get_connection(db_pool).await?;
+    let recipe = get_accessible_recipe(
+        &mut connection,
+        &Some(*user_id),
+        recipe_id,
+        RecipeAccess::Owner,
+    )
+    .await?;
+
+    return Ok(RecipeShare::belonging_to(&recipe)
+        .inner_join(users::table)
+        .select((RecipeShare::as_select(), User::as_select()))
+        .order_by(users::username)
+        .load(&mut connection)
+        .await?);
+}
+
+/// share the recipe with a user or change the access of an existing share
+pub async fn share_recipe(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+    username: &str,
+    access: &Access,
+) -> Result<(RecipeShare, User), ServiceError> {
+    info!(recipe_id, user_id, username, access:serde; "Sharing recipe");
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let recipe = get_accessible_recipe(
+                    connection,
+                    &Some(*user_id),
+                    recipe_id,
+                    RecipeAccess::Owner,
+                )
+                .await?;
+                let user = find_user(connection, username).await?;
+                if recipe.owner_id == Some(user.id) {
+                    return Err(ServiceError::InvalidValue {
+                        field: "username",
+                        detail: "The owner cannot share a recipe with themselves".to_string(),
+                    });
+                }
+
+                let share = diesel::insert_into(recipe_shares::table)
+                    .values(&RecipeShare {
+                        recipe_id: recipe.id,
+                        user_id: user.id,
+                        access: *access,
+                    })
+                    .on_conflict((recipe_shares::recipe_id, recipe_shares::user_id))
+                    .do_update()
+                    .set(recipe_shares::access.eq(excluded(recipe_shares::access)))
+                    .returning(RecipeShare::as_returning())
+                    .get_result(connection)
+                    .await?;
+
+                return Ok((share, user));
+            })
+        })
+        .await;
+}
+
+pub async fn unshare_recipe(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    recipe_id: &i32,
+    username: &str,
+) -> Result<(), ServiceError> {
+    info!(recipe_id, user_id, username; "Unsharing recipe");
+    let mut connection = get_connection(db_pool).await?;
+    let recipe = get_accessible_recipe(
+        &mut connection,
+        &Some(*user_id),
+        recipe_id,
+        RecipeAccess::Owner,
+    )
+    .await?;
+    let user = find_user(&mut connection, username).await?;
+
+    diesel::delete(recipe_shares::table.find((recipe.id, user.id)))
+        .execute(&mut connection)
+        .await?;
+
+    return Ok(());
+}
+
+async fn find_user(
+    connection: &mut AsyncPgConnection,
+    username: &str,
+) -> Result<User, ServiceError> {
+    return users::table
+        .filter(users::username.eq(username))
+        .select(User::as_select())
+        .first(connection)
+        .await
+        .optional()?
+        .ok_or_else(|| ServiceError::InvalidValue {
+            field: "username",
+            detail: format!("User '{username}' does not exist"),
+        });
+}
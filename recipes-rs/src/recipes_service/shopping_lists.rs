@@ -0,0 +1,319 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::{debug, info};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::errors::ServiceError;
+use super::meal_plans::MAX_RANGE_DAYS;
+use super::models::shopping_list::{
+    NewShoppingList, NewShoppingListItem, ShoppingList, ShoppingListItem,
+};
+use super::quantity::max_quantity;
+use super::recipes::{filter_recipes, get_accessible_recipe, RecipeAccess, RecipeFilter};
+use super::scaling::round_quantity;
+use super::schema::{
+    ingredients, meal_plan_entries, recipe_ingredient, recipes, shopping_list_items, shopping_lists,
+};
+use super::utils::get_connection;
+
+/// what to shop for -- recipes with portions and/or the meal plan between two dates
+#[derive(Serialize, Default)]
+pub struct ShoppingSource {
+    /// recipe ids with wanted portions, `None` keeps the portions of the recipe
+    pub recipes: Vec<(i32, Option<i32>)>,
+    pub plan_dates: Option<(NaiveDate, NaiveDate)>,
+}
+
+/// total amount of an ingredient in one unit
+#[derive(Serialize, Debug)]
+pub struct ShoppingItem {
+    pub name: String,
+    pub quantity: BigDecimal,
+    pub unit: String,
+}
+
+/// Sum ingredients of the recipes scaled to the wanted portions. Items are ordered by
+/// ingredient name so the items of one ingredient are next to each other.
+pub async fn compute_shopping_list(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    source: &ShoppingSource,
+) -> Result<Vec<ShoppingItem>, ServiceError> {
+    info!(user_id, source:serde; "Computing shopping list");
+    let mut connection = get_connection(db_pool).await?;
+    return aggregate_ingredients(&mut connection, user_id, source).await;
+}
+
+pub async fn create_shopping_list(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    name: &str,
+    source: &ShoppingSource,
+) -> Result<(ShoppingList, Vec<ShoppingListItem>), ServiceError> {
+    info!(user_id, name, source:serde; "Creating shopping list");
+    if name.trim().is_empty() {
+        return Err(ServiceError::InvalidValue {
+            field: "name",
+            detail: "must not be empty".to_string(),
+        });
+    }
+
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let items = aggregate_ingredients(connection, user_id, source).await?;
+                let shopping_list = diesel::insert_into(shopping_lists::table)
+                    .values(NewShoppingList {
+                        user_id: *user_id,
+                        name: name.trim(),
+                    })
+                    .returning(ShoppingList::as_returning())
+                    .get_result(connection)
+                    .await?;
+
+                let new_items: Vec<NewShoppingListItem> = items
+                    .into_iter()
+                    .map(|item| NewShoppingListItem {
+                        shopping_list_id: shopping_list.id,
+                        name: item.name,
+                        quantity: item.quantity,
+                        unit: item.unit,
+                    })
+                    .collect();
+                diesel::insert_into(shopping_list_items::table)
+                    .values(&new_items)
+                    .execute(connection)
+                    .await?;
+                debug!(items = new_items.len(); "Created shopping list items");
+
+                let items = load_items(connection, &shopping_list).await?;
+                return Ok((shopping_list, items));
+            })
+        })
+        .await;
+}
+
+pub async fn list_shopping_lists(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+) -> Result<Vec<(ShoppingList, Vec<ShoppingListItem>)>, ServiceError> {
+    info!(user_id; "Listing shopping lists");
+    let mut connection = get_connection(db_pool).await?;
+    let all_lists = shopping_lists::table
+        .filter(shopping_lists::user_id.eq(user_id))
+        .select(ShoppingList::as_select())
+        .order_by(shopping_lists::created_at.desc())
+        .load(&mut connection)
+        .await?;
+    let all_items = ShoppingListItem::belonging_to(&all_lists)
+        .select(ShoppingListItem::as_select())
+        .order_by((
+            shopping_list_items::name,
+            shopping_list_items::unit,
+            shopping_list_items::id,
+        ))
+        .load(&mut connection)
+        .await?;
+
+    return Ok(all_items
+        .grouped_by(&all_lists)
+        .into_iter()
+        .zip(all_lists)
+        .map(|(items, shopping_list)| (shopping_list, items))
+        .collect());
+}
+
+pub async fn get_shopping_list(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    shopping_list_id: &i32,
+) -> Result<(ShoppingList, Vec<ShoppingListItem>), ServiceError> {
+    info!(user_id, shopping_list_id; "Getting shopping list");
+    let mut connection = get_connection(db_pool).await?;
+    let shopping_list = get_user_list(&mut connection, user_id, shopping_list_id).await?;
+    let items = load_items(&mut connection, &shopping_list).await?;
+
+    return Ok((shopping_list, items));
+}
+
+/// tick an item off or back on
+pub async fn check_shopping_list_item(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    shopping_list_id: &i32,
+    item_id: &i32,
+    checked: &bool,
+) -> Result<ShoppingListItem, ServiceError> {
+    info!(user_id, shopping_list_id, item_id, checked; "Checking shopping list item");
+    let mut connection = get_connection(db_pool).await?;
+    let shopping_list = get_user_list(&mut connection, user_id, shopping_list_id).await?;
+
+    return Ok(diesel::update(
+        ShoppingListItem::belonging_to(&shopping_list).filter(shopping_list_items::id.eq(item_id)),
+    )
+    .set(shopping_list_items::checked.eq(checked))
+    .returning(ShoppingListItem::as_returning())
+    .get_result(&mut connection)
+    .await?);
+}
+
+pub async fn delete_shopping_list(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+    shopping_list_id: &i32,
+) -> Result<(), ServiceError> {
+    info!(user_id, shopping_list_id; "Deleting shopping list");
+    let mut connection = get_connection(db_pool).await?;
+    let shopping_list = get_user_list(&mut connection, user_id, shopping_list_id).await?;
+    diesel::delete(&shopping_list)
+        .execute(&mut connection)
+        .await?;
+
+    return Ok(());
+}
+
+async fn aggregate_ingredients(
+    connection: &mut AsyncPgConnection,
+    user_id: &i32,
+    source: &ShoppingSource,
+) -> Result<Vec<ShoppingItem>, ServiceError> {
+    let mut wanted_recipes = vec![];
+    for (recipe_id, portions) in &source.recipes {
+        if portions.is_some_and(|portions| !(1..=1000).contains(&portions)) {
+            return Err(ServiceError::InvalidValue {
+                field: "portions",
+                detail: "must be between 1 and 1000".to_string(),
+            });
+        }
+        match get_accessible_recipe(connection, &Some(*user_id), recipe_id, RecipeAccess::Read)
+            .await
+        {
+            Err(ServiceError::DbDiesel(diesel::result::Error::NotFound)) => {
+                return Err(ServiceError::InvalidValue {
+                    field: "recipe_id",
+                    detail: format!("Recipe '{recipe_id}' does not exist"),
+                });
+            }
+            result => result?,
+        };
+        wanted_recipes.push((*recipe_id, *portions));
+    }
+
+    if let Some((from, to)) = &source.plan_dates {
+        if to < from || (*to - *from).num_days() >= MAX_RANGE_DAYS {
+            return Err(ServiceError::InvalidValue {
+                field: "to",
+                detail: format!("must be within {MAX_RANGE_DAYS} days after 'from'"),
+            });
+        }
+        // planned recipes may have been made private or unshared since
+        let recipe_filter = RecipeFilter::default();
+        let visible_recipes = filter_recipes(&Some(*user_id), &recipe_filter).select(recipes::id);
+        let planned: Vec<(i32, i32)> = meal_plan_entries::table
+            .filter(meal_plan_entries::user_id.eq(user_id))
+            .filter(meal_plan_entries::date.between(from, to))
+            .filter(meal_plan_entries::recipe_id.eq_any(visible_recipes))
+            .select((meal_plan_entries::recipe_id, meal_plan_entries::portions))
+            .load(connection)
+            .await?;
+        debug!(planned = planned.len(); "Adding planned recipes");
+        wanted_recipes.extend(
+            planned
+                .into_iter()
+                .map(|(recipe_id, portions)| (recipe_id, Some(portions))),
+        );
+    }
+
+    if wanted_recipes.is_empty() {
+        return Err(ServiceError::InvalidValue {
+            field: "recipes",
+            detail: "There are no recipes to shop for".to_string(),
+        });
+    }
+
+    let recipe_ids: Vec<i32> = wanted_recipes.iter().map(|(id, _)| *id).collect();
+    let rows: Vec<(i32, i32, String, BigDecimal, String)> = recipe_ingredient::table
+        .inner_join(ingredients::table)
+        .inner_join(recipes::table)
+        .filter(recipe_ingredient::recipe_id.eq_any(&recipe_ids))
+        .select((
+            recipe_ingredient::recipe_id,
+            recipes::portions,
+            ingredients::name,
+            recipe_ingredient::quantity,
+            recipe_ingredient::unit,
+        ))
+        .load(connection)
+        .await?;
+
+    // keyed by ingredient name and unit, ordered for grouping
+    let mut totals: BTreeMap<(String, String), BigDecimal> = BTreeMap::new();
+    for (recipe_id, portions) in &wanted_recipes {
+        for (_, recipe_portions, name, quantity, unit) in
+            rows.iter().filter(|row| row.0 == *recipe_id)
+        {
+            let quantity = match portions {
+                Some(portions) if *recipe_portions > 0 => {
+                    quantity * BigDecimal::from(*portions) / BigDecimal::from(*recipe_portions)
+                }
+                _ => quantity.clone(),
+            };
+            *totals
+                .entry((name.clone(), unit.clone()))
+                .or_insert_with(|| BigDecimal::from(0)) += quantity;
+        }
+    }
+
+    let max_quantity = max_quantity();
+    return totals
+        .into_iter()
+        .map(|((name, unit), quantity)| {
+            let quantity = round_quantity(&quantity, &unit);
+            if quantity > max_quantity {
+                return Err(ServiceError::InvalidValue {
+                    field: "portions",
+                    detail: format!("The total quantity of '{name}' is too large"),
+                });
+            }
+            return Ok(ShoppingItem {
+                quantity,
+                name,
+                unit,
+            });
+        })
+        .collect();
+}
+
+async fn get_user_list(
+    connection: &mut AsyncPgConnection,
+    user_id: &i32,
+    shopping_list_id: &i32,
+) -> Result<ShoppingList, diesel::result::Error> {
+    return shopping_lists::table
+        .find(shopping_list_id)
+        .filter(shopping_lists::user_id.eq(user_id))
+        .select(ShoppingList::as_select())
+        .first(connection)
+        .await;
+}
+
+async fn load_items(
+    connection: &mut AsyncPgConnection,
+    shopping_list: &ShoppingList,
+) -> Result<Vec<ShoppingListItem>, diesel::result::Error> {
+    return ShoppingListItem::belonging_to(shopping_list)
+        .select(ShoppingListItem::as_select())
+        .order_by((
+            shopping_list_items::name,
+            shopping_list_items::unit,
+            shopping_list_items::id,
+        ))
+        .load(connection)
+        .await;
+}
@@ -0,0 +1,142 @@
+use super::errors::ServiceError;
+use super::models::recipe_step::NewRecipeStep;
+
+/// Instructions and steps of a recipe kept in sync. Given steps win and are flattened into the
+/// instructions, plain instructions are kept as they are and split into steps. `None` when
+/// neither is given.
+pub fn instructions_and_steps(
+    instructions: Option<String>,
+    steps: Option<Vec<NewRecipeStep>>,
+) -> Result<Option<(String, Vec<NewRecipeStep>)>, ServiceError> {
+    return match (instructions, steps) {
+        (_, Some(steps)) => {
+            check_steps(&steps)?;
+            Ok(Some((flatten_steps(&steps), steps)))
+        }
+        (Some(instructions), None) => {
+            let steps = split_instructions(&instructions)
+                .into_iter()
+                .map(|text| NewRecipeStep {
+                    text,
+                    duration_min: None,
+                    ingredient_ids: vec![],
+                })
+                .collect();
+            Ok(Some((instructions, steps)))
+        }
+        (None, None) => Ok(None),
+    };
+}
+
+/// like `instructions_and_steps` for new recipes, which need one of them
+pub fn required_instructions_and_steps(
+    instructions: Option<String>,
+    steps: Option<Vec<NewRecipeStep>>,
+) -> Result<(String, Vec<NewRecipeStep>), ServiceError> {
+    return instructions_and_steps(instructions, steps)?.ok_or(ServiceError::InvalidValue {
+        field: "instructions",
+        detail: "instructions or steps are required".to_string(),
+    });
+}
+
+/// Split instructions text into steps. A step ends at a blank line or where the next line starts
+/// with a number like "2." or "3)", the number is dropped.
+pub fn split_instructions(instructions: &str) -> Vec<String> {
+    let mut steps: Vec<String> = vec![];
+    let mut current: Vec<&str> = vec![];
+    for line in instructions.lines() {
+        let line = line.trim();
+        let numbered = strip_numbering(line);
+        if (line.is_empty() || numbered.is_some()) && !current.is_empty() {
+            steps.push(current.join("\n"));
+            current.clear();
+        }
+        match numbered {
+            Some(text) => current.push(text),
+            None if !line.is_empty() => current.push(line),
+            None => (),
+        }
+    }
+    if !current.is_empty() {
+        steps.push(current.join("\n"));
+    }
+
+    return steps;
+}
+
+/// instructions text of the steps, one paragraph per step
+pub fn flatten_steps(steps: &[NewRecipeStep]) -> String {
+    return steps
+        .iter()
+        .map(|step| step.text.trim())
+        .collect::<Vec<&str>>()
+        .join("\n\n");
+}
+
+/// text after a leading "1." or "1)" followed by whitespace, so "1.5 cups" is not numbering
+fn strip_numbering(line: &str) -> Option<&str> {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 || digits > 3 {
+        return None;
+    }
+    let rest = line[digits..].strip_prefix(['.', ')'])?;
+    if !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    return Some(rest.trim_start());
+}
+
+fn check_steps(steps: &[NewRecipeStep]) -> Result<(), ServiceError> {
+    for step in steps {
+        if step.text.trim().is_empty() {
+            return Err(ServiceError::InvalidValue {
+                field: "steps",
+                detail: "step text must not be empty".to_string(),
+            });
+        }
+        if step.duration_min.is_some_and(|duration| duration < 0) {
+            return Err(ServiceError::InvalidValue {
+                field: "steps",
+                detail: "step duration must not be negative".to_string(),
+            });
+        }
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_blank_lines() {
+        assert_eq!(
+            split_instructions("Boil water.\nAdd salt.\n\n\nCook pasta."),
+            vec!["Boil water.\nAdd salt.", "Cook pasta."]
+        );
+    }
+
+    #[test]
+    fn splits_at_numbering() {
+        assert_eq!(
+            split_instructions("1. Boil water.\n2) Add salt.\n   3.\tCook pasta."),
+            vec!["Boil water.", "Add salt.", "Cook pasta."]
+        );
+    }
+
+    #[test]
+    fn keeps_numbers_that_are_not_numbering() {
+        assert_eq!(
+            split_instructions("Add\n1.5 cups of flour\n2023. was a good year"),
+            vec!["Add\n1.5 cups of flour\n2023. was a good year"]
+        );
+    }
+
+    #[test]
+    fn ignores_blank_instructions() {
+        assert!(split_instructions("").is_empty());
+        assert!(split_instructions(" \n\n \t").is_empty());
+    }
+}
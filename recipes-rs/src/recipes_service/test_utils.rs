@@ -0,0 +1,118 @@
+use bigdecimal::BigDecimal;
+use diesel_async::pooled_connection::deadpool::Pool;
+use diesel_async::pooled_connection::AsyncDieselConnectionManager;
+use diesel_async::{AsyncConnection, AsyncPgConnection, SimpleAsyncConnection};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{env, fs};
+
+use super::models::ingredient::{Ingredient, RecipeIngredient, RecipePart};
+use super::models::recipe::{NewRecipe, Recipe, Visibility};
+
+static SCHEMAS: AtomicUsize = AtomicUsize::new(0);
+
+/// Pool on a fresh schema of `TEST_DATABASE_URL` with all migrations applied. `None` without the
+/// variable, tests needing PostgreSQL pass without running then.
+pub async fn test_pool() -> Option<Arc<Pool<AsyncPgConnection>>> {
+    let database_url = env::var("TEST_DATABASE_URL").ok()?;
+    let schema = format!(
+        "test_{}_{}",
+        std::process::id(),
+        SCHEMAS.fetch_add(1, Ordering::SeqCst)
+    );
+    let mut connection = AsyncPgConnection::establish(&database_url)
+        .await
+        .expect("TEST_DATABASE_URL must be reachable");
+    connection
+        .batch_execute(&format!(
+            "DROP SCHEMA IF EXISTS {schema} CASCADE; CREATE SCHEMA {schema};"
+        ))
+        .await
+        .unwrap();
+
+    let separator = if database_url.contains('?') { '&' } else { '?' };
+    let schema_url = format!("{database_url}{separator}options=-c%20search_path%3D{schema}");
+    let pool = Pool::builder(AsyncDieselConnectionManager::<AsyncPgConnection>::new(
+        schema_url,
+    ))
+    .build()
+    .unwrap();
+
+    let mut migrations: Vec<_> = fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/migrations"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path().join("up.sql"))
+        .filter(|up| up.exists())
+        .collect();
+    migrations.sort();
+    let mut connection = pool.get().await.unwrap();
+    for up in migrations {
+        let sql = fs::read_to_string(&up).unwrap();
+        if let Err(error) = connection.batch_execute(&sql).await {
+            panic!("Migration {} failed: {error}", up.display());
+        }
+    }
+
+    return Some(Arc::new(pool));
+}
+
+pub fn decimal(text: &str) -> BigDecimal {
+    return BigDecimal::from_str(text).unwrap();
+}
+
+/// stored public recipe for 4 portions
+pub fn recipe(id: i32) -> Recipe {
+    return Recipe {
+        id,
+        name: "Pancakes".to_string(),
+        instructions: "Mix and fry.".to_string(),
+        cuisine: "french".to_string(),
+        duration_min: 20,
+        preparation_needed: false,
+        portions: 4,
+        difficulty: 2,
+        owner_id: Some(1),
+        visibility: Visibility::Public,
+        rating_avg: None,
+        rating_count: 0,
+        version: 1,
+    };
+}
+
+pub fn new_recipe(owner_id: i32) -> NewRecipe {
+    return NewRecipe {
+        name: "Pancakes".to_string(),
+        instructions: "Mix and fry.".to_string(),
+        cuisine: "french".to_string(),
+        duration_min: 20,
+        preparation_needed: false,
+        portions: 4,
+        difficulty: 2,
+        owner_id: Some(owner_id),
+        visibility: Visibility::Private,
+    };
+}
+
+/// part of recipe 1 with ingredients given as `(id, name, quantity, unit)`
+pub fn part(part: i16, ingredients: &[(i32, &str, &str, &str)]) -> RecipePart {
+    return RecipePart {
+        part,
+        ingredients: ingredients
+            .iter()
+            .map(|(id, name, quantity, unit)| {
+                let rec_ing = RecipeIngredient {
+                    recipe_id: 1,
+                    ingredient_id: *id,
+                    part,
+                    quantity: decimal(quantity),
+                    unit: unit.to_string(),
+                };
+                let ingredient = Ingredient {
+                    id: *id,
+                    name: name.to_string(),
+                };
+                return (rec_ing, ingredient);
+            })
+            .collect(),
+    };
+}
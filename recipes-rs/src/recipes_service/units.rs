@@ -0,0 +1,508 @@
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+use super::models::ingredient::RecipePart;
+use super::scaling::round_quantity;
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitKind {
+    /// base unit is gram
+    Mass,
+    /// base unit is millilitre
+    Volume,
+    /// units of this kind are not convertible to each other
+    Count,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitSystem {
+    Metric,
+    UsCustomary,
+}
+
+pub struct Unit {
+    /// canonical name stored in `recipe_ingredient.unit`
+    pub code: &'static str,
+    pub kind: UnitKind,
+    /// `None` for units used in both systems (spoons, pieces)
+    pub system: Option<UnitSystem>,
+    /// amount of the base unit of the kind in one unit
+    pub base_amount: &'static str,
+    pub aliases: &'static [&'static str],
+}
+
+pub const UNITS: &[Unit] = &[
+    // mass
+    Unit {
+        code: "mg",
+        kind: UnitKind::Mass,
+        system: Some(UnitSystem::Metric),
+        base_amount: "0.001",
+        aliases: &["milligram", "milligrams"],
+    },
+    Unit {
+        code: "g",
+        kind: UnitKind::Mass,
+        system: Some(UnitSystem::Metric),
+        base_amount: "1",
+        aliases: &["gr", "gram", "grams", "gramme", "grammes"],
+    },
+    Unit {
+        code: "kg",
+        kind: UnitKind::Mass,
+        system: Some(UnitSystem::Metric),
+        base_amount: "1000",
+        aliases: &["kilo", "kilos", "kilogram", "kilograms"],
+    },
+    Unit {
+        code: "oz",
+        kind: UnitKind::Mass,
+        system: Some(UnitSystem::UsCustomary),
+        base_amount: "28.3495",
+        aliases: &["ounce", "ounces"],
+    },
+    Unit {
+        code: "lb",
+        kind: UnitKind::Mass,
+        system: Some(UnitSystem::UsCustomary),
+        base_amount: "453.592",
+        aliases: &["lbs", "pound", "pounds"],
+    },
+    // volume
+    Unit {
+        code: "ml",
+        kind: UnitKind::Volume,
+        system: Some(UnitSystem::Metric),
+        base_amount: "1",
+        aliases: &["milliliter", "milliliters", "millilitre", "millilitres"],
+    },
+    Unit {
+        code: "cl",
+        kind: UnitKind::Volume,
+        system: Some(UnitSystem::Metric),
+        base_amount: "10",
+        aliases: &["centiliter", "centiliters", "centilitre", "centilitres"],
+    },
+    Unit {
+        code: "dl",
+        kind: UnitKind::Volume,
+        system: Some(UnitSystem::Metric),
+        base_amount: "100",
+        aliases: &["deciliter", "deciliters", "decilitre", "decilitres"],
+    },
+    Unit {
+        code: "l",
+        kind: UnitKind::Volume,
+        system: Some(UnitSystem::Metric),
+        base_amount: "1000",
+        aliases: &["liter", "liters", "litre", "litres"],
+    },
+    Unit {
+        code: "tsp",
+        kind: UnitKind::Volume,
+        system: None,
+        base_amount: "4.92892",
+        aliases: &["teaspoon", "teaspoons", "tsps"],
+    },
+    Unit {
+        code: "tbsp",
+        kind: UnitKind::Volume,
+        system: None,
+        base_amount: "14.7868",
+        aliases: &["tablespoon", "tablespoons", "tbs", "tbsps"],
+    },
+    Unit {
+        code: "fl oz",
+        kind: UnitKind::Volume,
+        system: Some(UnitSystem::UsCustomary),
+        base_amount: "29.5735",
+        aliases: &["floz", "fluid ounce", "fluid ounces"],
+    },
+    Unit {
+        code: "cup",
+        kind: UnitKind::Volume,
+        system: Some(UnitSystem::UsCustomary),
+        base_amount: "236.588",
+        aliases: &["cups", "c"],
+    },
+    Unit {
+        code: "pt",
+        kind: UnitKind::Volume,
+        system: Some(UnitSystem::UsCustomary),
+        base_amount: "473.176",
+        aliases: &["pint", "pints"],
+    },
+    Unit {
+        code: "qt",
+        kind: UnitKind::Volume,
+        system: Some(UnitSystem::UsCustomary),
+        base_amount: "946.353",
+        aliases: &["quart", "quarts"],
+    },
+    Unit {
+        code: "gal",
+        kind: UnitKind::Volume,
+        system: Some(UnitSystem::UsCustomary),
+        base_amount: "3785.41",
+        aliases: &["gallon", "gallons"],
+    },
+    // count
+    Unit {
+        code: "pc",
+        kind: UnitKind::Count,
+        system: None,
+        base_amount: "1",
+        aliases: &["", "pcs", "piece", "pieces", "x"],
+    },
+    Unit {
+        code: "pinch",
+        kind: UnitKind::Count,
+        system: None,
+        base_amount: "1",
+        aliases: &["pinches"],
+    },
+    Unit {
+        code: "clove",
+        kind: UnitKind::Count,
+        system: None,
+        base_amount: "1",
+        aliases: &["cloves"],
+    },
+    Unit {
+        code: "slice",
+        kind: UnitKind::Count,
+        system: None,
+        base_amount: "1",
+        aliases: &["slices"],
+    },
+    Unit {
+        code: "bunch",
+        kind: UnitKind::Count,
+        system: None,
+        base_amount: "1",
+        aliases: &["bunches"],
+    },
+    Unit {
+        code: "can",
+        kind: UnitKind::Count,
+        system: None,
+        base_amount: "1",
+        aliases: &["cans", "tin", "tins"],
+    },
+    Unit {
+        code: "pkg",
+        kind: UnitKind::Count,
+        system: None,
+        base_amount: "1",
+        aliases: &["package", "packages", "pack", "packs"],
+    },
+];
+
+/// Density of common ingredients in g/ml and whether metric recipes weigh them.
+const DENSITIES: &[(&str, &str, bool)] = &[
+    ("flour", "0.53", true),
+    ("all-purpose flour", "0.53", true),
+    ("wheat flour", "0.53", true),
+    ("sugar", "0.85", true),
+    ("brown sugar", "0.83", true),
+    ("powdered sugar", "0.56", true),
+    ("icing sugar", "0.56", true),
+    ("butter", "0.96", true),
+    ("rice", "0.85", true),
+    ("oats", "0.38", true),
+    ("rolled oats", "0.38", true),
+    ("cocoa", "0.42", true),
+    ("cocoa powder", "0.42", true),
+    ("salt", "1.2", false),
+    ("honey", "1.42", true),
+    ("water", "1", false),
+    ("milk", "1.03", false),
+    ("cream", "1.01", false),
+    ("oil", "0.92", false),
+    ("olive oil", "0.92", false),
+];
+
+/// find a unit by its code or alias, case-insensitive
+pub fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.trim().trim_end_matches('.').to_lowercase();
+    return UNITS
+        .iter()
+        .find(|unit| unit.code == name || unit.aliases.contains(&name.as_str()));
+}
+
+/// Quantity in the base unit of its kind. `None` for unknown and countable units, which only
+/// compare with quantities in the same unit.
+pub fn to_base_amount(quantity: &BigDecimal, unit: &str) -> Option<(UnitKind, BigDecimal)> {
+    return find_unit(unit)
+        .filter(|unit| unit.kind != UnitKind::Count)
+        .map(|unit| (unit.kind, quantity * base_amount(unit)));
+}
+
+/// Quantity of the ingredient in the base unit of `kind`, converting between mass and volume by
+/// the density of the ingredient. `None` for unknown and countable units and when the ingredient
+/// has no known density.
+pub fn to_kind_amount(
+    quantity: &BigDecimal,
+    unit: &str,
+    kind: UnitKind,
+    ingredient_name: &str,
+) -> Option<BigDecimal> {
+    let (unit_kind, amount) = to_base_amount(quantity, unit)?;
+    if unit_kind == kind {
+        return Some(amount);
+    }
+
+    let (density, _) = find_density(ingredient_name)?;
+    return match (unit_kind, kind) {
+        (UnitKind::Volume, UnitKind::Mass) => Some(amount * density),
+        (UnitKind::Mass, UnitKind::Volume) => Some(amount / density),
+        _ => None,
+    };
+}
+
+/// Add `other_quantity` in `other_unit` to `quantity` in `unit`, keeping `unit`. `None` when the
+/// units differ and are not of the same convertible kind.
+pub fn add_quantities(
+    quantity: &BigDecimal,
+    unit: &str,
+    other_quantity: &BigDecimal,
+    other_unit: &str,
+) -> Option<BigDecimal> {
+    let same_unit = match (find_unit(unit), find_unit(other_unit)) {
+        (Some(unit), Some(other_unit)) => unit.code == other_unit.code,
+        (None, None) => unit.trim().to_lowercase() == other_unit.trim().to_lowercase(),
+        _ => false,
+    };
+    if same_unit {
+        return Some(quantity + other_quantity);
+    }
+
+    let (kind, amount) = to_base_amount(other_quantity, other_unit)?;
+    let unit = find_unit(unit).filter(|unit| unit.kind == kind)?;
+    return Some(quantity + amount / base_amount(unit));
+}
+
+/// Render ingredient quantities in units of the given system. Ingredients with known density
+/// are converted between volume and mass to match how the system usually measures them.
+pub fn convert_parts(parts: &mut [RecipePart], system: &UnitSystem) {
+    for part in parts.iter_mut() {
+        for (rec_ing, ingredient) in part.ingredients.iter_mut() {
+            let Some(unit) = find_unit(&rec_ing.unit) else {
+                continue;
+            };
+            if unit.kind == UnitKind::Count {
+                continue;
+            }
+
+            let density = find_density(&ingredient.name);
+            let target_kind = match (system, &density) {
+                (UnitSystem::Metric, Some((_, true))) => UnitKind::Mass,
+                (UnitSystem::UsCustomary, Some(_)) => UnitKind::Volume,
+                _ => unit.kind,
+            };
+            let in_system = unit.system.is_none() || unit.system == Some(*system);
+            if in_system && unit.kind == target_kind {
+                continue;
+            }
+
+            let amount = &rec_ing.quantity * base_amount(unit);
+            let amount = match (unit.kind, target_kind, &density) {
+                (UnitKind::Volume, UnitKind::Mass, Some((density, _))) => amount * density,
+                (UnitKind::Mass, UnitKind::Volume, Some((density, _))) => amount / density,
+                _ => amount,
+            };
+
+            let target = best_unit(system, &target_kind, &amount);
+            rec_ing.quantity = round_quantity(&(amount / base_amount(target)), target.code);
+            rec_ing.unit = target.code.to_string();
+        }
+    }
+}
+
+/// the most readable unit of the system for an amount given in base units
+fn best_unit(system: &UnitSystem, kind: &UnitKind, amount: &BigDecimal) -> &'static Unit {
+    let at_least = |code: &str| amount >= &base_amount(find_unit(code).unwrap());
+    let code = match (system, kind) {
+        (UnitSystem::Metric, UnitKind::Mass) if at_least("kg") => "kg",
+        (UnitSystem::Metric, UnitKind::Mass) => "g",
+        (UnitSystem::Metric, _) if at_least("l") => "l",
+        (UnitSystem::Metric, _) => "ml",
+        (UnitSystem::UsCustomary, UnitKind::Mass) if at_least("lb") => "lb",
+        (UnitSystem::UsCustomary, UnitKind::Mass) => "oz",
+        // from a quarter of a cup (4 tbsp) on
+        (UnitSystem::UsCustomary, _) if quarter_cups(amount) >= BigDecimal::from(1) => "cup",
+        (UnitSystem::UsCustomary, _) if at_least("tbsp") => "tbsp",
+        (UnitSystem::UsCustomary, _) => "tsp",
+    };
+
+    return find_unit(code).unwrap();
+}
+
+fn quarter_cups(amount: &BigDecimal) -> BigDecimal {
+    return amount * BigDecimal::from(4) / base_amount(find_unit("cup").unwrap());
+}
+
+fn base_amount(unit: &Unit) -> BigDecimal {
+    return BigDecimal::from_str(unit.base_amount).unwrap();
+}
+
+fn find_density(ingredient_name: &str) -> Option<(BigDecimal, bool)> {
+    let name = ingredient_name.trim().to_lowercase();
+    let singular = name.strip_suffix('s').unwrap_or(&name);
+    return DENSITIES
+        .iter()
+        .find(|(density_name, _, _)| *density_name == name || *density_name == singular)
+        .map(|(_, density, by_mass)| (BigDecimal::from_str(density).unwrap(), *by_mass));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes_service::test_utils::{decimal, part};
+
+    fn converted(name: &str, quantity: &str, unit: &str, system: UnitSystem) -> (String, String) {
+        let mut parts = vec![part(0, &[(1, name, quantity, unit)])];
+        convert_parts(&mut parts, &system);
+        let (rec_ing, _) = &parts[0].ingredients[0];
+        return (rec_ing.quantity.to_string(), rec_ing.unit.clone());
+    }
+
+    fn pair(quantity: &str, unit: &str) -> (String, String) {
+        return (quantity.to_string(), unit.to_string());
+    }
+
+    #[test]
+    fn finds_units_by_code_and_alias() {
+        assert_eq!(find_unit("g").map(|unit| unit.code), Some("g"));
+        assert_eq!(find_unit(" Grams ").map(|unit| unit.code), Some("g"));
+        assert_eq!(find_unit("Tbsp.").map(|unit| unit.code), Some("tbsp"));
+        assert_eq!(
+            find_unit("fluid ounces").map(|unit| unit.code),
+            Some("fl oz")
+        );
+        assert_eq!(find_unit("").map(|unit| unit.code), Some("pc"));
+        assert!(find_unit("handful").is_none());
+    }
+
+    #[test]
+    fn converts_to_base_amounts() {
+        assert_eq!(
+            to_base_amount(&BigDecimal::from(2), "kg"),
+            Some((UnitKind::Mass, BigDecimal::from(2000)))
+        );
+        assert_eq!(
+            to_base_amount(&BigDecimal::from(3), "dl"),
+            Some((UnitKind::Volume, BigDecimal::from(300)))
+        );
+        assert_eq!(to_base_amount(&BigDecimal::from(2), "pc"), None);
+        assert_eq!(to_base_amount(&BigDecimal::from(2), "handful"), None);
+    }
+
+    #[test]
+    fn adds_quantities_in_the_first_unit() {
+        let add = |quantity: &str, unit: &str, other_quantity: &str, other_unit: &str| {
+            add_quantities(
+                &decimal(quantity),
+                unit,
+                &decimal(other_quantity),
+                other_unit,
+            )
+            .map(|sum| sum.normalized().to_string())
+        };
+        assert_eq!(add("200", "g", "0.1", "kg"), Some("300".to_string()));
+        assert_eq!(add("1", "l", "250", "ml"), Some("1.25".to_string()));
+        assert_eq!(add("2", "pc", "1", "pieces"), Some("3".to_string()));
+        assert_eq!(add("1", "handful", "2", "Handful"), Some("3".to_string()));
+        // different kinds, countable or unknown units cannot be added
+        assert_eq!(add("200", "g", "2", "pc"), None);
+        assert_eq!(add("200", "g", "1", "l"), None);
+        assert_eq!(add("2", "clove", "1", "pc"), None);
+        assert_eq!(add("1", "handful", "10", "g"), None);
+    }
+
+    #[test]
+    fn converts_to_metric() {
+        assert_eq!(
+            converted("milk", "1", "cup", UnitSystem::Metric),
+            pair("237", "ml")
+        );
+        assert_eq!(
+            converted("water", "2", "qt", UnitSystem::Metric),
+            pair("1.89", "l")
+        );
+        assert_eq!(
+            converted("beef", "2", "lb", UnitSystem::Metric),
+            pair("907", "g")
+        );
+        // weighed by density in metric recipes
+        assert_eq!(
+            converted("Flour", "1", "cup", UnitSystem::Metric),
+            pair("125", "g")
+        );
+        assert_eq!(
+            converted("sugar", "2", "tbsp", UnitSystem::Metric),
+            pair("25", "g")
+        );
+    }
+
+    #[test]
+    fn converts_to_us_customary() {
+        assert_eq!(
+            converted("milk", "500", "ml", UnitSystem::UsCustomary),
+            pair("2", "cup")
+        );
+        assert_eq!(
+            converted("beef", "1", "kg", UnitSystem::UsCustomary),
+            pair("2.2", "lb")
+        );
+        assert_eq!(
+            converted("cheese", "100", "g", UnitSystem::UsCustomary),
+            pair("3.53", "oz")
+        );
+        // measured by volume in US recipes
+        assert_eq!(
+            converted("flour", "250", "g", UnitSystem::UsCustomary),
+            pair("2", "cup")
+        );
+        assert_eq!(
+            converted("water", "10", "ml", UnitSystem::UsCustomary),
+            pair("2", "tsp")
+        );
+    }
+
+    #[test]
+    fn keeps_units_of_the_system_and_counts() {
+        assert_eq!(
+            converted("milk", "2", "tbsp", UnitSystem::Metric),
+            pair("2", "tbsp")
+        );
+        assert_eq!(
+            converted("milk", "300", "ml", UnitSystem::Metric),
+            pair("300", "ml")
+        );
+        assert_eq!(
+            converted("eggs", "3", "pc", UnitSystem::UsCustomary),
+            pair("3", "pc")
+        );
+        assert_eq!(
+            converted("herbs", "1", "handful", UnitSystem::Metric),
+            pair("1", "handful")
+        );
+    }
+
+    #[test]
+    fn keeps_small_converted_quantities_above_zero() {
+        assert_eq!(
+            converted("saffron", "0.001", "oz", UnitSystem::Metric),
+            pair("0.1", "g")
+        );
+        assert_eq!(
+            converted("vanilla", "0.1", "ml", UnitSystem::UsCustomary),
+            pair("0.25", "tsp")
+        );
+    }
+}
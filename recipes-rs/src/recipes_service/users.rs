@@ -0,0 +1,389 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection, RunQueryDsl};
+use log::info;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, OnceLock};
+
+use super::errors::ServiceError;
+use super::models::user::{NewSession, NewUser, Role, Session, User, UserRole};
+use super::schema::{sessions, user_roles, users};
+use super::utils::get_connection;
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_USERNAME_LENGTH: usize = 64;
+const SESSION_TTL_DAYS: i64 = 30;
+/// Arbitrary application-wide key of the advisory lock taken while revoking roles
+const ADMIN_ROLES_LOCK_KEY: i64 = 0x7265_6369_7065_7301;
+
+/// Hash a password with Argon2. CPU heavy, run it off the async executor.
+pub fn hash_password(password: &str) -> Result<String, ServiceError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(ServiceError::InvalidValue {
+            field: "password",
+            detail: format!("must be at least {MIN_PASSWORD_LENGTH} characters long"),
+        });
+    }
+
+    let salt = SaltString::generate(&mut OsRng);
+    return Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|_| ServiceError::InvalidValue {
+            field: "password",
+            detail: "cannot be hashed".to_string(),
+        });
+}
+
+/// Hash of a random password, verified against when the user does not exist so a login takes as
+/// long for unknown usernames as for wrong passwords.
+pub fn dummy_password_hash() -> &'static str {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    return DUMMY_HASH.get_or_init(|| {
+        let salt = SaltString::generate(&mut OsRng);
+        return Argon2::default()
+            .hash_password(new_token().as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            // a generated salt and default parameters leave nothing to fail on
+            .expect("Argon2 hashing with a generated salt must succeed");
+    });
+}
+
+/// Check a password against a stored Argon2 hash. CPU heavy, run it off the async executor.
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    let Ok(parsed_hash) = PasswordHash::new(password_hash) else {
+        return false;
+    };
+    return Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_ok();
+}
+
+/// Create a viewer. The first admin comes from the configuration, see [`ensure_admin`].
+pub async fn create_user(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    username: &str,
+    password_hash: &str,
+) -> Result<(User, Vec<Role>), ServiceError> {
+    let username = username.trim();
+    info!(username; "Creating user");
+    if username.is_empty() || username.chars().count() > MAX_USERNAME_LENGTH {
+        return Err(ServiceError::InvalidValue {
+            field: "username",
+            detail: format!("must be between 1 and {MAX_USERNAME_LENGTH} characters long"),
+        });
+    }
+
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let user = diesel::insert_into(users::table)
+                    .values(NewUser {
+                        username,
+                        password_hash,
+                    })
+                    .returning(User::as_returning())
+                    .get_result(connection)
+                    .await?;
+
+                let roles = vec![Role::Viewer];
+                let new_roles: Vec<UserRole> = roles
+                    .iter()
+                    .map(|role| UserRole {
+                        user_id: user.id,
+                        role: *role,
+                    })
+                    .collect();
+                diesel::insert_into(user_roles::table)
+                    .values(&new_roles)
+                    .execute(connection)
+                    .await?;
+
+                return Ok((user, roles));
+            })
+        })
+        .await;
+}
+
+/// Create the configured admin unless the username exists and make sure it holds the admin role.
+/// An existing account is only promoted when its password is the configured one, otherwise
+/// whoever registered the username first would become admin.
+pub async fn ensure_admin(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    username: &str,
+    password: &str,
+    password_hash: &str,
+) -> Result<(User, Vec<Role>), ServiceError> {
+    info!(username; "Ensuring admin");
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                let inserted = diesel::insert_into(users::table)
+                    .values(NewUser {
+                        username,
+                        password_hash,
+                    })
+                    .on_conflict(users::username)
+                    .do_nothing()
+                    .execute(connection)
+                    .await?;
+                let user = find_user(connection, username).await?;
+                if inserted == 0 && !verify_password(password, &user.password_hash) {
+                    return Err(ServiceError::Conflict {
+                        code: "admin_password_mismatch",
+                        detail: format!(
+                            "User {username} already exists with a password other than \
+                             ADMIN_PASSWORD, refusing to make it admin"
+                        ),
+                    });
+                }
+
+                let new_roles: Vec<UserRole> = [Role::Admin, Role::Viewer]
+                    .iter()
+                    .map(|role| UserRole {
+                        user_id: user.id,
+                        role: *role,
+                    })
+                    .collect();
+                diesel::insert_into(user_roles::table)
+                    .values(&new_roles)
+                    .on_conflict_do_nothing()
+                    .execute(connection)
+                    .await?;
+
+                let roles = load_roles(connection, &user.id).await?;
+                return Ok((user, roles));
+            })
+        })
+        .await;
+}
+
+pub async fn get_user_roles(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+) -> Result<Vec<Role>, ServiceError> {
+    let mut connection = get_connection(db_pool).await?;
+    return Ok(load_roles(&mut connection, user_id).await?);
+}
+
+pub async fn grant_role(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    username: &str,
+    role: &Role,
+) -> Result<(User, Vec<Role>), ServiceError> {
+    info!(username, role:serde; "Granting role");
+    let mut connection = get_connection(db_pool).await?;
+    let user = find_user(&mut connection, username).await?;
+
+    diesel::insert_into(user_roles::table)
+        .values(UserRole {
+            user_id: user.id,
+            role: *role,
+        })
+        .on_conflict_do_nothing()
+        .execute(&mut connection)
+        .await?;
+
+    let roles = load_roles(&mut connection, &user.id).await?;
+    return Ok((user, roles));
+}
+
+pub async fn revoke_role(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    username: &str,
+    role: &Role,
+) -> Result<(User, Vec<Role>), ServiceError> {
+    info!(username, role:serde; "Revoking role");
+    let mut connection = get_connection(db_pool).await?;
+    return connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                lock_admin_roles(connection).await?;
+                let user = find_user(connection, username).await?;
+                diesel::delete(user_roles::table.find((user.id, role)))
+                    .execute(connection)
+                    .await?;
+
+                // nobody could grant roles anymore
+                let admins: i64 = user_roles::table
+                    .filter(user_roles::role.eq(Role::Admin))
+                    .count()
+                    .get_result(connection)
+                    .await?;
+                if admins == 0 {
+                    return Err(ServiceError::InvalidValue {
+                        field: "role",
+                        detail: "The last admin cannot lose the admin role".to_string(),
+                    });
+                }
+
+                let roles = load_roles(connection, &user.id).await?;
+                return Ok((user, roles));
+            })
+        })
+        .await;
+}
+
+/// Serialize concurrent revocations until the end of the transaction. Without it two admins
+/// revoking each other both see the other one still holding the role.
+async fn lock_admin_roles(connection: &mut AsyncPgConnection) -> Result<(), ServiceError> {
+    diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+        .bind::<BigInt, _>(ADMIN_ROLES_LOCK_KEY)
+        .execute(connection)
+        .await?;
+    return Ok(());
+}
+
+/// `None` for an unknown username
+pub async fn get_user_by_username(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    username: &str,
+) -> Result<Option<User>, ServiceError> {
+    info!(username; "Getting user");
+    let mut connection = get_connection(db_pool).await?;
+    return Ok(users::table
+        .select(User::as_select())
+        .filter(users::username.eq(username.trim()))
+        .first(&mut connection)
+        .await
+        .optional()?);
+}
+
+/// Start a session for the user, returns the bearer token. Only its hash is stored.
+pub async fn create_session(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    user_id: &i32,
+) -> Result<(String, Session), ServiceError> {
+    info!(user_id; "Creating session");
+    let token = new_token();
+    let token_hash = hash_token(&token);
+
+    let mut connection = get_connection(db_pool).await?;
+    let session = connection
+        .build_transaction()
+        .run(|connection| {
+            Box::pin(async move {
+                // forget the user's expired sessions while we are at it
+                diesel::delete(
+                    sessions::table
+                        .filter(sessions::user_id.eq(user_id))
+                        .filter(sessions::expires_at.le(Utc::now())),
+                )
+                .execute(connection)
+                .await?;
+
+                return diesel::insert_into(sessions::table)
+                    .values(NewSession {
+                        token_hash: &token_hash,
+                        user_id: *user_id,
+                        expires_at: Utc::now() + Duration::days(SESSION_TTL_DAYS),
+                    })
+                    .returning(Session::as_returning())
+                    .get_result(connection)
+                    .await;
+            })
+        })
+        .await?;
+
+    return Ok((token, session));
+}
+
+/// Find the user owning a valid session token and their roles
+pub async fn authenticate(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    token: &str,
+) -> Result<(User, Vec<Role>), ServiceError> {
+    let mut connection = get_connection(db_pool).await?;
+    let user = users::table
+        .inner_join(sessions::table)
+        .filter(sessions::token_hash.eq(hash_token(token)))
+        .filter(sessions::expires_at.gt(Utc::now()))
+        .select(User::as_select())
+        .first(&mut connection)
+        .await
+        .optional()?
+        .ok_or(ServiceError::Unauthorized {
+            detail: "Invalid or expired token",
+        })?;
+
+    let roles = load_roles(&mut connection, &user.id).await?;
+    return Ok((user, roles));
+}
+
+pub async fn delete_session(
+    db_pool: Arc<Pool<AsyncPgConnection>>,
+    token: &str,
+) -> Result<(), ServiceError> {
+    info!("Deleting session");
+    let mut connection = get_connection(db_pool).await?;
+    diesel::delete(sessions::table.find(hash_token(token)))
+        .execute(&mut connection)
+        .await?;
+
+    return Ok(());
+}
+
+async fn find_user(
+    connection: &mut AsyncPgConnection,
+    username: &str,
+) -> Result<User, diesel::result::Error> {
+    return users::table
+        .filter(users::username.eq(username))
+        .select(User::as_select())
+        .first(connection)
+        .await;
+}
+
+pub async fn load_roles(
+    connection: &mut AsyncPgConnection,
+    user_id: &i32,
+) -> Result<Vec<Role>, diesel::result::Error> {
+    return user_roles::table
+        .filter(user_roles::user_id.eq(user_id))
+        .select(user_roles::role)
+        .order_by(user_roles::role)
+        .load(connection)
+        .await;
+}
+
+/// random secret for bearer authentication
+pub fn new_token() -> String {
+    let mut token_bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut token_bytes);
+    return URL_SAFE_NO_PAD.encode(token_bytes);
+}
+
+/// tokens are random enough for a fast hash, unlike passwords
+pub fn hash_token(token: &str) -> String {
+    return format!("{:x}", Sha256::digest(token.as_bytes()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dummy_hash_is_a_valid_argon2_hash() {
+        // an unparsable hash would make logins of unknown usernames return early
+        assert!(PasswordHash::new(dummy_password_hash()).is_ok());
+        assert!(!verify_password("password1", dummy_password_hash()));
+    }
+
+    #[test]
+    fn verifies_hashed_passwords() {
+        let password_hash = hash_password("password1").unwrap();
+        assert!(verify_password("password1", &password_hash));
+        assert!(!verify_password("password2", &password_hash));
+        assert!(!verify_password("password1", ""));
+    }
+}
@@ -1,11 +1,49 @@
+use chrono::{Duration, NaiveDate};
 use diesel_async::{
     pooled_connection::deadpool::{Object, Pool, PoolError},
     AsyncPgConnection,
 };
 use std::sync::Arc;
 
+use super::errors::ServiceError;
+
 pub async fn get_connection(
     pool: Arc<Pool<AsyncPgConnection>>,
 ) -> Result<Object<AsyncPgConnection>, PoolError> {
     return pool.get().await;
 }
+
+/// text matched literally by `LIKE`, its wildcards `%` and `_` are escaped
+pub fn escape_like(text: &str) -> String {
+    return text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+}
+
+/// `date` moved by `days`, an error for `field` when that leaves the range chrono supports
+pub fn shift_date(
+    date: &NaiveDate,
+    days: i64,
+    field: &'static str,
+) -> Result<NaiveDate, ServiceError> {
+    return date
+        .checked_add_signed(Duration::days(days))
+        .ok_or_else(|| ServiceError::InvalidValue {
+            field,
+            detail: "is too far in the past or future".to_string(),
+        });
+}
+
+/// requested page of a listing, `page` is numbered from 1
+#[derive(Debug, Clone, Copy)]
+pub struct Page {
+    pub page: i64,
+    pub limit: i64,
+}
+
+impl Page {
+    pub fn offset(&self) -> i64 {
+        return (self.page - 1).saturating_mul(self.limit);
+    }
+}
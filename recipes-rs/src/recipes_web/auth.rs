@@ -0,0 +1,167 @@
+use actix_web::{dev::Payload, http::header::AUTHORIZATION, web, FromRequest, HttpRequest};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use std::{future::Future, pin::Pin};
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::recipes_service::{
+    api_keys::{authenticate_api_key, API_KEY_PREFIX},
+    models::{
+        api_key::Scope,
+        user::{Role, User},
+    },
+    users::authenticate,
+};
+
+use super::errors::ApiErrors;
+
+/// Extractor for handlers that require a signed-in user, reads the `Authorization: Bearer`
+/// session token or API key
+pub struct AuthenticatedUser {
+    pub user: User,
+    pub roles: Vec<Role>,
+    /// `None` for sessions, which are not limited by scopes
+    pub scopes: Option<Vec<Scope>>,
+    pub token: String,
+}
+
+impl AuthenticatedUser {
+    /// Guard for handlers restricted to some roles, call it before doing anything else
+    pub fn require_role(&self, allowed_roles: &[Role]) -> Result<(), ApiErrors> {
+        if self.roles.iter().any(|role| allowed_roles.contains(role)) {
+            return Ok(());
+        }
+        return Err(ApiErrors::Forbidden {
+            code: "missing_role",
+            detail: format!(
+                "One of the roles {} is required",
+                allowed_roles
+                    .iter()
+                    .map(|role| format!("{role:?}").to_lowercase())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        });
+    }
+
+    /// Guard for handlers machine clients may call only with the scope granted to their key
+    pub fn require_scope(&self, scope: Scope) -> Result<(), ApiErrors> {
+        return match &self.scopes {
+            Some(scopes) if !scopes.contains(&scope) => Err(ApiErrors::Forbidden {
+                code: "missing_scope",
+                detail: format!("The API key needs the '{}' scope", scope.as_str()),
+            }),
+            _ => Ok(()),
+        };
+    }
+}
+
+/// Extractor for handlers open to anonymous callers whose result depends on the caller.
+/// A missing token gives `None`, an invalid one is still rejected.
+pub struct OptionalUser(pub Option<AuthenticatedUser>);
+
+impl OptionalUser {
+    pub fn user_id(&self) -> Option<i32> {
+        return self.0.as_ref().map(|auth| auth.user.id);
+    }
+
+    pub fn require_scope(&self, scope: Scope) -> Result<(), ApiErrors> {
+        return match &self.0 {
+            Some(auth) => auth.require_scope(scope),
+            None => Ok(()),
+        };
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = ApiErrors;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let pool = req
+            .app_data::<web::Data<Pool<AsyncPgConnection>>>()
+            .cloned();
+        let token = bearer_token(req);
+
+        return Box::pin(async move {
+            let Some(token) = token else {
+                return Err(ApiErrors::Unauthorized {
+                    code: "missing_token",
+                    detail: "Authorization bearer token is required".to_string(),
+                });
+            };
+            return authenticate_token(pool, token).await;
+        });
+    }
+}
+
+impl FromRequest for OptionalUser {
+    type Error = ApiErrors;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let pool = req
+            .app_data::<web::Data<Pool<AsyncPgConnection>>>()
+            .cloned();
+        let token = bearer_token(req);
+
+        return Box::pin(async move {
+            return match token {
+                Some(token) => Ok(OptionalUser(Some(authenticate_token(pool, token).await?))),
+                None => Ok(OptionalUser(None)),
+            };
+        });
+    }
+}
+
+async fn authenticate_token(
+    pool: Option<web::Data<Pool<AsyncPgConnection>>>,
+    token: String,
+) -> Result<AuthenticatedUser, ApiErrors> {
+    let pool = pool.ok_or(ApiErrors::InternalError)?;
+    if token.starts_with(API_KEY_PREFIX) {
+        let (user, roles, scopes) = authenticate_api_key(pool.into_inner(), &token).await?;
+        return Ok(AuthenticatedUser {
+            user,
+            roles,
+            scopes: Some(scopes),
+            token,
+        });
+    }
+
+    let (user, roles) = authenticate(pool.into_inner(), &token).await?;
+    return Ok(AuthenticatedUser {
+        user,
+        roles,
+        scopes: None,
+        token,
+    });
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<String> {
+    let header = req.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = header.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") || token.trim().is_empty() {
+        return None;
+    }
+    return Some(token.trim().to_string());
+}
+
+/// OpenAPI document extras not generated from the handlers
+#[derive(OpenApi)]
+#[openapi(modifiers(&SecurityAddon))]
+pub struct ApiDoc;
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use actix_web::{
+    delete, get,
+    http::{header::ContentType, StatusCode},
+    post, web, HttpResponse, Responder,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use utoipa_actix_web::service_config;
+
+use crate::{
+    recipes_service::api_keys::{create_api_key, list_api_keys, revoke_api_key},
+    recipes_service::models::api_key::Scope,
+    recipes_web::{auth::AuthenticatedUser, errors, utils},
+};
+
+use super::{
+    requests::api_keys::NewApiKey,
+    responses::json::{ApiKeyResponse, NewApiKeyResponse},
+};
+
+#[utoipa::path(
+    tag = "api_keys",
+    responses(
+        (status = 200, description = "List API keys of the signed-in user", body = utils::ResponseBodyVec<Vec<ApiKeyResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("")]
+pub async fn api_keys_list(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::Admin)?;
+
+    let api_keys: Vec<ApiKeyResponse> = list_api_keys(pool.into_inner(), &auth.user.id)
+        .await?
+        .into_iter()
+        .map(|api_key| api_key.into())
+        .collect();
+
+    let response_body = utils::ResponseBodyVec {
+        result: api_keys,
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "api_keys",
+    responses(
+        (status = 201, description = "Create API key, the key is not shown again", body = NewApiKeyResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("")]
+pub async fn api_keys_create(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    api_key_body: web::Json<NewApiKey>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::Admin)?;
+    let api_key_body = api_key_body.into_inner();
+
+    let (key, api_key) = create_api_key(
+        pool.into_inner(),
+        &auth.user.id,
+        &api_key_body.name,
+        &api_key_body.scopes,
+        &api_key_body.expires_at,
+    )
+    .await?;
+    let response = NewApiKeyResponse {
+        key,
+        api_key: api_key.into(),
+    };
+    let response_serialized = serde_json::to_string(&response)?;
+
+    return Ok(HttpResponse::Created()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "api_keys",
+    responses(
+        (status = 204, description = "Revoke API key")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}")]
+pub async fn api_keys_revoke(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::Admin)?;
+    let api_key_id = path.into_inner();
+
+    revoke_api_key(pool.into_inner(), &auth.user.id, &api_key_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+pub fn api_keys_config(cfg: &mut service_config::ServiceConfig) {
+    cfg.service(api_keys_list);
+    cfg.service(api_keys_create);
+    cfg.service(api_keys_revoke);
+}
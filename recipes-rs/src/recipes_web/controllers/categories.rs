@@ -1,7 +1,10 @@
 use actix_web::{
     delete, get,
-    http::{header::ContentType, StatusCode},
-    post, put, web, HttpResponse, Responder,
+    http::{
+        header::{ContentType, ETag},
+        StatusCode,
+    },
+    post, put, web, HttpRequest, HttpResponse, Responder,
 };
 use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
 use utoipa_actix_web::service_config;
@@ -11,7 +14,13 @@ use crate::{
         create_category, delete_category, get_category, list_categories, update_category,
     },
     recipes_service::models::category::{Category, ChangeCategory, NewCategory},
-    recipes_web::{errors, utils},
+    recipes_service::models::{api_key::Scope, user::Role},
+    recipes_service::utils::Page,
+    recipes_web::{
+        auth::{AuthenticatedUser, OptionalUser},
+        errors, utils,
+        validation::Validate,
+    },
 };
 
 use super::responses::json::CategoryResponse;
@@ -24,9 +33,14 @@ use super::responses::json::CategoryResponse;
 )]
 #[get("")]
 pub async fn categories_list(
+    req: HttpRequest,
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: OptionalUser,
+    page_params: web::Query<utils::PageQuery>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
-    let categories_db = list_categories(pool.into_inner()).await?;
+    auth.require_scope(Scope::ReadRecipes)?;
+    let page: Page = (&page_params.into_inner()).into();
+    let (categories_db, total) = list_categories(pool.into_inner(), &page).await?;
     let categories_vec: Vec<CategoryResponse> = categories_db
         .iter()
         .map(|category| CategoryResponse {
@@ -36,6 +50,7 @@ pub async fn categories_list(
 
     let response_body = utils::ResponseBodyVec {
         result: categories_vec,
+        pagination: Some(utils::Pagination::new(&req, &page, total)),
     };
     let response_serialized = serde_json::to_string(&response_body)?;
 
@@ -47,17 +62,25 @@ pub async fn categories_list(
 #[utoipa::path(
     tag = "categories",
     responses(
-        (status = 200, description = "Get category", body = CategoryResponse)
+        (status = 200, description = "Get category, `ETag` is the category version", body = CategoryResponse),
+        (status = 304, description = "Category matches `If-None-Match`")
     )
 )]
 #[get("/{name}")]
 pub async fn categories_get(
+    req: HttpRequest,
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: OptionalUser,
     path: web::Path<String>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
     let category_name = path.into_inner();
 
     let category: Category = get_category(pool.into_inner(), category_name).await?;
+    let etag = utils::version_etag(category.version);
+    if utils::is_not_modified(&req, &etag) {
+        return Ok(utils::not_modified(etag));
+    }
     let category = CategoryResponse {
         name: category.name,
     };
@@ -65,21 +88,29 @@ pub async fn categories_get(
 
     return Ok(HttpResponse::Ok()
         .content_type(ContentType::json())
+        .insert_header(ETag(etag))
         .body(response_serialized));
 }
 
 #[utoipa::path(
     tag = "categories",
     responses(
-        (status = 201, description = "Create category", body = CategoryResponse)
-    )
+        (status = 201, description = "Create category, admins and editors only", body = CategoryResponse),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
 )]
 #[post("")]
 pub async fn categories_create(
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
     category_body: web::Json<NewCategory>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
-    let category = create_category(pool.into_inner(), &category_body.into_inner()).await?;
+    auth.require_scope(Scope::WriteRecipes)?;
+    auth.require_role(&[Role::Admin, Role::Editor])?;
+    let category_body = category_body.into_inner();
+    category_body.validate()?;
+    let category = create_category(pool.into_inner(), &category_body).await?;
     let category = CategoryResponse {
         name: category.name,
     };
@@ -94,20 +125,34 @@ pub async fn categories_create(
 #[utoipa::path(
     tag = "categories",
     responses(
-        (status = 200, description = "Alter category", body = CategoryResponse)
-    )
+        (status = 200, description = "Alter category, admins and editors only", body = CategoryResponse),
+        (status = 412, description = "Category version does not match `If-Match`"),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
 )]
 #[put("/{name}")]
 pub async fn categories_change(
+    req: HttpRequest,
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
     path: web::Path<String>,
     category_changeset: web::Json<ChangeCategory>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    auth.require_role(&[Role::Admin, Role::Editor])?;
     let category_name = path.into_inner();
     let category_changeset = category_changeset.into_inner();
+    category_changeset.validate()?;
 
-    let category: Category =
-        update_category(pool.into_inner(), category_name, &category_changeset).await?;
+    let category: Category = update_category(
+        pool.into_inner(),
+        category_name,
+        &utils::if_match_versions(&req),
+        &category_changeset,
+    )
+    .await?;
+    let etag = utils::version_etag(category.version);
     let category = CategoryResponse {
         name: category.name,
     };
@@ -115,23 +160,35 @@ pub async fn categories_change(
 
     return Ok(HttpResponse::Ok()
         .content_type(ContentType::json())
+        .insert_header(ETag(etag))
         .body(response_serialized));
 }
 
 #[utoipa::path(
     tag = "categories",
     responses(
-        (status = 200, description = "Delete category")
-    )
+        (status = 200, description = "Delete category, admins and editors only"),
+        (status = 412, description = "Category version does not match `If-Match`")
+    ),
+    security(("bearer_auth" = []))
 )]
 #[delete("/{name}")]
 pub async fn categories_delete(
+    req: HttpRequest,
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
     path: web::Path<String>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    auth.require_role(&[Role::Admin, Role::Editor])?;
     let category_name = path.into_inner();
 
-    delete_category(pool.into_inner(), category_name).await?;
+    delete_category(
+        pool.into_inner(),
+        category_name,
+        &utils::if_match_versions(&req),
+    )
+    .await?;
 
     return Ok(HttpResponse::NoContent()
         .content_type(ContentType::json())
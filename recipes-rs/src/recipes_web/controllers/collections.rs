@@ -0,0 +1,342 @@
+use actix_web::{
+    delete, get,
+    http::{header::ContentType, StatusCode},
+    post, put, web, HttpResponse, Responder,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use utoipa_actix_web::service_config;
+
+use crate::{
+    recipes_service::collections::{
+        add_collection_recipe, create_collection, delete_collection, get_collection,
+        get_shared_collection, list_collections, order_collection_recipes,
+        remove_collection_recipe, rename_collection, share_collection, unshare_collection,
+    },
+    recipes_service::models::api_key::Scope,
+    recipes_web::{
+        auth::{AuthenticatedUser, OptionalUser},
+        errors, utils,
+    },
+};
+
+use super::{
+    requests::collections::{NewCollection, OrderCollection},
+    responses::json::CollectionResponse,
+};
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 200, description = "List own collections", body = utils::ResponseBodyVec<Vec<CollectionResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("")]
+pub async fn collections_list(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+
+    let collections: Vec<CollectionResponse> = list_collections(pool.into_inner(), &auth.user.id)
+        .await?
+        .into_iter()
+        .map(|collection| collection.into())
+        .collect();
+
+    let response_body = utils::ResponseBodyVec {
+        result: collections,
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 200, description = "Get collection by its public link token, lists only recipes visible to the caller", body = CollectionResponse)
+    )
+)]
+#[get("/shared/{token}")]
+pub async fn collections_get_shared(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: OptionalUser,
+    path: web::Path<String>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let share_token = path.into_inner();
+
+    let collection: CollectionResponse =
+        get_shared_collection(pool.into_inner(), &auth.user_id(), &share_token)
+            .await?
+            .into();
+    let response_serialized = serde_json::to_string(&collection)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 200, description = "Get own collection", body = CollectionResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/{id}")]
+pub async fn collections_get(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let collection_id = path.into_inner();
+
+    let collection: CollectionResponse =
+        get_collection(pool.into_inner(), &auth.user.id, &collection_id)
+            .await?
+            .into();
+    let response_serialized = serde_json::to_string(&collection)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 201, description = "Create collection", body = CollectionResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("")]
+pub async fn collections_create(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    collection_body: web::Json<NewCollection>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+
+    let collection: CollectionResponse =
+        create_collection(pool.into_inner(), &auth.user.id, &collection_body.name)
+            .await?
+            .into();
+    let response_serialized = serde_json::to_string(&collection)?;
+
+    return Ok(HttpResponse::Created()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 200, description = "Rename collection", body = CollectionResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}")]
+pub async fn collections_change(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    collection_body: web::Json<NewCollection>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let collection_id = path.into_inner();
+
+    let collection: CollectionResponse = rename_collection(
+        pool.into_inner(),
+        &auth.user.id,
+        &collection_id,
+        &collection_body.name,
+    )
+    .await?
+    .into();
+    let response_serialized = serde_json::to_string(&collection)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 204, description = "Delete collection")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}")]
+pub async fn collections_delete(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let collection_id = path.into_inner();
+
+    delete_collection(pool.into_inner(), &auth.user.id, &collection_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 200, description = "Append recipe to collection", body = CollectionResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}/recipes/{recipe_id}")]
+pub async fn collections_add_recipe(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<(i32, i32)>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let (collection_id, recipe_id) = path.into_inner();
+
+    let collection: CollectionResponse =
+        add_collection_recipe(pool.into_inner(), &auth.user.id, &collection_id, &recipe_id)
+            .await?
+            .into();
+    let response_serialized = serde_json::to_string(&collection)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 204, description = "Remove recipe from collection")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}/recipes/{recipe_id}")]
+pub async fn collections_remove_recipe(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<(i32, i32)>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let (collection_id, recipe_id) = path.into_inner();
+
+    remove_collection_recipe(pool.into_inner(), &auth.user.id, &collection_id, &recipe_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 200, description = "Reorder collection recipes", body = CollectionResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}/order")]
+pub async fn collections_order(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    order_body: web::Json<OrderCollection>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let collection_id = path.into_inner();
+
+    let collection: CollectionResponse = order_collection_recipes(
+        pool.into_inner(),
+        &auth.user.id,
+        &collection_id,
+        &order_body.recipe_ids,
+    )
+    .await?
+    .into();
+    let response_serialized = serde_json::to_string(&collection)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 200, description = "Create new public read-only link, replacing the previous one", body = CollectionResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}/share")]
+pub async fn collections_share(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let collection_id = path.into_inner();
+
+    let collection: CollectionResponse =
+        share_collection(pool.into_inner(), &auth.user.id, &collection_id)
+            .await?
+            .into();
+    let response_serialized = serde_json::to_string(&collection)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "collections",
+    responses(
+        (status = 200, description = "Disable public link", body = CollectionResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}/share")]
+pub async fn collections_unshare(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let collection_id = path.into_inner();
+
+    let collection: CollectionResponse =
+        unshare_collection(pool.into_inner(), &auth.user.id, &collection_id)
+            .await?
+            .into();
+    let response_serialized = serde_json::to_string(&collection)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+pub fn collections_config(cfg: &mut service_config::ServiceConfig) {
+    cfg.service(collections_list);
+    cfg.service(collections_get_shared);
+    cfg.service(collections_get);
+    cfg.service(collections_create);
+    cfg.service(collections_change);
+    cfg.service(collections_delete);
+    cfg.service(collections_add_recipe);
+    cfg.service(collections_remove_recipe);
+    cfg.service(collections_order);
+    cfg.service(collections_share);
+    cfg.service(collections_unshare);
+}
@@ -0,0 +1,170 @@
+use actix_web::{
+    delete, get,
+    http::{header::ContentType, StatusCode},
+    post, put, web, HttpResponse, Responder,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use utoipa_actix_web::service_config;
+
+use crate::{
+    recipes_service::ingredients::{
+        delete_ingredient, get_ingredient, list_ingredients, merge_ingredients, update_ingredient,
+    },
+    recipes_service::models::{api_key::Scope, ingredient::ChangeIngredient, user::Role},
+    recipes_web::{auth::AuthenticatedUser, errors, utils, validation::Validate},
+};
+
+use super::{
+    requests::ingredients::{ListIngredientsQuery, MergeIngredients},
+    responses::json::IngredientResponse,
+};
+
+#[utoipa::path(
+    tag = "ingredients",
+    responses(
+        (status = 200, description = "List ingredients", body = utils::ResponseBodyVec<Vec<IngredientResponse>>)
+    )
+)]
+#[get("")]
+pub async fn ingredients_list(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    query_params: web::Query<ListIngredientsQuery>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    let ingredients_db = list_ingredients(pool.into_inner(), &query_params.search).await?;
+    let ingredients_vec: Vec<IngredientResponse> =
+        ingredients_db.into_iter().map(|i| i.into()).collect();
+
+    let response_body = utils::ResponseBodyVec {
+        result: ingredients_vec,
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "ingredients",
+    responses(
+        (status = 200, description = "Get ingredient", body = IngredientResponse)
+    )
+)]
+#[get("/{id}")]
+pub async fn ingredients_get(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    let ingredient_id = path.into_inner();
+
+    let ingredient: IngredientResponse = get_ingredient(pool.into_inner(), &ingredient_id)
+        .await?
+        .into();
+    let response_serialized = serde_json::to_string(&ingredient)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "ingredients",
+    responses(
+        (status = 200, description = "Rename ingredient, admins and editors only", body = IngredientResponse),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}")]
+pub async fn ingredients_change(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    ingredient_changeset: web::Json<ChangeIngredient>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    // ingredients are shared by all recipes
+    auth.require_role(&[Role::Admin, Role::Editor])?;
+    let ingredient_id = path.into_inner();
+    let ingredient_changeset = ingredient_changeset.into_inner();
+    ingredient_changeset.validate()?;
+
+    let ingredient: IngredientResponse =
+        update_ingredient(pool.into_inner(), &ingredient_id, &ingredient_changeset)
+            .await?
+            .into();
+    let response_serialized = serde_json::to_string(&ingredient)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "ingredients",
+    responses(
+        (status = 200, description = "Merge duplicate ingredients into the ingredient, admins and editors only", body = IngredientResponse),
+        (status = 409, description = "Recipes use several of the ingredients in quantities that cannot be added")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/{id}/merge")]
+pub async fn ingredients_merge(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    merge_body: web::Json<MergeIngredients>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    // ingredients are shared by all recipes
+    auth.require_role(&[Role::Admin, Role::Editor])?;
+    let ingredient_id = path.into_inner();
+
+    let ingredient: IngredientResponse = merge_ingredients(
+        pool.into_inner(),
+        &ingredient_id,
+        &merge_body.ingredient_ids,
+    )
+    .await?
+    .into();
+    let response_serialized = serde_json::to_string(&ingredient)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "ingredients",
+    responses(
+        (status = 204, description = "Delete ingredient, admins and editors only")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}")]
+pub async fn ingredients_delete(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    // ingredients are shared by all recipes
+    auth.require_role(&[Role::Admin, Role::Editor])?;
+    let ingredient_id = path.into_inner();
+
+    delete_ingredient(pool.into_inner(), &ingredient_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+pub fn ingredients_config(cfg: &mut service_config::ServiceConfig) {
+    cfg.service(ingredients_list);
+    cfg.service(ingredients_get);
+    cfg.service(ingredients_change);
+    cfg.service(ingredients_merge);
+    cfg.service(ingredients_delete);
+}
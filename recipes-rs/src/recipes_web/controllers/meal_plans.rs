@@ -0,0 +1,205 @@
+use actix_web::{
+    delete, get,
+    http::{header::ContentType, StatusCode},
+    post, put, web, HttpResponse, Responder,
+};
+use chrono::{Datelike, Duration, Utc};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use utoipa_actix_web::service_config;
+
+use crate::{
+    recipes_service::meal_plans::{
+        add_meal_plan_entry, copy_meal_plan_week, delete_meal_plan_entry, list_meal_plan,
+        update_meal_plan_entry,
+    },
+    recipes_service::models::{api_key::Scope, meal_plan::ChangeMealPlanEntry},
+    recipes_service::utils::shift_date,
+    recipes_web::{auth::AuthenticatedUser, errors, utils, validation::Validate},
+};
+
+use super::{
+    requests::meal_plans::{CopyMealPlanWeek, ListMealPlanQuery, NewMealPlanEntry},
+    responses::json::MealPlanEntryResponse,
+};
+
+#[utoipa::path(
+    tag = "meal_plans",
+    responses(
+        (status = 200, description = "List meal plan entries by date and slot", body = utils::ResponseBodyVec<Vec<MealPlanEntryResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("")]
+pub async fn meal_plans_list(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    query_params: web::Query<ListMealPlanQuery>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let (from, to) = match (query_params.from, query_params.to) {
+        (Some(from), Some(to)) => (from, to),
+        (Some(from), None) => (from, shift_date(&from, 6, "from")?),
+        (None, Some(to)) => (shift_date(&to, -6, "to")?, to),
+        (None, None) => {
+            let today = Utc::now().date_naive();
+            let monday = today - Duration::days(today.weekday().num_days_from_monday().into());
+            (monday, monday + Duration::days(6))
+        }
+    };
+
+    let entries: Vec<MealPlanEntryResponse> =
+        list_meal_plan(pool.into_inner(), &auth.user.id, &from, &to)
+            .await?
+            .into_iter()
+            .map(|entry| entry.into())
+            .collect();
+
+    let response_body = utils::ResponseBodyVec {
+        result: entries,
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "meal_plans",
+    responses(
+        (status = 201, description = "Plan recipe for a meal", body = MealPlanEntryResponse),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("")]
+pub async fn meal_plans_create(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    entry_body: web::Json<NewMealPlanEntry>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    entry_body.validate()?;
+
+    let entry: MealPlanEntryResponse = add_meal_plan_entry(
+        pool.into_inner(),
+        &auth.user.id,
+        &entry_body.date,
+        &entry_body.slot,
+        &entry_body.recipe_id,
+        &entry_body.portions,
+    )
+    .await?
+    .into();
+    let response_serialized = serde_json::to_string(&entry)?;
+
+    return Ok(HttpResponse::Created()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "meal_plans",
+    responses(
+        (status = 200, description = "Alter meal plan entry", body = MealPlanEntryResponse),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}")]
+pub async fn meal_plans_change(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    entry_changeset: web::Json<ChangeMealPlanEntry>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let entry_id = path.into_inner();
+    let entry_changeset = entry_changeset.into_inner();
+    entry_changeset.validate()?;
+
+    let entry: MealPlanEntryResponse = update_meal_plan_entry(
+        pool.into_inner(),
+        &auth.user.id,
+        &entry_id,
+        &entry_changeset,
+    )
+    .await?
+    .into();
+    let response_serialized = serde_json::to_string(&entry)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "meal_plans",
+    responses(
+        (status = 204, description = "Delete meal plan entry")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}")]
+pub async fn meal_plans_delete(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let entry_id = path.into_inner();
+
+    delete_meal_plan_entry(pool.into_inner(), &auth.user.id, &entry_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+#[utoipa::path(
+    tag = "meal_plans",
+    responses(
+        (status = 200, description = "Copy a week of the plan, returns the target week", body = utils::ResponseBodyVec<Vec<MealPlanEntryResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/copy-week")]
+pub async fn meal_plans_copy_week(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    copy_body: web::Json<CopyMealPlanWeek>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+
+    let entries: Vec<MealPlanEntryResponse> = copy_meal_plan_week(
+        pool.into_inner(),
+        &auth.user.id,
+        &copy_body.from,
+        &copy_body.to,
+        &copy_body.replace.unwrap_or(false),
+    )
+    .await?
+    .into_iter()
+    .map(|entry| entry.into())
+    .collect();
+
+    let response_body = utils::ResponseBodyVec {
+        result: entries,
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+pub fn meal_plans_config(cfg: &mut service_config::ServiceConfig) {
+    cfg.service(meal_plans_list);
+    cfg.service(meal_plans_create);
+    cfg.service(meal_plans_copy_week);
+    cfg.service(meal_plans_change);
+    cfg.service(meal_plans_delete);
+}
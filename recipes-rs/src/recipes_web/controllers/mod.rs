@@ -1,4 +1,12 @@
+pub mod api_keys;
 pub mod categories;
+pub mod collections;
+pub mod ingredients;
+pub mod meal_plans;
+pub mod pantry;
 pub mod recipes;
 pub mod requests;
 pub mod responses;
+pub mod shopping_lists;
+pub mod units;
+pub mod users;
@@ -0,0 +1,176 @@
+use actix_web::{
+    delete, get,
+    http::{header::ContentType, StatusCode},
+    post, put, web, HttpResponse, Responder,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use utoipa_actix_web::service_config;
+
+use crate::{
+    recipes_service::models::{api_key::Scope, pantry_item::NewPantryIngredient},
+    recipes_service::pantry::{
+        add_pantry_item, delete_pantry_item, get_pantry_item, list_pantry_items, update_pantry_item,
+    },
+    recipes_web::{auth::AuthenticatedUser, errors, utils},
+};
+
+use super::{requests::pantry::NewPantryItem, responses::json::PantryItemResponse};
+
+#[utoipa::path(
+    tag = "pantry",
+    responses(
+        (status = 200, description = "List pantry items, soonest expiring first", body = utils::ResponseBodyVec<Vec<PantryItemResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("")]
+pub async fn pantry_list(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+
+    let items: Vec<PantryItemResponse> = list_pantry_items(pool.into_inner(), &auth.user.id)
+        .await?
+        .into_iter()
+        .map(|item| item.into())
+        .collect();
+
+    let response_body = utils::ResponseBodyVec {
+        result: items,
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "pantry",
+    responses(
+        (status = 200, description = "Get pantry item", body = PantryItemResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/{id}")]
+pub async fn pantry_get(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let item_id = path.into_inner();
+
+    let item: PantryItemResponse = get_pantry_item(pool.into_inner(), &auth.user.id, &item_id)
+        .await?
+        .into();
+    let response_serialized = serde_json::to_string(&item)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "pantry",
+    responses(
+        (status = 201, description = "Add pantry item", body = PantryItemResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("")]
+pub async fn pantry_create(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    item_body: web::Json<NewPantryItem>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let pantry_ingredient = NewPantryIngredient {
+        name: &item_body.name,
+        quantity: &item_body.quantity,
+        unit: &item_body.unit,
+        expires_on: item_body.expires_on,
+    };
+
+    let item: PantryItemResponse =
+        add_pantry_item(pool.into_inner(), &auth.user.id, &pantry_ingredient)
+            .await?
+            .into();
+    let response_serialized = serde_json::to_string(&item)?;
+
+    return Ok(HttpResponse::Created()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "pantry",
+    responses(
+        (status = 200, description = "Replace pantry item", body = PantryItemResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}")]
+pub async fn pantry_change(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    item_body: web::Json<NewPantryItem>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let item_id = path.into_inner();
+    let pantry_ingredient = NewPantryIngredient {
+        name: &item_body.name,
+        quantity: &item_body.quantity,
+        unit: &item_body.unit,
+        expires_on: item_body.expires_on,
+    };
+
+    let item: PantryItemResponse = update_pantry_item(
+        pool.into_inner(),
+        &auth.user.id,
+        &item_id,
+        &pantry_ingredient,
+    )
+    .await?
+    .into();
+    let response_serialized = serde_json::to_string(&item)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "pantry",
+    responses(
+        (status = 204, description = "Delete pantry item")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}")]
+pub async fn pantry_delete(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let item_id = path.into_inner();
+
+    delete_pantry_item(pool.into_inner(), &auth.user.id, &item_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+pub fn pantry_config(cfg: &mut service_config::ServiceConfig) {
+    cfg.service(pantry_list);
+    cfg.service(pantry_get);
+    cfg.service(pantry_create);
+    cfg.service(pantry_change);
+    cfg.service(pantry_delete);
+}
@@ -1,27 +1,59 @@
 use actix_multipart::form::MultipartForm;
 use actix_web::{
     delete, get,
-    http::{header::ContentType, StatusCode},
-    post, put, web, HttpResponse, Responder,
+    http::{
+        header::{self, ContentType, ETag},
+        StatusCode,
+    },
+    patch, post, put, web, HttpMessage, HttpRequest, HttpResponse, Responder,
 };
+use bigdecimal::ToPrimitive;
 use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
 use utoipa_actix_web::service_config;
 
+use crate::recipes_service::favourites::{add_favourite, remove_favourite};
+use crate::recipes_service::import::{import_recipe, ImportSource};
 use crate::recipes_service::models::{
+    api_key::Scope,
     ingredient::NewRecipeIngredient,
-    recipe::{ChangeRecipe as ChangeRecipeUpdate, NewRecipe as NewRecipeInsert, Recipe},
+    recipe::{ChangeRecipe as ChangeRecipeUpdate, NewRecipe as NewRecipeInsert},
 };
+use crate::recipes_service::pantry::list_cookable_recipes;
 use crate::recipes_service::recipes::{
-    change_recipe_image, create_recipe, delete_recipe, get_recipe, get_recipe_image, list_recipes,
-    update_recipe,
+    change_recipe_image, create_recipe, delete_recipe, get_recipe, get_recipe_image,
+    get_scaled_recipe, has_recipe_image, list_recipes, update_recipe, RecipeFilter, RecipeSort,
+    SortOrder,
+};
+use crate::recipes_service::reviews::{create_review, delete_review, list_reviews, update_review};
+use crate::recipes_service::revisions::{
+    diff_revisions, get_revision, list_revisions, restore_revision,
+};
+use crate::recipes_service::shares::{list_recipe_shares, share_recipe, unshare_recipe};
+use crate::recipes_service::steps::{instructions_and_steps, required_instructions_and_steps};
+use crate::recipes_service::units::convert_parts;
+use crate::recipes_service::utils::Page;
+use crate::recipes_web::{
+    auth::{AuthenticatedUser, OptionalUser},
+    errors, utils,
+    validation::Validate,
 };
-use crate::recipes_web::{errors, utils};
 
 use super::{
-    requests::recipes::{ChangeRecipe, ChangeRecipeImage, ListRecipesQuery, NewRecipe},
-    responses::json::RecipeResponse,
+    requests::recipes::{
+        ChangeRecipeImage, CookableRecipesQuery, DiffRevisionsQuery, GetRecipeQuery,
+        ListRecipesQuery, NewRecipe, NewReview, RecipePatch, ShareRecipe,
+    },
+    responses::json::{
+        CookableRecipeResponse, ImportedRecipeResponse, RecipeDetailResponse, RecipeResponse,
+        RecipeRevisionDetailResponse, RecipeRevisionResponse, RecipeShareResponse, ReviewResponse,
+        RevisionChangeResponse,
+    },
+    responses::json_ld::RecipeJsonLd,
 };
 
+const MERGE_PATCH_JSON: &str = "application/merge-patch+json";
+const LD_JSON: &str = "application/ld+json";
+
 #[utoipa::path(
     tag = "recipes",
     responses(
@@ -30,22 +62,48 @@ use super::{
 )]
 #[get("")]
 pub async fn recipes_list(
+    req: HttpRequest,
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: OptionalUser,
     query_params: web::Query<ListRecipesQuery>,
+    page_params: web::Query<utils::PageQuery>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
-    // TODO: logging
-    let recipes_categories = list_recipes(
+    auth.require_scope(Scope::ReadRecipes)?;
+    let query_params = query_params.into_inner();
+    query_params.validate()?;
+    let sort = query_params.sort.unwrap_or(match query_params.q {
+        Some(_) => RecipeSort::Relevance,
+        None => RecipeSort::Id,
+    });
+    let filter = RecipeFilter {
+        q: query_params.q,
+        category: query_params.category,
+        cuisine: query_params.cuisine,
+        min_duration: query_params.min_duration,
+        max_duration: query_params.max_duration,
+        min_rating: query_params.min_rating,
+        favourites: query_params.favourites.unwrap_or_default(),
+        collection: query_params.collection,
+    };
+    let page: Page = (&page_params.into_inner()).into();
+
+    let (recipes_categories, total) = list_recipes(
         pool.into_inner(),
-        &query_params.category,
-        &query_params.cuisine,
-        &query_params.min_duration,
-        &query_params.max_duration,
+        &auth.user_id(),
+        &filter,
+        &sort,
+        // best matches first unless asked otherwise
+        &query_params.order.unwrap_or(match sort {
+            RecipeSort::Relevance => SortOrder::Desc,
+            _ => SortOrder::Asc,
+        }),
+        &page,
     )
     .await?;
 
     let recipes_full: Vec<RecipeResponse> = recipes_categories
         .into_iter()
-        .map(|(recipe, categories)| RecipeResponse {
+        .map(|(recipe, categories, snippet)| RecipeResponse {
             id: recipe.id,
             name: recipe.name,
             instructions: recipe.instructions,
@@ -54,12 +112,18 @@ pub async fn recipes_list(
             preparation_needed: recipe.preparation_needed,
             portions: recipe.portions,
             difficulty: recipe.difficulty,
+            owner_id: recipe.owner_id,
+            visibility: recipe.visibility,
+            rating_avg: recipe.rating_avg.and_then(|rating| rating.to_f64()),
+            rating_count: recipe.rating_count,
             categories: categories.into_iter().map(|c| c.into()).collect(),
+            snippet,
         })
         .collect();
 
     let response_body = utils::ResponseBodyVec {
         result: recipes_full,
+        pagination: Some(utils::Pagination::new(&req, &page, total)),
     };
     let response_serialized = serde_json::to_string(&response_body)?;
 
@@ -68,81 +132,182 @@ pub async fn recipes_list(
         .body(response_serialized));
 }
 
-// TODO: add ingredients to response but list should not have categories and ingredients
 #[utoipa::path(
     tag = "recipes",
     responses(
-        (status = 200, description = "Get recipe", body = RecipeResponse)
+        (status = 200, description = "List recipes cookable from the pantry, best match first. Only the 500 recipes with most of their ingredients in the pantry are ranked, `pagination.total` counts at most those.", body = utils::ResponseBodyVec<Vec<CookableRecipeResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/cookable")]
+pub async fn recipes_list_cookable(
+    req: HttpRequest,
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    query_params: web::Query<CookableRecipesQuery>,
+    page_params: web::Query<utils::PageQuery>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let query_params = query_params.into_inner();
+    query_params.validate()?;
+    let filter = RecipeFilter {
+        q: query_params.q,
+        category: query_params.category,
+        cuisine: query_params.cuisine,
+        min_duration: query_params.min_duration,
+        max_duration: query_params.max_duration,
+        min_rating: query_params.min_rating,
+        ..Default::default()
+    };
+    let page: Page = (&page_params.into_inner()).into();
+
+    let (cookable, total) =
+        list_cookable_recipes(pool.into_inner(), &auth.user.id, &filter, &page).await?;
+
+    let response_body = utils::ResponseBodyVec {
+        result: cookable
+            .into_iter()
+            .map(|cookable| cookable.into())
+            .collect::<Vec<CookableRecipeResponse>>(),
+        pagination: Some(utils::Pagination::new(&req, &page, total)),
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+// TODO: list should not have categories
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 200, description = "Get recipe, `ETag` is the recipe version. A schema.org Recipe when `Accept` prefers JSON-LD",
+            content((RecipeDetailResponse = "application/json"), (RecipeJsonLd = "application/ld+json"))),
+        (status = 304, description = "Recipe matches `If-None-Match`"),
+        (status = 406, description = "`Accept` refuses JSON and JSON-LD")
     )
 )]
 #[get("/{id}")]
 pub async fn recipes_get(
+    req: HttpRequest,
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: OptionalUser,
     path: web::Path<i32>,
+    query_params: web::Query<GetRecipeQuery>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    query_params.validate()?;
+    let media_type =
+        utils::preferred_media_type(&req, &[mime::APPLICATION_JSON.as_ref(), LD_JSON])?;
     let recipe_id = path.into_inner();
+    let user_id = auth.user_id();
+    let pool = pool.into_inner();
 
-    let (recipe, categories) = get_recipe(pool.into_inner(), &recipe_id).await?;
-
-    let response = RecipeResponse {
-        id: recipe.id,
-        name: recipe.name,
-        instructions: recipe.instructions,
-        cuisine: recipe.cuisine,
-        duration_min: recipe.duration_min,
-        preparation_needed: recipe.preparation_needed,
-        portions: recipe.portions,
-        difficulty: recipe.difficulty,
-        categories: categories.into_iter().map(|c| c.into()).collect(),
+    let (recipe, categories, mut parts, steps) = match query_params.portions {
+        Some(portions) => get_scaled_recipe(pool.clone(), &user_id, &recipe_id, &portions).await?,
+        None => get_recipe(pool.clone(), &user_id, &recipe_id).await?,
     };
+    if let Some(system) = &query_params.units {
+        convert_parts(&mut parts, system);
+    }
+    let etag = match media_type {
+        LD_JSON => utils::variant_etag(&utils::version_etag(recipe.version), "ld"),
+        _ => utils::version_etag(recipe.version),
+    };
+    if utils::is_not_modified(&req, &etag) {
+        return Ok(utils::not_modified_negotiated(etag));
+    }
 
-    let response_serialized = serde_json::to_string(&response)?;
+    let response_serialized = match media_type {
+        LD_JSON => {
+            let url = req.url_for("recipes_get", [recipe_id.to_string()])?;
+            let image = match has_recipe_image(pool, &recipe_id).await? {
+                true => Some(req.url_for("recipes_get_image", [recipe_id.to_string()])?),
+                false => None,
+            };
+            let response = RecipeJsonLd::new(
+                (recipe, categories, parts, steps),
+                url.to_string(),
+                image.map(|image| image.to_string()),
+            );
+            serde_json::to_string(&response)?
+        }
+        _ => {
+            let response: RecipeDetailResponse = (recipe, categories, parts, steps).into();
+            serde_json::to_string(&response)?
+        }
+    };
 
     return Ok(HttpResponse::Ok()
-        .content_type(ContentType::json())
+        .content_type(media_type)
+        .insert_header(ETag(etag))
+        .insert_header((header::VARY, "Accept"))
         .body(response_serialized));
 }
 
 #[utoipa::path(
     tag = "recipes",
     responses(
-        (status = 200, description = "Get recipe image", content_type = "image/jpeg", body = Vec<u8>)
+        (status = 200, description = "Get recipe image", content_type = "image/jpeg", body = Vec<u8>),
+        (status = 304, description = "Image matches `If-None-Match`")
     )
 )]
 #[get("/{id}/image")]
 pub async fn recipes_get_image(
+    req: HttpRequest,
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: OptionalUser,
     path: web::Path<i32>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
     let recipe_id = path.into_inner();
 
-    let image = get_recipe_image(pool.into_inner(), &recipe_id).await?;
+    let image = get_recipe_image(pool.into_inner(), &auth.user_id(), &recipe_id).await?;
+    let etag = utils::content_etag(&image.bytes);
+    if utils::is_not_modified(&req, &etag) {
+        return Ok(utils::not_modified(etag));
+    }
 
     return Ok(HttpResponse::Ok()
         .content_type(image.type_)
+        .insert_header(ETag(etag))
         .body(image.bytes));
 }
 
 #[utoipa::path(
     tag = "recipes",
     responses(
-        (status = 201, description = "Create recipe", body = Recipe)
-    )
+        (status = 201, description = "Create recipe", body = RecipeDetailResponse),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
 )]
 #[post("")]
 pub async fn recipes_create(
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
     recipe_body: web::Json<NewRecipe>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
     let recipe_body = recipe_body.into_inner();
+    recipe_body.validate()?;
+    let (instructions, steps) = required_instructions_and_steps(
+        recipe_body.instructions,
+        recipe_body
+            .steps
+            .map(|steps| steps.into_iter().map(|step| step.into()).collect()),
+    )?;
     let new_recipe = NewRecipeInsert {
         name: recipe_body.name,
-        instructions: recipe_body.instructions,
+        instructions,
         cuisine: recipe_body.cuisine,
         duration_min: recipe_body.duration_min,
         preparation_needed: recipe_body.preparation_needed,
         portions: recipe_body.portions,
         difficulty: recipe_body.difficulty,
+        owner_id: Some(auth.user.id),
+        visibility: recipe_body.visibility.unwrap_or_default(),
     };
     let rec_ings = recipe_body
         .ingredients
@@ -150,86 +315,227 @@ pub async fn recipes_create(
         .map(|rec_ing| NewRecipeIngredient {
             name: &rec_ing.name,
             part: rec_ing.part,
-            quantity: rec_ing.quantity,
+            quantity: &rec_ing.quantity,
             unit: &rec_ing.unit,
         })
         .collect();
 
     // TODO: improve error handling -- by improving return of the transaction
-    let (recipe, categories) = create_recipe(
+    let recipe = create_recipe(
         pool.into_inner(),
         &new_recipe,
         &recipe_body.categories,
         &rec_ings,
+        &steps,
     )
     .await?;
+    let etag = utils::version_etag(recipe.0.version);
 
-    let response = RecipeResponse {
-        id: recipe.id,
-        name: recipe.name,
-        instructions: recipe.instructions,
-        cuisine: recipe.cuisine,
-        duration_min: recipe.duration_min,
-        preparation_needed: recipe.preparation_needed,
-        portions: recipe.portions,
-        difficulty: recipe.difficulty,
-        categories: categories.into_iter().map(|c| c.into()).collect(),
+    let response: RecipeDetailResponse = recipe.into();
+
+    let response_serialized = serde_json::to_string(&response)?;
+
+    return Ok(HttpResponse::Created()
+        .content_type(ContentType::json())
+        .insert_header(ETag(etag))
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    request_body(
+        description = "schema.org Recipe as JSON-LD or a web page embedding it",
+        content((String = "application/ld+json"), (String = "text/html"))
+    ),
+    responses(
+        (status = 201, description = "Import recipe as private recipe of the caller", body = ImportedRecipeResponse),
+        (status = 415, description = "Body is neither JSON-LD nor HTML"),
+        (status = 422, description = "No usable schema.org Recipe found")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/import")]
+pub async fn recipes_import(
+    req: HttpRequest,
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    body: web::Bytes,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    // pages are not always valid UTF-8, the recipe usually still is
+    let body = String::from_utf8_lossy(&body);
+    let source = match req.content_type() {
+        LD_JSON | "application/json" => ImportSource::JsonLd(&body),
+        "text/html" => ImportSource::Html(&body),
+        _ => {
+            return Err(errors::ApiErrors::UnsupportedMediaType {
+                code: "unsupported_media_type",
+                detail: format!("Imports must be sent as {LD_JSON} or text/html"),
+            })
+        }
     };
 
+    let (recipe, warnings) = import_recipe(pool.into_inner(), &auth.user.id, &source).await?;
+    let etag = utils::version_etag(recipe.0.version);
+    let response = ImportedRecipeResponse {
+        recipe: recipe.into(),
+        warnings,
+    };
     let response_serialized = serde_json::to_string(&response)?;
 
     return Ok(HttpResponse::Created()
         .content_type(ContentType::json())
+        .insert_header(ETag(etag))
         .body(response_serialized));
 }
 
 #[utoipa::path(
     tag = "recipes",
     responses(
-        (status = 200, description = "Alter recipe", body = RecipeResponse)
-    )
+        (status = 200, description = "Replace recipe", body = RecipeDetailResponse),
+        (status = 412, description = "Recipe version does not match `If-Match`"),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
 )]
 #[put("/{id}")]
 pub async fn recipes_change(
+    req: HttpRequest,
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
     path: web::Path<i32>,
-    recipe_changeset_body: web::Json<ChangeRecipe>,
+    recipe_body: web::Json<NewRecipe>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
-    // TODO: enable update of ingredients
+    auth.require_scope(Scope::WriteRecipes)?;
     let recipe_id = path.into_inner();
-    let recipe_changeset_body = recipe_changeset_body.into_inner();
+    let recipe_body = recipe_body.into_inner();
+    recipe_body.validate()?;
+    let (instructions, steps) = required_instructions_and_steps(
+        recipe_body.instructions,
+        recipe_body
+            .steps
+            .map(|steps| steps.into_iter().map(|step| step.into()).collect()),
+    )?;
     let recipe_changeset = ChangeRecipeUpdate {
-        name: recipe_changeset_body.name,
-        instructions: recipe_changeset_body.instructions,
-        cuisine: recipe_changeset_body.cuisine,
-        duration_min: recipe_changeset_body.duration_min,
-        preparation_needed: recipe_changeset_body.preparation_needed,
-        portions: recipe_changeset_body.portions,
-        difficulty: recipe_changeset_body.difficulty,
+        name: Some(recipe_body.name),
+        instructions: Some(instructions),
+        cuisine: Some(recipe_body.cuisine),
+        duration_min: Some(recipe_body.duration_min),
+        preparation_needed: Some(recipe_body.preparation_needed),
+        portions: Some(recipe_body.portions),
+        difficulty: Some(recipe_body.difficulty),
+        visibility: recipe_body.visibility,
     };
+    let rec_ings = recipe_body
+        .ingredients
+        .iter()
+        .map(|rec_ing| NewRecipeIngredient {
+            name: &rec_ing.name,
+            part: rec_ing.part,
+            quantity: &rec_ing.quantity,
+            unit: &rec_ing.unit,
+        })
+        .collect();
 
-    let (recipe, categories_vec) = update_recipe(
+    let recipe = update_recipe(
         pool.into_inner(),
+        &auth.user.id,
         &recipe_id,
+        &utils::if_match_versions(&req),
         &recipe_changeset,
-        &recipe_changeset_body.categories,
+        &Some(recipe_body.categories),
+        &Some(rec_ings),
+        &Some(steps),
     )
     .await?;
-    let recipe_body = RecipeResponse {
-        id: recipe.id,
-        name: recipe.name,
-        instructions: recipe.instructions,
-        cuisine: recipe.cuisine,
-        duration_min: recipe.duration_min,
-        preparation_needed: recipe.preparation_needed,
-        portions: recipe.portions,
-        difficulty: recipe.difficulty,
-        categories: categories_vec.into_iter().map(|c| c.into()).collect(),
+    let etag = utils::version_etag(recipe.0.version);
+    let recipe_body: RecipeDetailResponse = recipe.into();
+    let response_serialized = serde_json::to_string(&recipe_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .insert_header(ETag(etag))
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    request_body(content = RecipePatch, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "Change recipe fields with a JSON merge patch", body = RecipeDetailResponse),
+        (status = 412, description = "Recipe version does not match `If-Match`"),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[patch("/{id}")]
+pub async fn recipes_patch(
+    req: HttpRequest,
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    patch_body: web::Json<RecipePatch>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    if req.content_type() != MERGE_PATCH_JSON {
+        return Err(errors::ApiErrors::UnsupportedMediaType {
+            code: "unsupported_media_type",
+            detail: format!("Patches must be sent as {MERGE_PATCH_JSON}"),
+        });
+    }
+    let recipe_id = path.into_inner();
+    let patch_body = patch_body.into_inner();
+    patch_body.validate()?;
+    let (instructions, steps) = instructions_and_steps(
+        utils::not_null(patch_body.instructions, "instructions")?,
+        utils::not_null(patch_body.steps, "steps")?
+            .map(|steps| steps.into_iter().map(|step| step.into()).collect()),
+    )?
+    .unzip();
+    let recipe_changeset = ChangeRecipeUpdate {
+        name: utils::not_null(patch_body.name, "name")?,
+        instructions,
+        cuisine: utils::not_null(patch_body.cuisine, "cuisine")?,
+        duration_min: utils::not_null(patch_body.duration_min, "duration_min")?,
+        preparation_needed: utils::not_null(patch_body.preparation_needed, "preparation_needed")?,
+        portions: utils::not_null(patch_body.portions, "portions")?,
+        difficulty: utils::not_null(patch_body.difficulty, "difficulty")?,
+        visibility: patch_body
+            .visibility
+            .map(|visibility| visibility.unwrap_or_default()),
     };
+    let categories = utils::not_null(patch_body.categories, "categories")?;
+    let ingredients = utils::not_null(patch_body.ingredients, "ingredients")?;
+    let rec_ings = ingredients.as_ref().map(|ingredients| {
+        ingredients
+            .iter()
+            .map(|rec_ing| NewRecipeIngredient {
+                name: &rec_ing.name,
+                part: rec_ing.part,
+                quantity: &rec_ing.quantity,
+                unit: &rec_ing.unit,
+            })
+            .collect()
+    });
+
+    let recipe = update_recipe(
+        pool.into_inner(),
+        &auth.user.id,
+        &recipe_id,
+        &utils::if_match_versions(&req),
+        &recipe_changeset,
+        &categories,
+        &rec_ings,
+        &steps,
+    )
+    .await?;
+    let etag = utils::version_etag(recipe.0.version);
+    let recipe_body: RecipeDetailResponse = recipe.into();
     let response_serialized = serde_json::to_string(&recipe_body)?;
 
     return Ok(HttpResponse::Ok()
         .content_type(ContentType::json())
+        .insert_header(ETag(etag))
         .body(response_serialized));
 }
 
@@ -237,33 +543,57 @@ pub async fn recipes_change(
     tag = "recipes",
     responses(
         (status = 200, description = "Alter recipe image", content_type = "image/jpeg", body = Vec<u8>)
-    )
+    ),
+    security(("bearer_auth" = []))
 )]
 #[put("/{id}/image")]
 pub async fn recipes_change_image(
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
     path: web::Path<i32>,
     MultipartForm(form): MultipartForm<ChangeRecipeImage>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
     let recipe_id = path.into_inner();
 
     let file_type = match form.image.content_type {
         Some(content_type) => content_type,
         // content type must be specified by the client
-        None => return Err(errors::ApiErrors::BadRequest),
+        None => {
+            return Err(errors::ApiErrors::BadRequest {
+                code: "missing_content_type",
+                detail: "Content type of the image must be specified".to_string(),
+                field: Some("image".to_string()),
+            })
+        }
     };
     if file_type.type_() != mime::IMAGE {
         // file is not image
-        return Err(errors::ApiErrors::BadRequest);
+        return Err(errors::ApiErrors::BadRequest {
+            code: "not_an_image",
+            detail: format!("Content type '{file_type}' is not an image"),
+            field: Some("image".to_string()),
+        });
     }
     if file_type.subtype() == mime::SVG {
         // don't allow SVG files --> Stored XSS vulnerability
-        return Err(errors::ApiErrors::BadRequest);
+        return Err(errors::ApiErrors::BadRequest {
+            code: "unsupported_image_type",
+            detail: "SVG images are not supported".to_string(),
+            field: Some("image".to_string()),
+        });
     }
 
     let image_bytes = web::block(move || std::fs::read(form.image.file.path())).await??;
 
-    change_recipe_image(pool.into_inner(), &recipe_id, &image_bytes, &file_type).await?;
+    change_recipe_image(
+        pool.into_inner(),
+        &auth.user.id,
+        &recipe_id,
+        &image_bytes,
+        &file_type,
+    )
+    .await?;
 
     return Ok(HttpResponse::Ok()
         .content_type(file_type.essence_str())
@@ -273,17 +603,28 @@ pub async fn recipes_change_image(
 #[utoipa::path(
     tag = "recipes",
     responses(
-        (status = 204, description = "Delete recipe")
-    )
+        (status = 204, description = "Delete recipe"),
+        (status = 412, description = "Recipe version does not match `If-Match`")
+    ),
+    security(("bearer_auth" = []))
 )]
 #[delete("/{id}")]
 pub async fn recipes_delete(
+    req: HttpRequest,
     pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
     path: web::Path<i32>,
 ) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
     let recipe_id = path.into_inner();
 
-    delete_recipe(pool.into_inner(), &recipe_id).await?;
+    delete_recipe(
+        pool.into_inner(),
+        &auth.user.id,
+        &recipe_id,
+        &utils::if_match_versions(&req),
+    )
+    .await?;
 
     return Ok(HttpResponse::NoContent()
         .content_type(ContentType::json())
@@ -291,12 +632,433 @@ pub async fn recipes_delete(
         .finish());
 }
 
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 200, description = "List users the recipe is shared with", body = utils::ResponseBodyVec<Vec<RecipeShareResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/{id}/shares")]
+pub async fn recipes_list_shares(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let recipe_id = path.into_inner();
+
+    let shares: Vec<RecipeShareResponse> =
+        list_recipe_shares(pool.into_inner(), &auth.user.id, &recipe_id)
+            .await?
+            .into_iter()
+            .map(|share| share.into())
+            .collect();
+
+    let response_body = utils::ResponseBodyVec {
+        result: shares,
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 200, description = "Share recipe with the user", body = RecipeShareResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}/shares/{username}")]
+pub async fn recipes_share(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<(i32, String)>,
+    share_body: web::Json<ShareRecipe>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let (recipe_id, username) = path.into_inner();
+
+    let share: RecipeShareResponse = share_recipe(
+        pool.into_inner(),
+        &auth.user.id,
+        &recipe_id,
+        &username,
+        &share_body.access,
+    )
+    .await?
+    .into();
+    let response_serialized = serde_json::to_string(&share)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 204, description = "Stop sharing recipe with the user")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}/shares/{username}")]
+pub async fn recipes_unshare(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<(i32, String)>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let (recipe_id, username) = path.into_inner();
+
+    unshare_recipe(pool.into_inner(), &auth.user.id, &recipe_id, &username).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 204, description = "Star recipe")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}/favourite")]
+pub async fn recipes_add_favourite(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let recipe_id = path.into_inner();
+
+    add_favourite(pool.into_inner(), &auth.user.id, &recipe_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 204, description = "Unstar recipe")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}/favourite")]
+pub async fn recipes_remove_favourite(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let recipe_id = path.into_inner();
+
+    remove_favourite(pool.into_inner(), &auth.user.id, &recipe_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 200, description = "List recipe reviews, latest first", body = utils::ResponseBodyVec<Vec<ReviewResponse>>)
+    )
+)]
+#[get("/{id}/reviews")]
+pub async fn recipes_list_reviews(
+    req: HttpRequest,
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: OptionalUser,
+    path: web::Path<i32>,
+    page_params: web::Query<utils::PageQuery>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let recipe_id = path.into_inner();
+    let page: Page = (&page_params.into_inner()).into();
+
+    let (reviews, total) =
+        list_reviews(pool.into_inner(), &auth.user_id(), &recipe_id, &page).await?;
+
+    let response_body = utils::ResponseBodyVec {
+        result: reviews
+            .into_iter()
+            .map(|review| review.into())
+            .collect::<Vec<ReviewResponse>>(),
+        pagination: Some(utils::Pagination::new(&req, &page, total)),
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 201, description = "Review recipe, once per user", body = ReviewResponse),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/{id}/reviews")]
+pub async fn recipes_create_review(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    review_body: web::Json<NewReview>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    review_body.validate()?;
+    let recipe_id = path.into_inner();
+
+    let review = create_review(
+        pool.into_inner(),
+        &auth.user.id,
+        &recipe_id,
+        &review_body.rating,
+        &review_body.text,
+    )
+    .await?;
+    let response: ReviewResponse = (review, auth.user).into();
+    let response_serialized = serde_json::to_string(&response)?;
+
+    return Ok(HttpResponse::Created()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 200, description = "Change own review of the recipe", body = ReviewResponse),
+        (status = 422, description = "Invalid fields")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}/reviews")]
+pub async fn recipes_change_review(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    review_body: web::Json<NewReview>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    review_body.validate()?;
+    let recipe_id = path.into_inner();
+
+    let review = update_review(
+        pool.into_inner(),
+        &auth.user.id,
+        &recipe_id,
+        &review_body.rating,
+        &review_body.text,
+    )
+    .await?;
+    let response: ReviewResponse = (review, auth.user).into();
+    let response_serialized = serde_json::to_string(&response)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 204, description = "Delete own review of the recipe")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}/reviews")]
+pub async fn recipes_delete_review(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let recipe_id = path.into_inner();
+
+    delete_review(pool.into_inner(), &auth.user.id, &recipe_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 200, description = "List recipe revisions, latest first", body = utils::ResponseBodyVec<Vec<RecipeRevisionResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/{id}/revisions")]
+pub async fn recipes_list_revisions(
+    req: HttpRequest,
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    page_params: web::Query<utils::PageQuery>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let recipe_id = path.into_inner();
+    let page: Page = (&page_params.into_inner()).into();
+
+    let (revisions, total) =
+        list_revisions(pool.into_inner(), &auth.user.id, &recipe_id, &page).await?;
+
+    let response_body = utils::ResponseBodyVec {
+        result: revisions
+            .into_iter()
+            .map(|revision| revision.into())
+            .collect::<Vec<RecipeRevisionResponse>>(),
+        pagination: Some(utils::Pagination::new(&req, &page, total)),
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 200, description = "Fields changed between two recipe revisions", body = Vec<RevisionChangeResponse>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/{id}/revisions/diff")]
+pub async fn recipes_diff_revisions(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+    query_params: web::Query<DiffRevisionsQuery>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    query_params.validate()?;
+    let recipe_id = path.into_inner();
+
+    let changes = diff_revisions(
+        pool.into_inner(),
+        &auth.user.id,
+        &recipe_id,
+        &query_params.from,
+        &query_params.to,
+    )
+    .await?;
+
+    let response: Vec<RevisionChangeResponse> =
+        changes.into_iter().map(|change| change.into()).collect();
+    let response_serialized = serde_json::to_string(&response)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 200, description = "Get recipe revision with its snapshot", body = RecipeRevisionDetailResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/{id}/revisions/{revision}")]
+pub async fn recipes_get_revision(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<(i32, i32)>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let (recipe_id, revision) = path.into_inner();
+
+    let revision = get_revision(pool.into_inner(), &auth.user.id, &recipe_id, &revision).await?;
+
+    let response: RecipeRevisionDetailResponse = revision.into();
+    let response_serialized = serde_json::to_string(&response)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "recipes",
+    responses(
+        (status = 200, description = "Restore recipe to an earlier revision, recorded as a new revision", body = RecipeDetailResponse),
+        (status = 412, description = "Recipe version does not match `If-Match`")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/{id}/revisions/{revision}/restore")]
+pub async fn recipes_restore_revision(
+    req: HttpRequest,
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<(i32, i32)>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let (recipe_id, revision) = path.into_inner();
+
+    let recipe = restore_revision(
+        pool.into_inner(),
+        &auth.user.id,
+        &recipe_id,
+        &revision,
+        &utils::if_match_versions(&req),
+    )
+    .await?;
+    let etag = utils::version_etag(recipe.0.version);
+
+    let response: RecipeDetailResponse = recipe.into();
+    let response_serialized = serde_json::to_string(&response)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .insert_header(ETag(etag))
+        .body(response_serialized));
+}
+
 pub fn recipes_config(cfg: &mut service_config::ServiceConfig) {
     cfg.service(recipes_list);
+    // before `/{id}` which would reject the path
+    cfg.service(recipes_list_cookable);
     cfg.service(recipes_get);
     cfg.service(recipes_get_image);
     cfg.service(recipes_create);
+    cfg.service(recipes_import);
     cfg.service(recipes_change);
+    cfg.service(recipes_patch);
     cfg.service(recipes_change_image);
     cfg.service(recipes_delete);
+    cfg.service(recipes_list_shares);
+    cfg.service(recipes_share);
+    cfg.service(recipes_unshare);
+    cfg.service(recipes_add_favourite);
+    cfg.service(recipes_remove_favourite);
+    cfg.service(recipes_list_reviews);
+    cfg.service(recipes_create_review);
+    cfg.service(recipes_change_review);
+    cfg.service(recipes_delete_review);
+    cfg.service(recipes_list_revisions);
+    // before `/{id}/revisions/{revision}` which would reject the path
+    cfg.service(recipes_diff_revisions);
+    cfg.service(recipes_get_revision);
+    cfg.service(recipes_restore_revision);
 }
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::recipes_service::models::api_key::Scope;
+
+// POST
+
+#[derive(ToSchema, Deserialize)]
+pub struct NewApiKey {
+    /// what the key is used for, e.g. "kitchen tablet"
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    /// the key never expires when missing
+    pub expires_at: Option<DateTime<Utc>>,
+}
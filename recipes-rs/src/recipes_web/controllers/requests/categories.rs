@@ -0,0 +1,16 @@
+use crate::recipes_service::models::category::{ChangeCategory, NewCategory};
+use crate::recipes_web::validation::{FieldErrors, Validate};
+
+impl Validate for NewCategory {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.length("name", &self.name, 1, 50);
+    }
+}
+
+impl Validate for ChangeCategory {
+    fn check(&self, errors: &mut FieldErrors) {
+        if let Some(name) = &self.name {
+            errors.length("name", name, 1, 50);
+        }
+    }
+}
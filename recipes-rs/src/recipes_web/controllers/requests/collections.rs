@@ -0,0 +1,15 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+// POST, PUT
+
+#[derive(ToSchema, Deserialize)]
+pub struct NewCollection {
+    pub name: String,
+}
+
+#[derive(ToSchema, Deserialize)]
+pub struct OrderCollection {
+    /// recipes to put first in this order, the others keep their order after them
+    pub recipe_ids: Vec<i32>,
+}
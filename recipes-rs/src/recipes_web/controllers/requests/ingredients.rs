@@ -0,0 +1,45 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::recipes_service::models::ingredient::ChangeIngredient;
+use crate::recipes_web::validation::{FieldErrors, Validate};
+
+// GET
+
+#[derive(Deserialize)]
+pub struct ListIngredientsQuery {
+    pub search: Option<String>,
+}
+
+// POST
+
+#[derive(ToSchema, Deserialize)]
+pub struct MergeIngredients {
+    /// duplicates to be merged into the ingredient from path
+    pub ingredient_ids: Vec<i32>,
+}
+
+impl Validate for ChangeIngredient {
+    fn check(&self, errors: &mut FieldErrors) {
+        // the name is the only field, a rename without it changes nothing
+        match &self.name {
+            Some(name) => errors.length("name", name, 1, 100),
+            None => errors.add("name", "is required".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_blank_and_missing_names() {
+        let change = |name: Option<&str>| ChangeIngredient {
+            name: name.map(str::to_string),
+        };
+        assert!(change(Some(" flour ")).validate().is_ok());
+        assert!(change(Some("   ")).validate().is_err());
+        assert!(change(None).validate().is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::recipes_service::models::meal_plan::{ChangeMealPlanEntry, MealSlot};
+use crate::recipes_web::validation::{FieldErrors, Validate};
+
+// GET
+
+#[derive(Deserialize)]
+pub struct ListMealPlanQuery {
+    /// first listed day, the current week is listed without dates
+    pub from: Option<NaiveDate>,
+    /// last listed day, a week after `from` when missing
+    pub to: Option<NaiveDate>,
+}
+
+// POST
+
+#[derive(ToSchema, Deserialize)]
+pub struct NewMealPlanEntry {
+    pub date: NaiveDate,
+    pub slot: MealSlot,
+    pub recipe_id: i32,
+    /// portions of the recipe when missing
+    #[schema(minimum = 1, maximum = 1000)]
+    pub portions: Option<i32>,
+}
+
+#[derive(ToSchema, Deserialize)]
+pub struct CopyMealPlanWeek {
+    /// first day of the copied week
+    pub from: NaiveDate,
+    /// first day of the week to copy to
+    pub to: NaiveDate,
+    /// remove entries already planned in the target week
+    pub replace: Option<bool>,
+}
+
+impl Validate for NewMealPlanEntry {
+    fn check(&self, errors: &mut FieldErrors) {
+        if let Some(portions) = self.portions {
+            errors.range("portions", portions, 1, 1000);
+        }
+    }
+}
+
+impl Validate for ChangeMealPlanEntry {
+    fn check(&self, errors: &mut FieldErrors) {
+        if let Some(portions) = self.portions {
+            errors.range("portions", portions, 1, 1000);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_portions() {
+        let change = |portions: Option<i32>| ChangeMealPlanEntry {
+            date: None,
+            slot: None,
+            recipe_id: None,
+            portions,
+        };
+        assert!(change(None).validate().is_ok());
+        assert!(change(Some(1)).validate().is_ok());
+        assert!(change(Some(1000)).validate().is_ok());
+        assert!(change(Some(0)).validate().is_err());
+        assert!(change(Some(1001)).validate().is_err());
+    }
+}
@@ -1 +1,9 @@
+pub mod api_keys;
+pub mod categories;
+pub mod collections;
+pub mod ingredients;
+pub mod meal_plans;
+pub mod pantry;
 pub mod recipes;
+pub mod shopping_lists;
+pub mod users;
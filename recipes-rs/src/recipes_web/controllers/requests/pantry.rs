@@ -0,0 +1,21 @@
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::recipes_web::utils;
+
+// POST, PUT
+
+#[derive(ToSchema, Deserialize)]
+pub struct NewPantryItem {
+    /// ingredient name, created when it does not exist yet
+    pub name: String,
+    /// decimal number or fraction, e.g. 2, "0.5", "1 1/2" or "1½"
+    #[serde(deserialize_with = "utils::deserialize_quantity")]
+    #[schema(value_type = String, example = "1 1/2")]
+    pub quantity: BigDecimal,
+    pub unit: String,
+    /// never expires when missing
+    pub expires_on: Option<NaiveDate>,
+}
@@ -1,52 +1,171 @@
 use actix_multipart::form::{tempfile::TempFile, MultipartForm};
+use bigdecimal::BigDecimal;
 use serde::Deserialize;
 use utoipa::ToSchema;
 
+use crate::recipes_service::models::{
+    recipe::Visibility, recipe_share::Access, recipe_step::NewRecipeStep,
+};
+use crate::recipes_service::quantity::max_quantity;
+use crate::recipes_service::recipes::{RecipeSort, SortOrder};
+use crate::recipes_service::units::UnitSystem;
+use crate::recipes_web::utils;
+use crate::recipes_web::validation::{FieldErrors, Validate};
+
 // GET
 
 #[derive(Deserialize)]
 pub struct ListRecipesQuery {
+    pub q: Option<String>,
     pub category: Option<String>,
     pub cuisine: Option<String>,
     pub min_duration: Option<i32>,
     pub max_duration: Option<i32>,
+    /// lowest average review rating, e.g. 4 or 3.5
+    pub min_rating: Option<BigDecimal>,
+    /// only recipes starred by the caller
+    pub favourites: Option<bool>,
+    /// only recipes in the collection of the caller
+    pub collection: Option<i32>,
+    pub sort: Option<RecipeSort>,
+    pub order: Option<SortOrder>,
 }
 
-// POST
+#[derive(Deserialize)]
+pub struct CookableRecipesQuery {
+    pub q: Option<String>,
+    pub category: Option<String>,
+    pub cuisine: Option<String>,
+    pub min_duration: Option<i32>,
+    pub max_duration: Option<i32>,
+    /// lowest average review rating, e.g. 4 or 3.5
+    pub min_rating: Option<BigDecimal>,
+}
+
+#[derive(Deserialize)]
+pub struct GetRecipeQuery {
+    /// scale ingredient quantities to the number of portions
+    pub portions: Option<i32>,
+    /// render ingredient quantities in units of the system
+    pub units: Option<UnitSystem>,
+}
+
+#[derive(Deserialize)]
+pub struct DiffRevisionsQuery {
+    pub from: i32,
+    pub to: i32,
+}
+
+// POST, PUT
 
 #[derive(ToSchema, Deserialize)]
 pub struct NewIngredients {
+    #[schema(min_length = 1, max_length = 100)]
     pub name: String,
+    #[schema(minimum = 0)]
     pub part: i16,
-    pub quantity: i32,
+    /// positive decimal number or fraction, e.g. 2, "0.5", "1 1/2" or "1½", at most 9999999.999
+    #[serde(deserialize_with = "utils::deserialize_quantity")]
+    #[schema(value_type = String, example = "1 1/2", maximum = 9999999.999)]
+    pub quantity: BigDecimal,
+    #[schema(max_length = 20)]
     pub unit: String,
 }
 
+#[derive(ToSchema, Deserialize)]
+pub struct NewStep {
+    #[schema(min_length = 1)]
+    pub text: String,
+    /// time the step takes in minutes
+    #[schema(minimum = 0)]
+    pub duration_min: Option<i32>,
+    /// ids of recipe ingredients used in the step
+    pub ingredient_ids: Option<Vec<i32>>,
+}
+
 #[derive(ToSchema, Deserialize)]
 pub struct NewRecipe {
+    #[schema(min_length = 1, max_length = 200)]
     pub name: String,
-    pub instructions: String,
+    /// split into steps when `steps` are missing, required then
+    #[schema(min_length = 1)]
+    pub instructions: Option<String>,
+    #[schema(max_length = 100)]
     pub cuisine: String,
+    #[schema(minimum = 0)]
     pub duration_min: i32,
     pub preparation_needed: bool,
+    #[schema(minimum = 1, maximum = 1000)]
     pub portions: i32,
+    /// from 1 (easy) to 5 (hard)
+    #[schema(minimum = 1, maximum = 5)]
     pub difficulty: i32,
     pub categories: Vec<String>,
     pub ingredients: Vec<NewIngredients>,
+    /// flattened into `instructions` when present
+    pub steps: Option<Vec<NewStep>>,
+    /// private when missing on create, unchanged when missing on replace, can be changed by the
+    /// owner only
+    pub visibility: Option<Visibility>,
 }
 
-// PUT
+// PATCH
 
+/// JSON merge patch (RFC 7396) of a recipe. Missing fields are left as they are, arrays replace
+/// the current ones as a whole and `null` removes a value, which only `visibility` allows.
 #[derive(ToSchema, Deserialize)]
-pub struct ChangeRecipe {
-    pub name: Option<String>,
-    pub instructions: Option<String>,
-    pub cuisine: Option<String>,
-    pub duration_min: Option<i32>,
-    pub preparation_needed: Option<bool>,
-    pub portions: Option<i32>,
-    pub difficulty: Option<i32>,
-    pub categories: Option<Vec<String>>,
+pub struct RecipePatch {
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<String>, min_length = 1, max_length = 200)]
+    pub name: Option<Option<String>>,
+    /// replaces all steps when present without `steps`
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<String>, min_length = 1)]
+    pub instructions: Option<Option<String>>,
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<String>, max_length = 100)]
+    pub cuisine: Option<Option<String>>,
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<i32>, minimum = 0)]
+    pub duration_min: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<bool>)]
+    pub preparation_needed: Option<Option<bool>>,
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<i32>, minimum = 1, maximum = 1000)]
+    pub portions: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<i32>, minimum = 1, maximum = 5)]
+    pub difficulty: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<Vec<String>>)]
+    pub categories: Option<Option<Vec<String>>>,
+    /// replaces all recipe ingredients when present
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<Vec<NewIngredients>>)]
+    pub ingredients: Option<Option<Vec<NewIngredients>>>,
+    /// replaces all steps and `instructions` when present
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<Vec<NewStep>>)]
+    pub steps: Option<Option<Vec<NewStep>>>,
+    /// `null` makes the recipe private, can be changed by the owner only
+    #[serde(default, deserialize_with = "utils::deserialize_nullable")]
+    #[schema(value_type = Option<Visibility>)]
+    pub visibility: Option<Option<Visibility>>,
+}
+
+#[derive(ToSchema, Deserialize)]
+pub struct NewReview {
+    /// 1 to 5 stars
+    #[schema(minimum = 1, maximum = 5)]
+    pub rating: i16,
+    #[schema(max_length = 5000)]
+    pub text: Option<String>,
+}
+
+#[derive(ToSchema, Deserialize)]
+pub struct ShareRecipe {
+    pub access: Access,
 }
 
 #[derive(MultipartForm)]
@@ -54,3 +173,343 @@ pub struct ChangeRecipeImage {
     #[multipart(limit = "20MB")] // TODO: not sure about the value
     pub image: TempFile,
 }
+
+impl From<NewStep> for NewRecipeStep {
+    fn from(step: NewStep) -> Self {
+        Self {
+            text: step.text,
+            duration_min: step.duration_min,
+            ingredient_ids: step.ingredient_ids.unwrap_or_default(),
+        }
+    }
+}
+
+/// A recipe uses an ingredient once, repeated names would clash on insert. Names are stored trimmed
+/// and compared case-sensitively like the database does, "Salt" and "salt" are two ingredients.
+fn check_unique_ingredients(errors: &mut FieldErrors, ingredients: &[NewIngredients]) {
+    for (index, ingredient) in ingredients.iter().enumerate() {
+        if ingredients[..index]
+            .iter()
+            .any(|other| other.name.trim() == ingredient.name.trim())
+        {
+            errors.add(
+                &format!("ingredients[{index}].name"),
+                "must not repeat an earlier ingredient".to_string(),
+            );
+        }
+    }
+}
+
+/// shared rules of the recipe filters
+fn check_filters(
+    errors: &mut FieldErrors,
+    min_duration: &Option<i32>,
+    max_duration: &Option<i32>,
+    min_rating: &Option<BigDecimal>,
+) {
+    if let Some(min_duration) = min_duration {
+        errors.min("min_duration", *min_duration, 0);
+    }
+    if let Some(max_duration) = max_duration {
+        errors.min("max_duration", *max_duration, 0);
+    }
+    if let (Some(min_duration), Some(max_duration)) = (min_duration, max_duration) {
+        errors.require(
+            min_duration <= max_duration,
+            "max_duration",
+            "must not be less than min_duration",
+        );
+    }
+    if let Some(min_rating) = min_rating {
+        errors.range(
+            "min_rating",
+            min_rating.clone(),
+            BigDecimal::from(1),
+            BigDecimal::from(5),
+        );
+    }
+}
+
+impl Validate for ListRecipesQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        check_filters(
+            errors,
+            &self.min_duration,
+            &self.max_duration,
+            &self.min_rating,
+        );
+    }
+}
+
+impl Validate for CookableRecipesQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        check_filters(
+            errors,
+            &self.min_duration,
+            &self.max_duration,
+            &self.min_rating,
+        );
+    }
+}
+
+impl Validate for GetRecipeQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        if let Some(portions) = self.portions {
+            errors.range("portions", portions, 1, 1000);
+        }
+    }
+}
+
+impl Validate for DiffRevisionsQuery {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.min("from", self.from, 1);
+        errors.min("to", self.to, 1);
+    }
+}
+
+impl Validate for NewIngredients {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.length("name", &self.name, 1, 100);
+        errors.min("part", self.part, 0);
+        errors.require(
+            self.quantity > BigDecimal::from(0),
+            "quantity",
+            "must be greater than 0",
+        );
+        let max_quantity = max_quantity();
+        errors.require(
+            self.quantity <= max_quantity,
+            "quantity",
+            &format!("must be at most {max_quantity}"),
+        );
+        errors.length("unit", &self.unit, 0, 20);
+    }
+}
+
+impl Validate for NewReview {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.range("rating", self.rating, 1, 5);
+        if let Some(text) = &self.text {
+            errors.length("text", text, 0, 5000);
+        }
+    }
+}
+
+impl Validate for NewStep {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.require(!self.text.trim().is_empty(), "text", "must not be blank");
+        if let Some(duration_min) = self.duration_min {
+            errors.min("duration_min", duration_min, 0);
+        }
+    }
+}
+
+impl Validate for NewRecipe {
+    fn check(&self, errors: &mut FieldErrors) {
+        errors.length("name", &self.name, 1, 200);
+        if let Some(instructions) = &self.instructions {
+            errors.require(
+                !instructions.trim().is_empty(),
+                "instructions",
+                "must not be blank",
+            );
+        }
+        errors.length("cuisine", &self.cuisine, 0, 100);
+        errors.min("duration_min", self.duration_min, 0);
+        errors.range("portions", self.portions, 1, 1000);
+        errors.range("difficulty", self.difficulty, 1, 5);
+        errors.each_length("categories", &self.categories, 1, 50);
+        errors.each("ingredients", &self.ingredients);
+        check_unique_ingredients(errors, &self.ingredients);
+        if let Some(steps) = &self.steps {
+            errors.each("steps", steps);
+        }
+    }
+}
+
+impl Validate for RecipePatch {
+    fn check(&self, errors: &mut FieldErrors) {
+        // `null` of required fields is rejected by the controller, only values are checked
+        if let Some(Some(name)) = &self.name {
+            errors.length("name", name, 1, 200);
+        }
+        if let Some(Some(instructions)) = &self.instructions {
+            errors.require(
+                !instructions.trim().is_empty(),
+                "instructions",
+                "must not be blank",
+            );
+        }
+        if let Some(Some(cuisine)) = &self.cuisine {
+            errors.length("cuisine", cuisine, 0, 100);
+        }
+        if let Some(Some(duration_min)) = self.duration_min {
+            errors.min("duration_min", duration_min, 0);
+        }
+        if let Some(Some(portions)) = self.portions {
+            errors.range("portions", portions, 1, 1000);
+        }
+        if let Some(Some(difficulty)) = self.difficulty {
+            errors.range("difficulty", difficulty, 1, 5);
+        }
+        if let Some(Some(categories)) = &self.categories {
+            errors.each_length("categories", categories, 1, 50);
+        }
+        if let Some(Some(ingredients)) = &self.ingredients {
+            errors.each("ingredients", ingredients);
+            check_unique_ingredients(errors, ingredients);
+        }
+        if let Some(Some(steps)) = &self.steps {
+            errors.each("steps", steps);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes_web::errors::ApiErrors;
+    use serde_json::json;
+
+    /// paths of the invalid fields, in the order they were checked
+    fn invalid_fields<T: Validate>(request: &T) -> Vec<String> {
+        return match request.validate() {
+            Ok(()) => vec![],
+            Err(ApiErrors::InvalidFields { errors }) => {
+                errors.into_iter().map(|error| error.field).collect()
+            }
+            Err(error) => panic!("unexpected error {error:?}"),
+        };
+    }
+
+    fn recipe(changes: serde_json::Value) -> NewRecipe {
+        let mut recipe = json!({
+            "name": "Pancakes",
+            "instructions": "Mix and fry.",
+            "cuisine": "french",
+            "duration_min": 20,
+            "preparation_needed": false,
+            "portions": 4,
+            "difficulty": 2,
+            "categories": ["breakfast"],
+            "ingredients": [
+                {"name": "flour", "part": 0, "quantity": "200", "unit": "g"},
+                {"name": "milk", "part": 0, "quantity": 0.3, "unit": "l"},
+            ],
+        });
+        recipe
+            .as_object_mut()
+            .unwrap()
+            .extend(changes.as_object().unwrap().clone());
+        return serde_json::from_value(recipe).unwrap();
+    }
+
+    fn ingredient(name: &str, quantity: &str) -> serde_json::Value {
+        return json!({"name": name, "part": 0, "quantity": quantity, "unit": "g"});
+    }
+
+    #[test]
+    fn accepts_a_valid_recipe() {
+        assert!(invalid_fields(&recipe(json!({}))).is_empty());
+    }
+
+    #[test]
+    fn rejects_out_of_range_recipe_fields() {
+        let recipe = recipe(json!({
+            "name": "  ",
+            "duration_min": -5,
+            "portions": 0,
+            "difficulty": -7,
+            "categories": ["breakfast", ""],
+        }));
+        assert_eq!(
+            invalid_fields(&recipe),
+            vec![
+                "name",
+                "duration_min",
+                "portions",
+                "difficulty",
+                "categories[1]"
+            ]
+        );
+    }
+
+    #[test]
+    fn prefixes_fields_of_ingredients_and_steps() {
+        let recipe = recipe(json!({
+            "ingredients": [
+                ingredient("flour", "200"),
+                ingredient(" ", "1"),
+                ingredient("sugar", "0"),
+                ingredient(&"x".repeat(101), "1"),
+            ],
+            "steps": [
+                {"text": "Mix."},
+                {"text": " ", "duration_min": -1},
+            ],
+        }));
+        assert_eq!(
+            invalid_fields(&recipe),
+            vec![
+                "ingredients[1].name",
+                "ingredients[2].quantity",
+                "ingredients[3].name",
+                "steps[1].text",
+                "steps[1].duration_min"
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_repeated_ingredient_names() {
+        let recipe = recipe(json!({
+            "ingredients": [
+                ingredient("flour", "200"),
+                ingredient("Flour", "300"),
+                ingredient(" flour ", "50"),
+            ],
+        }));
+        assert_eq!(invalid_fields(&recipe), vec!["ingredients[2].name"]);
+    }
+
+    #[test]
+    fn checks_only_patched_fields() {
+        let patch: RecipePatch = serde_json::from_value(json!({"name": null})).unwrap();
+        assert!(invalid_fields(&patch).is_empty());
+
+        let patch: RecipePatch = serde_json::from_value(json!({
+            "name": "",
+            "duration_min": -1,
+            "portions": 0,
+            "difficulty": -7,
+            "ingredients": [ingredient("flour", "200"), ingredient("", "1")],
+        }))
+        .unwrap();
+        assert_eq!(
+            invalid_fields(&patch),
+            vec![
+                "name",
+                "duration_min",
+                "portions",
+                "difficulty",
+                "ingredients[1].name"
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_inconsistent_filters() {
+        let query = |query: &str| -> ListRecipesQuery {
+            return serde_urlencoded::from_str(query).unwrap();
+        };
+        assert!(invalid_fields(&query("min_duration=10&max_duration=30")).is_empty());
+        assert_eq!(
+            invalid_fields(&query("min_duration=30&max_duration=10")),
+            vec!["max_duration"]
+        );
+        assert_eq!(
+            invalid_fields(&query("min_duration=-1&min_rating=6")),
+            vec!["min_duration", "min_rating"]
+        );
+    }
+}
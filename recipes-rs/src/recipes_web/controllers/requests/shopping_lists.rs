@@ -0,0 +1,38 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+// POST
+
+#[derive(ToSchema, Deserialize)]
+pub struct ShoppingRecipe {
+    pub recipe_id: i32,
+    /// portions of the recipe when missing
+    #[schema(minimum = 1, maximum = 1000)]
+    pub portions: Option<i32>,
+}
+
+/// recipes to shop for, taken from the list and/or the meal plan between the dates
+#[derive(ToSchema, Deserialize)]
+pub struct ShoppingListSource {
+    pub recipes: Option<Vec<ShoppingRecipe>>,
+    /// first day of the meal plan to shop for
+    pub from: Option<NaiveDate>,
+    /// last day of the meal plan to shop for, a week after `from` when missing, at most a year
+    /// after it
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(ToSchema, Deserialize)]
+pub struct NewShoppingList {
+    pub name: String,
+    #[serde(flatten)]
+    pub source: ShoppingListSource,
+}
+
+// PUT
+
+#[derive(ToSchema, Deserialize)]
+pub struct CheckShoppingListItem {
+    pub checked: bool,
+}
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+// POST
+
+#[derive(ToSchema, Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
@@ -1,13 +1,65 @@
+use bigdecimal::ToPrimitive;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::Serialize;
 use utoipa::ToSchema;
 
-use crate::recipes_service::models::category::Category;
+use crate::recipes_service::models::{
+    api_key::{ApiKey, Scope},
+    category::Category,
+    collection::Collection,
+    ingredient::{Ingredient, RecipePart},
+    meal_plan::{MealPlanEntry, MealSlot},
+    pantry_item::PantryItem,
+    recipe::{Recipe, Visibility},
+    recipe_revision::{RecipeRevision, RecipeSnapshot},
+    recipe_share::{Access, RecipeShare},
+    recipe_step::RecipeStep,
+    review::Review,
+    shopping_list::{ShoppingList, ShoppingListItem},
+    user::{Role, User},
+};
+use crate::recipes_service::pantry::CookableRecipe;
+use crate::recipes_service::revisions::FieldChange;
+use crate::recipes_service::shopping_lists::ShoppingItem;
+use crate::recipes_service::units::{Unit, UnitKind, UnitSystem};
 
 #[derive(Serialize, ToSchema)]
 pub struct CategoryResponse {
     pub name: String,
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct IngredientResponse {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UnitResponse {
+    pub code: String,
+    pub kind: UnitKind,
+    /// missing for units used in both systems
+    pub system: Option<UnitSystem>,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecipeIngredientResponse {
+    /// referred to by `ingredient_ids` of steps
+    pub id: i32,
+    pub name: String,
+    /// decimal number, e.g. "2.5", as scaling to other portions makes whole quantities
+    /// fractional
+    pub quantity: String,
+    pub unit: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecipePartResponse {
+    pub part: i16,
+    pub ingredients: Vec<RecipeIngredientResponse>,
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct RecipeResponse {
     pub id: i32,
@@ -18,11 +70,419 @@ pub struct RecipeResponse {
     pub preparation_needed: bool,
     pub portions: i32,
     pub difficulty: i32,
+    pub owner_id: Option<i32>,
+    pub visibility: Visibility,
+    /// average review rating from 1 to 5, missing without reviews
+    pub rating_avg: Option<f64>,
+    pub rating_count: i32,
+    pub categories: Vec<CategoryResponse>,
+    /// highlighted instructions fragment matching the search query, HTML-escaped with matches
+    /// wrapped in `<mark>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+/// recipe of the "what can I cook" listing
+#[derive(Serialize, ToSchema)]
+pub struct CookableRecipeResponse {
+    pub id: i32,
+    pub name: String,
+    pub cuisine: String,
+    pub duration_min: i32,
+    pub portions: i32,
+    pub difficulty: i32,
+    /// number of ingredients available in the pantry
+    pub available: usize,
+    /// number of all recipe ingredients
+    pub total: usize,
+    /// available / total, from 0 to 1
+    pub fraction: f64,
+    /// names of the ingredients to buy
+    pub missing: Vec<String>,
+    /// earliest expiry of the used pantry items
+    pub expires_on: Option<NaiveDate>,
+}
+
+/// single recipe response -- includes ingredients grouped by recipe part
+#[derive(Serialize, ToSchema)]
+pub struct RecipeDetailResponse {
+    pub id: i32,
+    pub name: String,
+    pub instructions: String,
+    pub cuisine: String,
+    pub duration_min: i32,
+    pub preparation_needed: bool,
+    pub portions: i32,
+    pub difficulty: i32,
+    pub owner_id: Option<i32>,
+    pub visibility: Visibility,
+    /// average review rating from 1 to 5, missing without reviews
+    pub rating_avg: Option<f64>,
+    pub rating_count: i32,
     pub categories: Vec<CategoryResponse>,
+    pub parts: Vec<RecipePartResponse>,
+    pub steps: Vec<RecipeStepResponse>,
+}
+
+/// recipe created by an import, with the parts of the document which could not be mapped
+#[derive(Serialize, ToSchema)]
+pub struct ImportedRecipeResponse {
+    pub recipe: RecipeDetailResponse,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecipeStepResponse {
+    /// position of the step, from 1
+    pub ordinal: i32,
+    pub text: String,
+    pub duration_min: Option<i32>,
+    /// ids of recipe ingredients used in the step
+    pub ingredient_ids: Vec<i32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecipeRevisionResponse {
+    pub revision: i32,
+    /// author of the revision, missing when the user was removed
+    pub user_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecipeRevisionDetailResponse {
+    pub revision: i32,
+    pub user_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub snapshot: RecipeSnapshot,
+}
+
+/// snapshot field with different values in the two revisions
+#[derive(Serialize, ToSchema)]
+pub struct RevisionChangeResponse {
+    pub field: String,
+    pub from: serde_json::Value,
+    pub to: serde_json::Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecipeShareResponse {
+    pub user_id: i32,
+    pub username: String,
+    pub access: Access,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CollectionRecipeResponse {
+    pub id: i32,
+    pub name: String,
+}
+
+/// collection with its recipes in collection order
+#[derive(Serialize, ToSchema)]
+pub struct CollectionResponse {
+    pub id: i32,
+    pub name: String,
+    /// token of the public read-only link, missing when not shared
+    pub share_token: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub recipes: Vec<CollectionRecipeResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReviewResponse {
+    pub recipe_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub rating: i16,
+    pub text: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UserResponse {
+    pub id: i32,
+    pub username: String,
+    pub created_at: DateTime<Utc>,
+    pub roles: Vec<Role>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SessionResponse {
+    /// bearer token for the `Authorization` header
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub user: UserResponse,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub id: i32,
+    pub name: String,
+    /// start of the key to tell keys apart
+    pub prefix: String,
+    pub scopes: Vec<Scope>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// response to key creation -- the only time the key is shown
+#[derive(Serialize, ToSchema)]
+pub struct NewApiKeyResponse {
+    /// bearer token for the `Authorization` header
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MealPlanEntryResponse {
+    pub id: i32,
+    pub date: NaiveDate,
+    pub slot: MealSlot,
+    pub recipe_id: i32,
+    pub recipe_name: String,
+    pub portions: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PantryItemResponse {
+    pub id: i32,
+    pub ingredient_id: i32,
+    pub name: String,
+    /// decimal number, e.g. "2.5"
+    pub quantity: String,
+    pub unit: String,
+    pub expires_on: Option<NaiveDate>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ShoppingItemResponse {
+    /// missing for items of unsaved lists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
+    /// decimal number, e.g. "2.5"
+    pub quantity: String,
+    pub unit: String,
+    pub checked: bool,
+}
+
+/// amounts of one ingredient, one item per unit
+#[derive(Serialize, ToSchema)]
+pub struct ShoppingIngredientResponse {
+    pub name: String,
+    pub items: Vec<ShoppingItemResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ShoppingListResponse {
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub ingredients: Vec<ShoppingIngredientResponse>,
+}
+
+/// group items ordered by ingredient name
+pub fn group_shopping_items(
+    items: impl IntoIterator<Item = (String, ShoppingItemResponse)>,
+) -> Vec<ShoppingIngredientResponse> {
+    let mut ingredients: Vec<ShoppingIngredientResponse> = vec![];
+    for (name, item) in items {
+        match ingredients.last_mut() {
+            Some(ingredient) if ingredient.name == name => ingredient.items.push(item),
+            _ => ingredients.push(ShoppingIngredientResponse {
+                name,
+                items: vec![item],
+            }),
+        }
+    }
+
+    return ingredients;
 }
 
 // traits
 
+impl From<(PantryItem, Ingredient)> for PantryItemResponse {
+    fn from((item, ingredient): (PantryItem, Ingredient)) -> Self {
+        Self {
+            id: item.id,
+            ingredient_id: ingredient.id,
+            name: ingredient.name,
+            quantity: item.quantity.normalized().to_string(),
+            unit: item.unit,
+            expires_on: item.expires_on,
+        }
+    }
+}
+
+impl From<CookableRecipe> for CookableRecipeResponse {
+    fn from(cookable: CookableRecipe) -> Self {
+        Self {
+            fraction: cookable.fraction(),
+            id: cookable.recipe.id,
+            name: cookable.recipe.name,
+            cuisine: cookable.recipe.cuisine,
+            duration_min: cookable.recipe.duration_min,
+            portions: cookable.recipe.portions,
+            difficulty: cookable.recipe.difficulty,
+            available: cookable.available,
+            total: cookable.total,
+            missing: cookable.missing,
+            expires_on: cookable.expires_on,
+        }
+    }
+}
+
+impl From<ShoppingListItem> for ShoppingItemResponse {
+    fn from(item: ShoppingListItem) -> Self {
+        Self {
+            id: Some(item.id),
+            quantity: item.quantity.normalized().to_string(),
+            unit: item.unit,
+            checked: item.checked,
+        }
+    }
+}
+
+impl From<ShoppingItem> for ShoppingItemResponse {
+    fn from(item: ShoppingItem) -> Self {
+        Self {
+            id: None,
+            quantity: item.quantity.normalized().to_string(),
+            unit: item.unit,
+            checked: false,
+        }
+    }
+}
+
+impl From<(ShoppingList, Vec<ShoppingListItem>)> for ShoppingListResponse {
+    fn from((shopping_list, items): (ShoppingList, Vec<ShoppingListItem>)) -> Self {
+        Self {
+            id: shopping_list.id,
+            name: shopping_list.name,
+            created_at: shopping_list.created_at,
+            ingredients: group_shopping_items(
+                items
+                    .into_iter()
+                    .map(|item| (item.name.clone(), item.into())),
+            ),
+        }
+    }
+}
+
+impl From<(MealPlanEntry, String)> for MealPlanEntryResponse {
+    fn from((entry, recipe_name): (MealPlanEntry, String)) -> Self {
+        Self {
+            id: entry.id,
+            date: entry.date,
+            slot: entry.slot,
+            recipe_id: entry.recipe_id,
+            recipe_name,
+            portions: entry.portions,
+        }
+    }
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.id,
+            name: api_key.name,
+            prefix: api_key.prefix,
+            scopes: api_key.scopes,
+            created_at: api_key.created_at,
+            expires_at: api_key.expires_at,
+            last_used_at: api_key.last_used_at,
+        }
+    }
+}
+
+impl From<(User, Vec<Role>)> for UserResponse {
+    fn from((user, roles): (User, Vec<Role>)) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            created_at: user.created_at,
+            roles,
+        }
+    }
+}
+
+impl From<(RecipeShare, User)> for RecipeShareResponse {
+    fn from((share, user): (RecipeShare, User)) -> Self {
+        Self {
+            user_id: share.user_id,
+            username: user.username,
+            access: share.access,
+        }
+    }
+}
+
+impl From<(Collection, Vec<Recipe>)> for CollectionResponse {
+    fn from((collection, recipes): (Collection, Vec<Recipe>)) -> Self {
+        Self {
+            id: collection.id,
+            name: collection.name,
+            share_token: collection.share_token,
+            created_at: collection.created_at,
+            recipes: recipes
+                .into_iter()
+                .map(|recipe| CollectionRecipeResponse {
+                    id: recipe.id,
+                    name: recipe.name,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<(Review, User)> for ReviewResponse {
+    fn from((review, user): (Review, User)) -> Self {
+        Self {
+            recipe_id: review.recipe_id,
+            user_id: review.user_id,
+            username: user.username,
+            rating: review.rating,
+            text: review.text,
+            created_at: review.created_at,
+            updated_at: review.updated_at,
+        }
+    }
+}
+
+impl From<RecipeRevision> for RecipeRevisionResponse {
+    fn from(recipe_revision: RecipeRevision) -> Self {
+        Self {
+            revision: recipe_revision.revision,
+            user_id: recipe_revision.user_id,
+            created_at: recipe_revision.created_at,
+        }
+    }
+}
+
+impl From<(RecipeRevision, RecipeSnapshot)> for RecipeRevisionDetailResponse {
+    fn from((recipe_revision, snapshot): (RecipeRevision, RecipeSnapshot)) -> Self {
+        Self {
+            revision: recipe_revision.revision,
+            user_id: recipe_revision.user_id,
+            created_at: recipe_revision.created_at,
+            snapshot,
+        }
+    }
+}
+
+impl From<FieldChange> for RevisionChangeResponse {
+    fn from(change: FieldChange) -> Self {
+        Self {
+            field: change.field,
+            from: change.from,
+            to: change.to,
+        }
+    }
+}
+
 impl From<Category> for CategoryResponse {
     fn from(category: Category) -> Self {
         Self {
@@ -30,3 +490,86 @@ impl From<Category> for CategoryResponse {
         }
     }
 }
+
+impl From<Ingredient> for IngredientResponse {
+    fn from(ingredient: Ingredient) -> Self {
+        Self {
+            id: ingredient.id,
+            name: ingredient.name,
+        }
+    }
+}
+
+impl From<&Unit> for UnitResponse {
+    fn from(unit: &Unit) -> Self {
+        Self {
+            code: unit.code.to_string(),
+            kind: unit.kind,
+            system: unit.system,
+            aliases: unit
+                .aliases
+                .iter()
+                .filter(|alias| !alias.is_empty())
+                .map(|alias| alias.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl From<RecipePart> for RecipePartResponse {
+    fn from(part: RecipePart) -> Self {
+        Self {
+            part: part.part,
+            ingredients: part
+                .ingredients
+                .into_iter()
+                .map(|(rec_ing, ingredient)| RecipeIngredientResponse {
+                    id: ingredient.id,
+                    name: ingredient.name,
+                    quantity: rec_ing.quantity.normalized().to_string(),
+                    unit: rec_ing.unit,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<(Recipe, Vec<Category>, Vec<RecipePart>, Vec<RecipeStep>)> for RecipeDetailResponse {
+    fn from(
+        (recipe, categories, parts, steps): (
+            Recipe,
+            Vec<Category>,
+            Vec<RecipePart>,
+            Vec<RecipeStep>,
+        ),
+    ) -> Self {
+        Self {
+            id: recipe.id,
+            name: recipe.name,
+            instructions: recipe.instructions,
+            cuisine: recipe.cuisine,
+            duration_min: recipe.duration_min,
+            preparation_needed: recipe.preparation_needed,
+            portions: recipe.portions,
+            difficulty: recipe.difficulty,
+            owner_id: recipe.owner_id,
+            visibility: recipe.visibility,
+            rating_avg: recipe.rating_avg.and_then(|rating| rating.to_f64()),
+            rating_count: recipe.rating_count,
+            categories: categories.into_iter().map(|c| c.into()).collect(),
+            parts: parts.into_iter().map(|p| p.into()).collect(),
+            steps: steps.into_iter().map(|s| s.into()).collect(),
+        }
+    }
+}
+
+impl From<RecipeStep> for RecipeStepResponse {
+    fn from(step: RecipeStep) -> Self {
+        Self {
+            ordinal: step.ordinal,
+            text: step.text,
+            duration_min: step.duration_min,
+            ingredient_ids: step.ingredient_ids,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use bigdecimal::ToPrimitive;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::recipes_service::models::{
+    category::Category, ingredient::RecipePart, recipe::Recipe, recipe_step::RecipeStep,
+};
+
+pub const SCHEMA_ORG_CONTEXT: &str = "https://schema.org";
+
+/// schema.org Recipe of a recipe, for rich search results and other apps
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RecipeJsonLd {
+    #[serde(rename = "@context")]
+    pub context: &'static str,
+    #[serde(rename = "@type")]
+    pub type_: &'static str,
+    #[serde(rename = "@id")]
+    pub id: String,
+    pub url: String,
+    pub name: String,
+    /// URL of the recipe image, missing without image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipe_cuisine: Option<String>,
+    pub recipe_category: Vec<String>,
+    /// ISO 8601 duration, e.g. "PT1H30M"
+    pub total_time: String,
+    pub recipe_yield: String,
+    /// ingredient lines, e.g. "1.5 cup flour"
+    pub recipe_ingredient: Vec<String>,
+    pub recipe_instructions: Vec<HowToStepJsonLd>,
+    /// missing without reviews
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggregate_rating: Option<AggregateRatingJsonLd>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HowToStepJsonLd {
+    #[serde(rename = "@type")]
+    pub type_: &'static str,
+    pub position: i32,
+    pub text: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateRatingJsonLd {
+    #[serde(rename = "@type")]
+    pub type_: &'static str,
+    pub rating_value: f64,
+    pub rating_count: i32,
+    pub best_rating: i32,
+    pub worst_rating: i32,
+}
+
+impl RecipeJsonLd {
+    /// `url` is the address of the recipe, `image` the one of its image
+    pub fn new(
+        (recipe, categories, parts, steps): (
+            Recipe,
+            Vec<Category>,
+            Vec<RecipePart>,
+            Vec<RecipeStep>,
+        ),
+        url: String,
+        image: Option<String>,
+    ) -> Self {
+        Self {
+            context: SCHEMA_ORG_CONTEXT,
+            type_: "Recipe",
+            id: url.clone(),
+            url,
+            name: recipe.name,
+            image,
+            recipe_cuisine: Some(recipe.cuisine).filter(|cuisine| !cuisine.is_empty()),
+            recipe_category: categories.into_iter().map(|c| c.name).collect(),
+            total_time: iso_duration(recipe.duration_min),
+            recipe_yield: recipe.portions.to_string(),
+            recipe_ingredient: parts
+                .into_iter()
+                .flat_map(|part| part.ingredients)
+                .map(|(rec_ing, ingredient)| {
+                    let quantity = rec_ing.quantity.normalized().to_string();
+                    // pieces read naturally without unit, e.g. "2 eggs"
+                    match rec_ing.unit.as_str() {
+                        "pc" => format!("{quantity} {}", ingredient.name),
+                        unit => format!("{quantity} {unit} {}", ingredient.name),
+                    }
+                })
+                .collect(),
+            recipe_instructions: steps
+                .into_iter()
+                .map(|step| HowToStepJsonLd {
+                    type_: "HowToStep",
+                    position: step.ordinal,
+                    text: step.text,
+                })
+                .collect(),
+            aggregate_rating: recipe
+                .rating_avg
+                .and_then(|rating| rating.to_f64())
+                .filter(|_| recipe.rating_count > 0)
+                .map(|rating| AggregateRatingJsonLd {
+                    type_: "AggregateRating",
+                    rating_value: rating,
+                    rating_count: recipe.rating_count,
+                    best_rating: 5,
+                    worst_rating: 1,
+                }),
+        }
+    }
+}
+
+/// ISO 8601 duration of minutes, e.g. "PT1H30M"
+fn iso_duration(minutes: i32) -> String {
+    return match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("PT{minutes}M"),
+        (hours, 0) => format!("PT{hours}H"),
+        (hours, minutes) => format!("PT{hours}H{minutes}M"),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_iso_durations() {
+        assert_eq!(iso_duration(0), "PT0M");
+        assert_eq!(iso_duration(45), "PT45M");
+        assert_eq!(iso_duration(60), "PT1H");
+        assert_eq!(iso_duration(90), "PT1H30M");
+        assert_eq!(iso_duration(26 * 60 + 5), "PT26H5M");
+    }
+}
@@ -0,0 +1,234 @@
+use actix_web::{
+    delete, get,
+    http::{header::ContentType, StatusCode},
+    post, put, web, HttpResponse, Responder,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use utoipa_actix_web::service_config;
+
+use crate::{
+    recipes_service::errors::ServiceError,
+    recipes_service::models::api_key::Scope,
+    recipes_service::shopping_lists::{
+        check_shopping_list_item, compute_shopping_list, create_shopping_list,
+        delete_shopping_list, get_shopping_list, list_shopping_lists, ShoppingSource,
+    },
+    recipes_service::utils::shift_date,
+    recipes_web::{auth::AuthenticatedUser, errors, utils},
+};
+
+use super::{
+    requests::shopping_lists::{CheckShoppingListItem, NewShoppingList, ShoppingListSource},
+    responses::json::{
+        group_shopping_items, ShoppingIngredientResponse, ShoppingItemResponse,
+        ShoppingListResponse,
+    },
+};
+
+#[utoipa::path(
+    tag = "shopping_lists",
+    responses(
+        (status = 200, description = "Compute shopping list without saving it", body = utils::ResponseBodyVec<Vec<ShoppingIngredientResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/preview")]
+pub async fn shopping_lists_preview(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    source_body: web::Json<ShoppingListSource>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let source = shopping_source(source_body.into_inner())?;
+
+    let items = compute_shopping_list(pool.into_inner(), &auth.user.id, &source).await?;
+    let response_body = utils::ResponseBodyVec {
+        result: group_shopping_items(
+            items
+                .into_iter()
+                .map(|item| (item.name.clone(), item.into())),
+        ),
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "shopping_lists",
+    responses(
+        (status = 200, description = "List saved shopping lists", body = utils::ResponseBodyVec<Vec<ShoppingListResponse>>)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("")]
+pub async fn shopping_lists_list(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+
+    let shopping_lists: Vec<ShoppingListResponse> =
+        list_shopping_lists(pool.into_inner(), &auth.user.id)
+            .await?
+            .into_iter()
+            .map(|shopping_list| shopping_list.into())
+            .collect();
+
+    let response_body = utils::ResponseBodyVec {
+        result: shopping_lists,
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "shopping_lists",
+    responses(
+        (status = 200, description = "Get shopping list", body = ShoppingListResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/{id}")]
+pub async fn shopping_lists_get(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::ReadRecipes)?;
+    let shopping_list_id = path.into_inner();
+
+    let shopping_list: ShoppingListResponse =
+        get_shopping_list(pool.into_inner(), &auth.user.id, &shopping_list_id)
+            .await?
+            .into();
+    let response_serialized = serde_json::to_string(&shopping_list)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "shopping_lists",
+    responses(
+        (status = 201, description = "Compute and save shopping list", body = ShoppingListResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("")]
+pub async fn shopping_lists_create(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    shopping_list_body: web::Json<NewShoppingList>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let shopping_list_body = shopping_list_body.into_inner();
+    let source = shopping_source(shopping_list_body.source)?;
+
+    let shopping_list: ShoppingListResponse = create_shopping_list(
+        pool.into_inner(),
+        &auth.user.id,
+        &shopping_list_body.name,
+        &source,
+    )
+    .await?
+    .into();
+    let response_serialized = serde_json::to_string(&shopping_list)?;
+
+    return Ok(HttpResponse::Created()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "shopping_lists",
+    responses(
+        (status = 200, description = "Tick shopping list item off or back on", body = ShoppingItemResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{id}/items/{item_id}")]
+pub async fn shopping_lists_check_item(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<(i32, i32)>,
+    check_body: web::Json<CheckShoppingListItem>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let (shopping_list_id, item_id) = path.into_inner();
+
+    let item: ShoppingItemResponse = check_shopping_list_item(
+        pool.into_inner(),
+        &auth.user.id,
+        &shopping_list_id,
+        &item_id,
+        &check_body.checked,
+    )
+    .await?
+    .into();
+    let response_serialized = serde_json::to_string(&item)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "shopping_lists",
+    responses(
+        (status = 204, description = "Delete shopping list")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{id}")]
+pub async fn shopping_lists_delete(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<i32>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::WriteRecipes)?;
+    let shopping_list_id = path.into_inner();
+
+    delete_shopping_list(pool.into_inner(), &auth.user.id, &shopping_list_id).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+fn shopping_source(source: ShoppingListSource) -> Result<ShoppingSource, ServiceError> {
+    let plan_dates = match (source.from, source.to) {
+        (Some(from), Some(to)) => Some((from, to)),
+        (Some(from), None) => Some((from, shift_date(&from, 6, "from")?)),
+        (None, Some(to)) => Some((shift_date(&to, -6, "to")?, to)),
+        (None, None) => None,
+    };
+
+    return Ok(ShoppingSource {
+        recipes: source
+            .recipes
+            .unwrap_or_default()
+            .iter()
+            .map(|recipe| (recipe.recipe_id, recipe.portions))
+            .collect(),
+        plan_dates,
+    });
+}
+
+pub fn shopping_lists_config(cfg: &mut service_config::ServiceConfig) {
+    cfg.service(shopping_lists_preview);
+    cfg.service(shopping_lists_list);
+    cfg.service(shopping_lists_get);
+    cfg.service(shopping_lists_create);
+    cfg.service(shopping_lists_check_item);
+    cfg.service(shopping_lists_delete);
+}
@@ -0,0 +1,34 @@
+use actix_web::{get, http::header::ContentType, HttpResponse, Responder};
+use utoipa_actix_web::service_config;
+
+use crate::{
+    recipes_service::units::UNITS,
+    recipes_web::{errors, utils},
+};
+
+use super::responses::json::UnitResponse;
+
+#[utoipa::path(
+    tag = "units",
+    responses(
+        (status = 200, description = "List supported units", body = utils::ResponseBodyVec<Vec<UnitResponse>>)
+    )
+)]
+#[get("")]
+pub async fn units_list() -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    let units_vec: Vec<UnitResponse> = UNITS.iter().map(|unit| unit.into()).collect();
+
+    let response_body = utils::ResponseBodyVec {
+        result: units_vec,
+        pagination: None,
+    };
+    let response_serialized = serde_json::to_string(&response_body)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+pub fn units_config(cfg: &mut service_config::ServiceConfig) {
+    cfg.service(units_list);
+}
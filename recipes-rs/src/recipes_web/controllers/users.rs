@@ -0,0 +1,190 @@
+use actix_web::{
+    delete, get,
+    http::{header::ContentType, StatusCode},
+    post, put, web, HttpResponse, Responder,
+};
+use diesel_async::{pooled_connection::deadpool::Pool, AsyncPgConnection};
+use utoipa_actix_web::service_config;
+
+use crate::{
+    recipes_service::models::{api_key::Scope, user::Role},
+    recipes_service::users::{
+        create_session, create_user, delete_session, dummy_password_hash, get_user_by_username,
+        get_user_roles, grant_role, hash_password, revoke_role, verify_password,
+    },
+    recipes_web::{auth::AuthenticatedUser, errors},
+};
+
+use super::{
+    requests::users::Credentials,
+    responses::json::{SessionResponse, UserResponse},
+};
+
+#[utoipa::path(
+    tag = "users",
+    responses(
+        (status = 201, description = "Register user", body = UserResponse)
+    )
+)]
+#[post("")]
+pub async fn users_register(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    credentials: web::Json<Credentials>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    let Credentials { username, password } = credentials.into_inner();
+
+    let password_hash = web::block(move || hash_password(&password)).await??;
+    let user: UserResponse = create_user(pool.into_inner(), &username, &password_hash)
+        .await?
+        .into();
+    let response_serialized = serde_json::to_string(&user)?;
+
+    return Ok(HttpResponse::Created()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "users",
+    responses(
+        (status = 200, description = "Log in and get a bearer token", body = SessionResponse)
+    )
+)]
+#[post("/login")]
+pub async fn users_login(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    credentials: web::Json<Credentials>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    let Credentials { username, password } = credentials.into_inner();
+    let pool = pool.into_inner();
+
+    let user = get_user_by_username(pool.clone(), &username).await?;
+    // unknown users are verified too, so timing does not tell which usernames exist
+    let password_hash = user.as_ref().map(|user| user.password_hash.clone());
+    let password_valid = web::block(move || match &password_hash {
+        Some(password_hash) => verify_password(&password, password_hash),
+        None => verify_password(&password, dummy_password_hash()),
+    })
+    .await?;
+    let Some(user) = user.filter(|_| password_valid) else {
+        return Err(errors::ApiErrors::Unauthorized {
+            code: "unauthorized",
+            detail: "Invalid username or password".to_string(),
+        });
+    };
+
+    let (token, session) = create_session(pool.clone(), &user.id).await?;
+    let roles = get_user_roles(pool, &user.id).await?;
+    let response = SessionResponse {
+        token,
+        expires_at: session.expires_at,
+        user: (user, roles).into(),
+    };
+    let response_serialized = serde_json::to_string(&response)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "users",
+    responses(
+        (status = 204, description = "Log out, invalidates the bearer token")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/logout")]
+pub async fn users_logout(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    delete_session(pool.into_inner(), &auth.token).await?;
+
+    return Ok(HttpResponse::NoContent()
+        .content_type(ContentType::json())
+        .status(StatusCode::NO_CONTENT)
+        .finish());
+}
+
+#[utoipa::path(
+    tag = "users",
+    responses(
+        (status = 200, description = "Get the signed-in user", body = UserResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[get("/me")]
+pub async fn users_me(
+    auth: AuthenticatedUser,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    let user: UserResponse = (auth.user, auth.roles).into();
+    let response_serialized = serde_json::to_string(&user)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "users",
+    responses(
+        (status = 200, description = "Grant the role to the user, admins only", body = UserResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[put("/{username}/roles/{role}")]
+pub async fn users_grant_role(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<(String, Role)>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::Admin)?;
+    auth.require_role(&[Role::Admin])?;
+    let (username, role) = path.into_inner();
+
+    let user: UserResponse = grant_role(pool.into_inner(), &username, &role)
+        .await?
+        .into();
+    let response_serialized = serde_json::to_string(&user)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+#[utoipa::path(
+    tag = "users",
+    responses(
+        (status = 200, description = "Revoke the role from the user, admins only", body = UserResponse)
+    ),
+    security(("bearer_auth" = []))
+)]
+#[delete("/{username}/roles/{role}")]
+pub async fn users_revoke_role(
+    pool: web::Data<Pool<AsyncPgConnection>>,
+    auth: AuthenticatedUser,
+    path: web::Path<(String, Role)>,
+) -> actix_web::Result<impl Responder, errors::ApiErrors> {
+    auth.require_scope(Scope::Admin)?;
+    auth.require_role(&[Role::Admin])?;
+    let (username, role) = path.into_inner();
+
+    let user: UserResponse = revoke_role(pool.into_inner(), &username, &role)
+        .await?
+        .into();
+    let response_serialized = serde_json::to_string(&user)?;
+
+    return Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .body(response_serialized));
+}
+
+pub fn users_config(cfg: &mut service_config::ServiceConfig) {
+    cfg.service(users_register);
+    cfg.service(users_login);
+    cfg.service(users_logout);
+    cfg.service(users_me);
+    cfg.service(users_grant_role);
+    cfg.service(users_revoke_role);
+}
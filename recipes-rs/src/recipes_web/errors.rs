@@ -1,11 +1,18 @@
 use crate::recipes_service::errors::ServiceError;
 use actix_web::{
-    error,
-    http::{header::ContentType, StatusCode},
-    HttpResponse,
+    error::{self, JsonPayloadError, PathError, QueryPayloadError},
+    http::{
+        header::{CONTENT_TYPE, WWW_AUTHENTICATE},
+        StatusCode,
+    },
+    HttpRequest, HttpResponse,
 };
 use derive_more::derive::{Display, Error};
-use diesel::result::Error as DieselError;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use log::error;
+use serde::Serialize;
+
+const PROBLEM_JSON: &str = "application/problem+json";
 
 #[derive(Debug, Display, Error)]
 pub enum ApiErrors {
@@ -13,22 +20,131 @@ pub enum ApiErrors {
     InternalError,
     #[display("Not found")]
     NotFound,
-    #[display("Bad request")]
-    BadRequest,
+    #[display("{detail}")]
+    BadRequest {
+        code: &'static str,
+        detail: String,
+        field: Option<String>,
+    },
+    #[display("{detail}")]
+    Unauthorized { code: &'static str, detail: String },
+    #[display("{detail}")]
+    Forbidden { code: &'static str, detail: String },
+    #[display("{detail}")]
+    NotAcceptable { code: &'static str, detail: String },
+    #[display("{detail}")]
+    PreconditionFailed { code: &'static str, detail: String },
+    #[display("{detail}")]
+    UnsupportedMediaType { code: &'static str, detail: String },
+    #[display("{detail}")]
+    Conflict {
+        code: &'static str,
+        detail: String,
+        field: Option<String>,
+    },
+    #[display("{detail}")]
+    UnprocessableEntity {
+        code: &'static str,
+        detail: String,
+        field: Option<String>,
+    },
+    #[display("The request has invalid fields")]
+    InvalidFields {
+        #[error(not(source))]
+        errors: Vec<FieldError>,
+    },
+}
+
+/// field of the request breaking a validation rule
+#[derive(Serialize, Clone, Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub detail: String,
+}
+
+/// RFC 7807 problem details body
+#[derive(Serialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    /// stable machine-readable error code
+    pub code: String,
+    /// request or entity field the error relates to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// all invalid fields of the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
+}
+
+impl ApiErrors {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiErrors::InternalError => "internal_error",
+            ApiErrors::NotFound => "not_found",
+            ApiErrors::InvalidFields { .. } => "invalid_fields",
+            ApiErrors::Unauthorized { code, .. }
+            | ApiErrors::Forbidden { code, .. }
+            | ApiErrors::NotAcceptable { code, .. }
+            | ApiErrors::PreconditionFailed { code, .. }
+            | ApiErrors::UnsupportedMediaType { code, .. }
+            | ApiErrors::BadRequest { code, .. }
+            | ApiErrors::Conflict { code, .. }
+            | ApiErrors::UnprocessableEntity { code, .. } => code,
+        }
+    }
+
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            ApiErrors::BadRequest { field, .. }
+            | ApiErrors::Conflict { field, .. }
+            | ApiErrors::UnprocessableEntity { field, .. } => field.as_deref(),
+            _ => None,
+        }
+    }
 }
 
 impl error::ResponseError for ApiErrors {
     fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
-        HttpResponse::build(self.status_code())
-            .insert_header(ContentType::html())
-            .body(self.to_string())
+        let status = self.status_code();
+        let problem = ProblemDetails {
+            type_: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: self.to_string(),
+            code: self.code().to_string(),
+            field: self.field().map(|f| f.to_string()),
+            errors: match self {
+                ApiErrors::InvalidFields { errors } => Some(errors.clone()),
+                _ => None,
+            },
+        };
+
+        let mut response = HttpResponse::build(status);
+        response.insert_header((CONTENT_TYPE, PROBLEM_JSON));
+        if status == StatusCode::UNAUTHORIZED {
+            response.insert_header((WWW_AUTHENTICATE, "Bearer"));
+        }
+        response.json(problem)
     }
 
     fn status_code(&self) -> actix_web::http::StatusCode {
         match *self {
             ApiErrors::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
             ApiErrors::NotFound => StatusCode::NOT_FOUND,
-            ApiErrors::BadRequest => StatusCode::BAD_REQUEST,
+            ApiErrors::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            ApiErrors::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            ApiErrors::Forbidden { .. } => StatusCode::FORBIDDEN,
+            ApiErrors::NotAcceptable { .. } => StatusCode::NOT_ACCEPTABLE,
+            ApiErrors::PreconditionFailed { .. } => StatusCode::PRECONDITION_FAILED,
+            ApiErrors::UnsupportedMediaType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ApiErrors::Conflict { .. } => StatusCode::CONFLICT,
+            ApiErrors::UnprocessableEntity { .. } | ApiErrors::InvalidFields { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
         }
     }
 }
@@ -37,6 +153,33 @@ impl From<ServiceError> for ApiErrors {
     fn from(service_error: ServiceError) -> Self {
         match service_error {
             ServiceError::DbDiesel(e) => e.into(),
+            ServiceError::InUse { resource, used_by } => Self::Conflict {
+                code: "in_use",
+                detail: format!("The {resource} is still used by {used_by}"),
+                field: None,
+            },
+            ServiceError::Conflict { code, detail } => Self::Conflict {
+                code,
+                detail,
+                field: None,
+            },
+            ServiceError::InvalidValue { field, detail } => Self::UnprocessableEntity {
+                code: "invalid_value",
+                detail,
+                field: Some(field.to_string()),
+            },
+            ServiceError::Unauthorized { detail } => Self::Unauthorized {
+                code: "unauthorized",
+                detail: detail.to_string(),
+            },
+            ServiceError::Forbidden { detail } => Self::Forbidden {
+                code: "forbidden",
+                detail: detail.to_string(),
+            },
+            ServiceError::PreconditionFailed { resource } => Self::PreconditionFailed {
+                code: "precondition_failed",
+                detail: format!("The {resource} was changed since it was read"),
+            },
             _ => Self::InternalError,
         }
     }
@@ -46,12 +189,93 @@ impl From<DieselError> for ApiErrors {
     fn from(diesel_error: DieselError) -> Self {
         match diesel_error {
             DieselError::NotFound => Self::NotFound,
-            DieselError::DatabaseError(_kind, _error_info) => Self::BadRequest,
+            DieselError::DatabaseError(kind, error_info) => {
+                let key = error_info.details().and_then(parse_key_details);
+                let field = key
+                    .as_ref()
+                    .map(|(field, _)| field.clone())
+                    .or(error_info.column_name().map(|c| c.to_string()));
+                match kind {
+                    DatabaseErrorKind::UniqueViolation => Self::Conflict {
+                        code: "already_exists",
+                        detail: match &key {
+                            Some((field, value)) => format!("{field} '{value}' already exists"),
+                            None => {
+                                log_database_error(&kind, error_info.message());
+                                "The value already exists".to_string()
+                            }
+                        },
+                        field,
+                    },
+                    DatabaseErrorKind::ForeignKeyViolation => match &key {
+                        // deleting/updating a row other rows still point to
+                        Some((field, value)) if is_still_referenced(error_info.details()) => {
+                            Self::Conflict {
+                                code: "still_referenced",
+                                detail: format!("{field} '{value}' is still in use"),
+                                field: Some(field.clone()),
+                            }
+                        }
+                        Some((field, value)) => Self::UnprocessableEntity {
+                            code: "missing_reference",
+                            detail: format!("{field} '{value}' does not exist"),
+                            field: Some(field.clone()),
+                        },
+                        None => {
+                            log_database_error(&kind, error_info.message());
+                            Self::UnprocessableEntity {
+                                code: "missing_reference",
+                                detail: "A referenced value does not exist".to_string(),
+                                field,
+                            }
+                        }
+                    },
+                    DatabaseErrorKind::NotNullViolation => {
+                        log_database_error(&kind, error_info.message());
+                        Self::UnprocessableEntity {
+                            code: "missing_value",
+                            detail: "A required value is missing".to_string(),
+                            field,
+                        }
+                    }
+                    DatabaseErrorKind::CheckViolation => {
+                        log_database_error(&kind, error_info.message());
+                        Self::UnprocessableEntity {
+                            code: "invalid_value",
+                            detail: "A value is out of range".to_string(),
+                            field,
+                        }
+                    }
+                    // the transaction lost against a concurrent one
+                    DatabaseErrorKind::SerializationFailure => Self::Conflict {
+                        code: "concurrent_change",
+                        detail: "The request conflicted with a concurrent change, retry it"
+                            .to_string(),
+                        field: None,
+                    },
+                    // a server problem
+                    _ => {
+                        log_database_error(&kind, error_info.message());
+                        Self::InternalError
+                    }
+                }
+            }
+            DieselError::QueryBuilderError(e) => Self::BadRequest {
+                code: "invalid_request",
+                detail: e.to_string(),
+                field: None,
+            },
             _ => Self::InternalError,
         }
     }
 }
 
+/// Database messages name constraints, tables and columns. They are logged, clients only get
+/// fixed details.
+fn log_database_error(kind: &DatabaseErrorKind, message: &str) {
+    error!(kind:? = kind, message; "Database error");
+}
+
 impl From<serde_json::Error> for ApiErrors {
     fn from(_serde_error: serde_json::Error) -> Self {
         Self::InternalError
@@ -69,3 +293,50 @@ impl From<actix_web::error::BlockingError> for ApiErrors {
         Self::InternalError
     }
 }
+
+impl From<actix_web::error::UrlGenerationError> for ApiErrors {
+    fn from(_url_error: actix_web::error::UrlGenerationError) -> Self {
+        Self::InternalError
+    }
+}
+
+// extractor error handlers -- keep problem+json bodies for malformed requests
+
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    return ApiErrors::BadRequest {
+        code: "invalid_body",
+        detail: err.to_string(),
+        field: None,
+    }
+    .into();
+}
+
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    return ApiErrors::BadRequest {
+        code: "invalid_query",
+        detail: err.to_string(),
+        field: None,
+    }
+    .into();
+}
+
+pub fn path_error_handler(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    return ApiErrors::BadRequest {
+        code: "invalid_path",
+        detail: err.to_string(),
+        field: None,
+    }
+    .into();
+}
+
+/// parse PostgreSQL `Key (column)=(value) ...` constraint details
+fn parse_key_details(details: &str) -> Option<(String, String)> {
+    let rest = details.strip_prefix("Key (")?;
+    let (field, rest) = rest.split_once(")=(")?;
+    let (value, _) = rest.rsplit_once(')')?;
+    return Some((field.to_string(), value.to_string()));
+}
+
+fn is_still_referenced(details: Option<&str>) -> bool {
+    return details.is_some_and(|d| d.contains("is still referenced"));
+}
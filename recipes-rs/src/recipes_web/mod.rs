@@ -1,3 +1,5 @@
+pub mod auth;
 pub mod controllers;
 pub mod errors;
 pub mod utils;
+pub mod validation;
@@ -1,7 +1,388 @@
-use serde::Serialize;
+use actix_web::{
+    http::header::{self, Accept, ETag, EntityTag, IfMatch, IfNoneMatch, Quality},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bigdecimal::BigDecimal;
+use mime::Mime;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
 use utoipa::ToSchema;
 
+use crate::recipes_service::{quantity::parse_quantity, utils::Page};
+use crate::recipes_web::errors::ApiErrors;
+
+pub const DEFAULT_PAGE_LIMIT: i64 = 20;
+pub const MAX_PAGE_LIMIT: i64 = 100;
+/// pages past it are empty in any listing, keeps offsets and page links from overflowing
+pub const MAX_PAGE: i64 = 1_000_000;
+
 #[derive(Serialize, ToSchema)]
 pub struct ResponseBodyVec<T> {
     pub result: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<Pagination>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Pagination {
+    pub page: i64,
+    pub limit: i64,
+    /// number of all items matching the request
+    pub total: i64,
+    /// link to the next page, missing on the last page
+    pub next: Option<String>,
+    /// link to the previous page, missing on the first page
+    pub prev: Option<String>,
+}
+
+/// page & limit query parameters shared by listings
+#[derive(Deserialize)]
+pub struct PageQuery {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+impl From<&PageQuery> for Page {
+    fn from(query: &PageQuery) -> Self {
+        Self {
+            page: query.page.unwrap_or(1).clamp(1, MAX_PAGE),
+            limit: query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_LIMIT)
+                .clamp(1, MAX_PAGE_LIMIT),
+        }
+    }
+}
+
+impl Pagination {
+    pub fn new(req: &HttpRequest, page: &Page, total: i64) -> Self {
+        let has_next = page.offset().saturating_add(page.limit) < total;
+        let has_prev = page.page > 1;
+        return Self {
+            page: page.page,
+            limit: page.limit,
+            total,
+            next: has_next.then(|| page_link(req, page.page + 1)),
+            prev: has_prev.then(|| page_link(req, page.page - 1)),
+        };
+    }
+}
+
+/// link to the same listing (keeping filters) with a different page
+fn page_link(req: &HttpRequest, page: i64) -> String {
+    let mut query: Vec<(String, String)> =
+        serde_urlencoded::from_str(req.query_string()).unwrap_or_default();
+    query.retain(|(key, _)| key != "page");
+    query.push(("page".to_string(), page.to_string()));
+
+    return format!(
+        "{}?{}",
+        req.path(),
+        serde_urlencoded::to_string(query).unwrap_or_default()
+    );
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QuantityInput {
+    Number(f64),
+    Text(String),
+}
+
+/// deserialize ingredient quantity given as JSON number or decimal/fraction string
+pub fn deserialize_quantity<'de, D>(deserializer: D) -> Result<BigDecimal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let text = match QuantityInput::deserialize(deserializer)? {
+        QuantityInput::Number(number) => number.to_string(),
+        QuantityInput::Text(text) => text,
+    };
+
+    return parse_quantity(&text)
+        .ok_or_else(|| de::Error::custom(format!("invalid quantity '{text}'")));
+}
+
+/// entity tag of a versioned resource
+pub fn version_etag(version: i32) -> EntityTag {
+    return EntityTag::new_strong(version.to_string());
+}
+
+/// entity tag of a resource without version, from a hash of its representation
+pub fn content_etag(content: &[u8]) -> EntityTag {
+    return EntityTag::new_strong(URL_SAFE_NO_PAD.encode(&Sha256::digest(content)[..16]));
+}
+
+/// entity tag of another representation of the same resource version, e.g. JSON-LD
+pub fn variant_etag(etag: &EntityTag, variant: &str) -> EntityTag {
+    return EntityTag::new(etag.weak, format!("{}-{variant}", etag.tag()));
+}
+
+/// Offered media type the request prefers by its `Accept` header, the first offered one when
+/// the header is missing. An offered type gets the quality of the most specific range matching
+/// it, so `q=0` refuses it even when `*/*` is accepted. 406 when no offered type is acceptable.
+pub fn preferred_media_type<'a>(
+    req: &HttpRequest,
+    offered: &[&'a str],
+) -> Result<&'a str, ApiErrors> {
+    let Some(accept) = req.get_header::<Accept>() else {
+        return Ok(offered[0]);
+    };
+    if accept.is_empty() {
+        return Ok(offered[0]);
+    }
+
+    let ranked = accept.ranked();
+    let preferred = offered
+        .iter()
+        .enumerate()
+        .filter_map(|(index, offered)| {
+            let (type_, _) = offered.split_once('/').unwrap_or((offered, ""));
+            // exact ranges before `type/*` before `*/*`
+            let specificity = |range: &Mime| {
+                return match (range.type_(), range.subtype()) {
+                    (mime::STAR, _) => Some(0),
+                    (range_type, mime::STAR) if range_type == type_ => Some(1),
+                    _ if range.essence_str() == *offered => Some(2),
+                    _ => None,
+                };
+            };
+            let (_, range) = accept
+                .iter()
+                .filter_map(|range| {
+                    specificity(&range.item).map(|specificity| (specificity, range))
+                })
+                .max_by_key(|(specificity, _)| *specificity)?;
+            if range.quality == Quality::ZERO {
+                return None;
+            }
+            // equally good types keep the order of `Accept`, then the offered order
+            let rank = ranked
+                .iter()
+                .position(|ranked| *ranked == range.item)
+                .unwrap_or(ranked.len());
+            return Some((*offered, range.quality, Reverse(rank), Reverse(index)));
+        })
+        .max_by_key(|(_, quality, rank, index)| (*quality, *rank, *index));
+
+    return match preferred {
+        Some((media_type, ..)) => Ok(media_type),
+        None => Err(ApiErrors::NotAcceptable {
+            code: "not_acceptable",
+            detail: format!("Available media types are {}", offered.join(", ")),
+        }),
+    };
+}
+
+/// true when `If-None-Match` of the request matches the current entity tag
+pub fn is_not_modified(req: &HttpRequest, etag: &EntityTag) -> bool {
+    return match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(etags)) => etags.iter().any(|item| item.weak_eq(etag)),
+        None => false,
+    };
+}
+
+/// 304 response to a GET whose `If-None-Match` matched
+pub fn not_modified(etag: EntityTag) -> HttpResponse {
+    return HttpResponse::NotModified()
+        .insert_header(ETag(etag))
+        .finish();
+}
+
+/// like `not_modified` for a representation chosen by `Accept`, a 304 repeats the `Vary` of
+/// the 200 so caches keep the representations apart
+pub fn not_modified_negotiated(etag: EntityTag) -> HttpResponse {
+    return HttpResponse::NotModified()
+        .insert_header(ETag(etag))
+        .insert_header((header::VARY, "Accept"))
+        .finish();
+}
+
+/// Versions accepted by `If-Match` of the request, `None` when any version is. A malformed
+/// header accepts none, ignoring it could overwrite a change.
+pub fn if_match_versions(req: &HttpRequest) -> Option<Vec<i32>> {
+    return match if_match(req) {
+        Some(IfMatch::Items(etags)) => Some(
+            etags
+                .iter()
+                .filter(|etag| !etag.weak)
+                .filter_map(|etag| etag.tag().parse().ok())
+                .collect(),
+        ),
+        Some(IfMatch::Any) | None => None,
+    };
+}
+
+/// `If-Match` of the request, a malformed header as an empty list of entity tags
+fn if_match(req: &HttpRequest) -> Option<IfMatch> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return None;
+    }
+
+    return Some(
+        req.get_header::<IfMatch>()
+            .unwrap_or(IfMatch::Items(vec![])),
+    );
+}
+
+/// Deserialize a field that may be `null` to `Some(None)`. With `#[serde(default)]` a missing
+/// field stays `None`, so patches can tell removing a value from leaving it as it is.
+pub fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    return Ok(Some(Option::deserialize(deserializer)?));
+}
+
+/// value of a patched field that cannot be removed, fails with 422 on `null`
+pub fn not_null<T>(value: Option<Option<T>>, field: &str) -> Result<Option<T>, ApiErrors> {
+    return match value {
+        Some(None) => Err(ApiErrors::UnprocessableEntity {
+            code: "missing_value",
+            detail: format!("{field} cannot be removed"),
+            field: Some(field.to_string()),
+        }),
+        Some(Some(value)) => Ok(Some(value)),
+        None => Ok(None),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn request(name: header::HeaderName, value: &str) -> HttpRequest {
+        return TestRequest::default()
+            .insert_header((name, value))
+            .to_http_request();
+    }
+
+    fn if_match_request(value: &str) -> HttpRequest {
+        return request(header::IF_MATCH, value);
+    }
+
+    fn if_none_match_request(value: &str) -> HttpRequest {
+        return request(header::IF_NONE_MATCH, value);
+    }
+
+    fn preferred(accept: &str) -> Result<&'static str, ApiErrors> {
+        return preferred_media_type(
+            &request(header::ACCEPT, accept),
+            &["application/json", "application/ld+json"],
+        );
+    }
+
+    #[test]
+    fn reads_if_match_versions() {
+        assert_eq!(
+            if_match_versions(&TestRequest::default().to_http_request()),
+            None
+        );
+        assert_eq!(if_match_versions(&if_match_request("*")), None);
+        assert_eq!(if_match_versions(&if_match_request("\"3\"")), Some(vec![3]));
+        assert_eq!(
+            if_match_versions(&if_match_request("\"3\", \"5\"")),
+            Some(vec![3, 5])
+        );
+        // weak tags never match strongly, other representations and other tags are no versions
+        assert_eq!(
+            if_match_versions(&if_match_request("W/\"3\", \"5\"")),
+            Some(vec![5])
+        );
+        assert_eq!(
+            if_match_versions(&if_match_request("\"3-ld\"")),
+            Some(vec![])
+        );
+        assert_eq!(
+            if_match_versions(&if_match_request("\"abc\"")),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn malformed_if_match_accepts_no_version() {
+        assert_eq!(if_match_versions(&if_match_request("3")), Some(vec![]));
+        assert_eq!(if_match_versions(&if_match_request("\"3")), Some(vec![]));
+    }
+
+    #[test]
+    fn checks_if_none_match_weakly() {
+        let etag = version_etag(3);
+        assert!(!is_not_modified(
+            &TestRequest::default().to_http_request(),
+            &etag
+        ));
+        assert!(is_not_modified(&if_none_match_request("*"), &etag));
+        assert!(is_not_modified(&if_none_match_request("\"3\""), &etag));
+        assert!(is_not_modified(&if_none_match_request("W/\"3\""), &etag));
+        assert!(is_not_modified(
+            &if_none_match_request("\"1\", \"3\""),
+            &etag
+        ));
+        assert!(!is_not_modified(&if_none_match_request("\"4\""), &etag));
+        // a malformed header only costs a full response
+        assert!(!is_not_modified(&if_none_match_request("3"), &etag));
+    }
+
+    #[test]
+    fn tells_representations_apart() {
+        let etag = version_etag(3);
+        let ld_etag = variant_etag(&etag, "ld");
+        assert_eq!(ld_etag.to_string(), "\"3-ld\"");
+        assert!(is_not_modified(
+            &if_none_match_request("\"3-ld\""),
+            &ld_etag
+        ));
+        assert!(!is_not_modified(&if_none_match_request("\"3-ld\""), &etag));
+        assert!(!is_not_modified(&if_none_match_request("\"3\""), &ld_etag));
+        assert_eq!(
+            variant_etag(&EntityTag::new_weak("3".to_string()), "ld").to_string(),
+            "W/\"3-ld\""
+        );
+    }
+
+    #[test]
+    fn prefers_media_types_by_accept() {
+        assert_eq!(
+            preferred_media_type(
+                &TestRequest::default().to_http_request(),
+                &["application/json", "application/ld+json"]
+            )
+            .unwrap(),
+            "application/json"
+        );
+        assert_eq!(preferred("*/*").unwrap(), "application/json");
+        assert_eq!(
+            preferred("application/ld+json").unwrap(),
+            "application/ld+json"
+        );
+        assert_eq!(
+            preferred("application/ld+json, application/json").unwrap(),
+            "application/ld+json"
+        );
+        assert_eq!(
+            preferred("application/json;q=0.5, application/ld+json").unwrap(),
+            "application/ld+json"
+        );
+        assert_eq!(
+            preferred("application/*, application/ld+json;q=0.9").unwrap(),
+            "application/json"
+        );
+    }
+
+    #[test]
+    fn refuses_media_types_with_zero_quality() {
+        assert_eq!(
+            preferred("application/json;q=0, */*").unwrap(),
+            "application/ld+json"
+        );
+        assert!(preferred("application/json;q=0, application/ld+json;q=0").is_err());
+        assert!(preferred("*/*;q=0").is_err());
+        assert!(preferred("text/html").is_err());
+    }
 }
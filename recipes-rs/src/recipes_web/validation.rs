@@ -0,0 +1,83 @@
+use std::fmt::Display;
+
+use crate::recipes_web::errors::{ApiErrors, FieldError};
+
+/// Rules of a request body or query, checked before the service is called. Limits should match
+/// the `#[schema(...)]` constraints of the fields.
+pub trait Validate {
+    /// add errors of the fields breaking the rules
+    fn check(&self, errors: &mut FieldErrors);
+
+    /// fail with 422 listing all invalid fields
+    fn validate(&self) -> Result<(), ApiErrors> {
+        let mut errors = FieldErrors::default();
+        self.check(&mut errors);
+        if errors.errors.is_empty() {
+            return Ok(());
+        }
+        return Err(ApiErrors::InvalidFields {
+            errors: errors.errors,
+        });
+    }
+}
+
+/// errors of invalid fields, fields of nested values are prefixed with their path like
+/// `ingredients[1].quantity`
+#[derive(Default)]
+pub struct FieldErrors {
+    errors: Vec<FieldError>,
+    prefix: String,
+}
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &str, detail: String) {
+        self.errors.push(FieldError {
+            field: format!("{}{field}", self.prefix),
+            detail,
+        });
+    }
+
+    /// fail the field unless the condition holds
+    pub fn require(&mut self, valid: bool, field: &str, detail: &str) {
+        if !valid {
+            self.add(field, detail.to_string());
+        }
+    }
+
+    /// text of `min` to `max` characters, surrounding whitespace is not counted
+    pub fn length(&mut self, field: &str, value: &str, min: usize, max: usize) {
+        let length = value.trim().chars().count();
+        if length < min || length > max {
+            self.add(field, format!("must have {min} to {max} characters"));
+        }
+    }
+
+    pub fn min<T: PartialOrd + Display>(&mut self, field: &str, value: T, min: T) {
+        if value < min {
+            self.add(field, format!("must be at least {min}"));
+        }
+    }
+
+    pub fn range<T: PartialOrd + Display>(&mut self, field: &str, value: T, min: T, max: T) {
+        if value < min || value > max {
+            self.add(field, format!("must be between {min} and {max}"));
+        }
+    }
+
+    /// check every item with its rules
+    pub fn each<T: Validate>(&mut self, field: &str, values: &[T]) {
+        let outer = std::mem::take(&mut self.prefix);
+        for (index, value) in values.iter().enumerate() {
+            self.prefix = format!("{outer}{field}[{index}].");
+            value.check(self);
+        }
+        self.prefix = outer;
+    }
+
+    /// check the length of every text item
+    pub fn each_length(&mut self, field: &str, values: &[String], min: usize, max: usize) {
+        for (index, value) in values.iter().enumerate() {
+            self.length(&format!("{field}[{index}]"), value, min, max);
+        }
+    }
+}